@@ -13,6 +13,7 @@ import { Ownable2StepUpgradeable } from "@openzeppelin/contracts-upgradeable/acc
 import { PausableUpgradeable } from "@openzeppelin/contracts-upgradeable/utils/PausableUpgradeable.sol";
 import { UUPSUpgradeable } from "@openzeppelin/contracts-upgradeable/proxy/utils/UUPSUpgradeable.sol";
 import { IERC20 } from "@openzeppelin/contracts/token/ERC20/IERC20.sol";
+import { IERC20Permit } from "@openzeppelin/contracts/token/ERC20/extensions/IERC20Permit.sol";
 import { SafeERC20 } from "@openzeppelin/contracts/token/ERC20/utils/SafeERC20.sol";
 
 using SafeERC20 for IERC20;
@@ -59,6 +60,17 @@ contract Shielder is
     /// A special value of `tokenAddress` in circuits used to represent the native token.
     address private constant NATIVE_TOKEN_NOTE_ADDRESS = address(0);
 
+    // -- Structs --
+
+    /// EIP-2612 permit signed by the caller, granting this contract an allowance for the
+    /// deposited amount.
+    struct PermitSignature {
+        uint256 deadline;
+        uint8 v;
+        bytes32 r;
+        bytes32 s;
+    }
+
     // -- Events --
     event NewAccount(
         bytes3 contractVersion,
@@ -271,7 +283,7 @@ contract Shielder is
         uint256 macCommitment,
         bytes calldata proof,
         bytes calldata memo
-    ) external whenNotPaused {
+    ) public whenNotPaused {
         IERC20 token = IERC20(tokenAddress);
         if (
             amount > MAX_CONTRACT_BALANCE ||
@@ -312,6 +324,44 @@ contract Shielder is
         );
     }
 
+    /*
+     * Same as `newAccountERC20`, but the allowance for `amount` is granted with an EIP-2612
+     * permit in the same transaction, so no prior `approve` is needed.
+     */
+    function newAccountERC20WithPermit(
+        bytes3 expectedContractVersion,
+        address tokenAddress,
+        uint256 amount,
+        uint256 newNote,
+        uint256 prenullifier,
+        uint256 symKeyEncryptionC1X,
+        uint256 symKeyEncryptionC1Y,
+        uint256 symKeyEncryptionC2X,
+        uint256 symKeyEncryptionC2Y,
+        uint256 macSalt,
+        uint256 macCommitment,
+        bytes calldata proof,
+        bytes calldata memo,
+        PermitSignature calldata permit
+    ) external {
+        _permitERC20(tokenAddress, amount, permit);
+        newAccountERC20(
+            expectedContractVersion,
+            tokenAddress,
+            amount,
+            newNote,
+            prenullifier,
+            symKeyEncryptionC1X,
+            symKeyEncryptionC1Y,
+            symKeyEncryptionC2X,
+            symKeyEncryptionC2Y,
+            macSalt,
+            macCommitment,
+            proof,
+            memo
+        );
+    }
+
     function _newAccount(
         bytes3 expectedContractVersion,
         address tokenAddress,
@@ -438,7 +488,7 @@ contract Shielder is
         uint256 macCommitment,
         bytes calldata proof,
         bytes calldata memo
-    ) external whenNotPaused {
+    ) public whenNotPaused {
         IERC20 token = IERC20(tokenAddress);
         if (
             amount > MAX_CONTRACT_BALANCE ||
@@ -475,6 +525,38 @@ contract Shielder is
         );
     }
 
+    /*
+     * Same as `depositERC20`, but the allowance for `amount` is granted with an EIP-2612 permit
+     * in the same transaction, so no prior `approve` is needed.
+     */
+    function depositERC20WithPermit(
+        bytes3 expectedContractVersion,
+        address tokenAddress,
+        uint256 amount,
+        uint256 oldNullifierHash,
+        uint256 newNote,
+        uint256 merkleRoot,
+        uint256 macSalt,
+        uint256 macCommitment,
+        bytes calldata proof,
+        bytes calldata memo,
+        PermitSignature calldata permit
+    ) external {
+        _permitERC20(tokenAddress, amount, permit);
+        depositERC20(
+            expectedContractVersion,
+            tokenAddress,
+            amount,
+            oldNullifierHash,
+            newNote,
+            merkleRoot,
+            macSalt,
+            macCommitment,
+            proof,
+            memo
+        );
+    }
+
     function _deposit(
         bytes3 expectedContractVersion,
         address tokenAddress,
@@ -735,6 +817,28 @@ contract Shielder is
         }
     }
 
+    /// Grant this contract an allowance of `amount` from the caller with an EIP-2612 permit.
+    ///
+    /// A failing permit is ignored: anyone can submit a permit seen in the mempool, so it may have
+    /// already been used, in which case the allowance is in place. Otherwise, the transfer fails.
+    function _permitERC20(
+        address tokenAddress,
+        uint256 amount,
+        PermitSignature calldata permit
+    ) private {
+        try
+            IERC20Permit(tokenAddress).permit(
+                msg.sender,
+                address(this),
+                amount,
+                permit.deadline,
+                permit.v,
+                permit.r,
+                permit.s
+            )
+        {} catch {}
+    }
+
     function addressToUInt256(address addr) public pure returns (uint256) {
         return uint256(uint160(addr));
     }
@@ -0,0 +1,88 @@
+use alloy_network::AnyNetwork;
+use alloy_primitives::BlockHash;
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockNumberOrTag, BlockTransactionsKind};
+use alloy_transport::BoxTransport;
+use log::{info, warn};
+use rusqlite::Connection;
+
+use crate::{db, error::Error};
+
+/// The last block with at least `confirmations` confirmations.
+pub async fn confirmed_head(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    confirmations: u64,
+) -> Result<u64, Error> {
+    Ok(provider
+        .get_block_number()
+        .await?
+        .saturating_sub(confirmations))
+}
+
+pub async fn canonical_block_hash(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    block_number: u64,
+) -> Result<Option<BlockHash>, Error> {
+    Ok(provider
+        .get_block_by_number(
+            BlockNumberOrTag::Number(block_number),
+            BlockTransactionsKind::Hashes,
+        )
+        .await?
+        .map(|block| block.header.hash))
+}
+
+/// Save the checkpoint in `table` together with the hash of the checkpointed block.
+pub fn save(
+    connection: &Connection,
+    table: &str,
+    block_number: u64,
+    block_hash: BlockHash,
+) -> Result<(), Error> {
+    let transaction = connection.unchecked_transaction()?;
+    db::insert_block_hash(&transaction, table, block_number, block_hash.as_slice())?;
+    db::update_checkpoint(&transaction, table, block_number)?;
+    transaction.commit()?;
+    Ok(())
+}
+
+/// Block from which indexing should be resumed.
+///
+/// If the checkpointed blocks are no longer canonical, everything indexed after the last
+/// canonical one is removed with `rollback` and the checkpoint is moved back to it. If none of the
+/// remembered blocks is canonical, indexing restarts from `from_block`.
+pub async fn resume_block(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    connection: &Connection,
+    table: &str,
+    from_block: u64,
+    rollback: impl Fn(&Connection, u64) -> Result<(), rusqlite::Error>,
+) -> Result<u64, Error> {
+    let checkpoint = db::query_checkpoint(connection, table)?;
+    let block_hashes = db::query_block_hashes(connection, table)?;
+    if block_hashes.is_empty() {
+        return Ok(from_block.max(checkpoint));
+    }
+
+    let mut last_canonical = None;
+    for (block_number, block_hash) in &block_hashes {
+        let canonical = canonical_block_hash(provider, *block_number).await?;
+        if canonical.is_some_and(|canonical| canonical.as_slice() == block_hash.as_slice()) {
+            last_canonical = Some(*block_number);
+            break;
+        }
+    }
+    if last_canonical == Some(block_hashes[0].0) {
+        return Ok(from_block.max(checkpoint));
+    }
+
+    let rewind_to = last_canonical.unwrap_or(from_block.saturating_sub(1));
+    warn!("Chain reorganization detected, rolling back to block {rewind_to}");
+    let transaction = connection.unchecked_transaction()?;
+    rollback(&transaction, rewind_to)?;
+    db::rewind_checkpoint(&transaction, table, rewind_to)?;
+    transaction.commit()?;
+    info!("Rolled back data indexed after block {rewind_to}");
+
+    Ok(from_block.max(rewind_to + 1))
+}
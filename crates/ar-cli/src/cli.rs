@@ -47,6 +47,12 @@ pub enum Command {
         #[clap(long, default_value = "10000")]
         batch_size: usize,
 
+        /// After indexing the confirmed blocks, keep indexing new events as soon as they are
+        /// emitted (over a subscription for WebSocket and IPC RPC URLs). Events from blocks that
+        /// are reorged out are removed.
+        #[clap(long)]
+        follow: bool,
+
         #[clap(flatten)]
         db: Db,
     },
@@ -56,6 +62,13 @@ pub enum Command {
         #[clap(flatten)]
         common: ChainConfig,
 
+        #[clap(long, default_value = "10000")]
+        batch_size: usize,
+
+        /// How many transactions are fetched at the same time.
+        #[clap(long, default_value = "16")]
+        concurrency: usize,
+
         #[clap(flatten)]
         db: Db,
 
@@ -98,6 +111,11 @@ pub struct ChainConfig {
 
     #[arg(long, default_value = "0")]
     pub from_block: u64,
+
+    /// Only blocks with at least this many confirmations are indexed. Data from blocks that are
+    /// reorged out later is rolled back and indexed again.
+    #[arg(long, default_value = "12")]
+    pub confirmations: u64,
 }
 fn parse_byte_array<const N: usize>(input: &str) -> Result<[u8; N], &'static str> {
     let sanitized_input = input.strip_prefix("0x").unwrap_or(input);
@@ -1,49 +1,75 @@
 use std::{
-    cmp::max,
+    cmp::min,
+    collections::BTreeMap,
     fs::File,
     io::{self, Read},
     path::PathBuf,
 };
 
 use alloy_network::AnyNetwork;
-use alloy_primitives::{Address, U256};
-use alloy_provider::{Provider, ProviderBuilder};
-use alloy_rpc_types::{BlockNumberOrTag, BlockTransactionsKind, TransactionTrait};
-use alloy_sol_types::SolCall;
-use log::{debug, info, trace};
+use alloy_primitives::{Address, TxHash, U256};
+use alloy_provider::{ext::DebugApi, Provider};
+use alloy_rpc_types::{
+    trace::geth::{
+        CallFrame, GethDebugBuiltInTracerType, GethDebugTracerType, GethDebugTracingOptions,
+        GethTrace,
+    },
+    Filter, TransactionTrait,
+};
+use alloy_sol_types::{SolCall, SolEvent};
+use alloy_transport::BoxTransport;
+use futures::{StreamExt, TryStreamExt};
+use log::{debug, info, trace, warn};
 use rusqlite::Connection;
 use shielder_circuits::{grumpkin, GrumpkinPointAffine};
-use shielder_contract::ShielderContract::{newAccountERC20Call, newAccountNativeCall};
+use shielder_contract::{
+    providers::create_simple_provider,
+    ShielderContract::{newAccountERC20Call, newAccountNativeCall, NewAccount},
+};
 use type_conversions::u256_to_field;
 
 use crate::{
+    checkpoint,
     db::{self, ViewingKey},
     error::Error,
 };
 
 const CHECKPOINT_TABLE_NAME: &str = "last_keys_block";
 
+/// Viewing key encrypted for the AR, as passed to `newAccountNative` or `newAccountERC20`.
+struct EncryptedViewingKey {
+    c1x: U256,
+    c1y: U256,
+    c2x: U256,
+    c2y: U256,
+}
+
 /// Goes back in transaction history and collects all viewing keys
-/// - look for new_account txs
-/// - read c1,c2
+/// - look for `NewAccount` events, in batches of `batch_size` blocks
+/// - fetch their transactions (`concurrency` at a time) and read c1,c2 from the new account call
+///   (if the Shielder contract was called indirectly, e.g. through a multicall, the call is found
+///   with `debug_traceTransaction`)
 /// - decrypt message  => k (viewing key)
 /// - record k
+/// - only blocks with at least `confirmations` confirmations are read
+/// - keys from blocks that have been reorged out since the last run are removed first
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     rpc_url: &str,
     shielder_address: &Address,
     private_key_file: &PathBuf,
     from_block: u64,
+    confirmations: u64,
+    batch_size: usize,
+    concurrency: usize,
     db_path: &PathBuf,
     redact_sensitive_data: bool,
 ) -> Result<(), Error> {
     let connection = db::init(db_path)?;
 
-    let provider = ProviderBuilder::new()
-        .network::<AnyNetwork>()
-        .on_builtin(rpc_url)
-        .await?;
+    let provider = create_simple_provider(rpc_url).await?;
 
-    let last_finalized_block_number = provider.get_block_number().await?;
+    let last_finalized_block_number = checkpoint::confirmed_head(&provider, confirmations).await?;
     info!("last finalized block number: {last_finalized_block_number}");
 
     let bytes = {
@@ -60,80 +86,185 @@ pub async fn run(
     db::create_viewing_keys_table(&connection)?;
     db::create_checkpoint_table(&connection, CHECKPOINT_TABLE_NAME)?;
 
-    let last_seen_block = db::query_checkpoint(&connection, CHECKPOINT_TABLE_NAME)?;
-
-    info!("last seen block: {last_seen_block}");
-
-    for block_number in max(from_block, last_seen_block)..=last_finalized_block_number {
-        if let Some(block) = provider
-            .get_block_by_number(
-                BlockNumberOrTag::Number(block_number),
-                BlockTransactionsKind::Full,
-            )
-            .await?
-        {
-            if let Some(txs) = block.transactions.as_transactions() {
-                for tx in txs {
-                    if tx.to().eq(&Some(*shielder_address)) {
-                        if let Ok(newAccountNativeCall {
-                            symKeyEncryptionC1X,
-                            symKeyEncryptionC1Y,
-                            symKeyEncryptionC2X,
-                            symKeyEncryptionC2Y,
-                            ..
-                        }) = newAccountNativeCall::abi_decode(tx.input(), false)
-                        {
-                            debug!("Processing newAccountNative transaction {tx:?}");
-
-                            decode_and_persist(
-                                &connection,
-                                symKeyEncryptionC1X,
-                                symKeyEncryptionC1Y,
-                                symKeyEncryptionC2X,
-                                symKeyEncryptionC2Y,
-                                private_key,
-                                redact_sensitive_data,
-                            )?;
-                        }
-
-                        if let Ok(newAccountERC20Call {
-                            symKeyEncryptionC1X,
-                            symKeyEncryptionC1Y,
-                            symKeyEncryptionC2X,
-                            symKeyEncryptionC2Y,
-                            ..
-                        }) = newAccountERC20Call::abi_decode(tx.input(), false)
-                        {
-                            debug!("Processing newAccountERC20 transaction {tx:?}");
-                            decode_and_persist(
-                                &connection,
-                                symKeyEncryptionC1X,
-                                symKeyEncryptionC1Y,
-                                symKeyEncryptionC2X,
-                                symKeyEncryptionC2Y,
-                                private_key,
-                                redact_sensitive_data,
-                            )?;
-                        }
-                    }
+    let first_block = checkpoint::resume_block(
+        &provider,
+        &connection,
+        CHECKPOINT_TABLE_NAME,
+        from_block,
+        db::delete_viewing_keys_after,
+    )
+    .await?;
+
+    info!("resuming from block: {first_block}");
+
+    let base_filter = Filter::new()
+        .address(*shielder_address)
+        .event_signature(NewAccount::SIGNATURE_HASH);
+
+    for block_number in (first_block..=last_finalized_block_number).step_by(batch_size) {
+        let last_batch_block = min(
+            block_number + batch_size as u64 - 1,
+            last_finalized_block_number,
+        );
+        let filter = base_filter
+            .clone()
+            .from_block(block_number)
+            .to_block(last_batch_block);
+
+        // A transaction might create several accounts - it is enough to look at it once.
+        let mut new_account_txs = BTreeMap::new();
+        for log in provider.get_logs(&filter).await? {
+            let tx_hash = log.transaction_hash.ok_or(Error::MissingData)?;
+            let tx_block_number = log.block_number.ok_or(Error::MissingData)?;
+            new_account_txs.insert(tx_hash, tx_block_number);
+        }
+        debug!(
+            "Found {} new account transactions in the block range {block_number} : {last_batch_block}",
+            new_account_txs.len()
+        );
+
+        let encrypted_keys = futures::stream::iter(new_account_txs)
+            .map(|(tx_hash, tx_block_number)| {
+                let provider = &provider;
+                async move {
+                    let keys = new_account_calls(provider, shielder_address, tx_hash).await?;
+                    Ok::<_, Error>((tx_block_number, keys))
                 }
+            })
+            .buffered(concurrency.max(1))
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        let Some(block_hash) =
+            checkpoint::canonical_block_hash(&provider, last_batch_block).await?
+        else {
+            return Err(Error::MissingData);
+        };
+
+        let transaction = connection.unchecked_transaction()?;
+        for (tx_block_number, keys) in encrypted_keys {
+            for key in keys {
+                decode_and_persist(
+                    &transaction,
+                    key,
+                    private_key,
+                    tx_block_number,
+                    redact_sensitive_data,
+                )?;
             }
         }
-
-        trace!("Updating last seen block: {block_number}");
-        db::update_checkpoint(&connection, CHECKPOINT_TABLE_NAME, block_number)?;
+        transaction.commit()?;
+
+        trace!("Updating last seen block: {last_batch_block}");
+        checkpoint::save(
+            &connection,
+            CHECKPOINT_TABLE_NAME,
+            last_batch_block,
+            block_hash,
+        )?;
     }
 
     Ok(())
 }
 
+/// Encrypted viewing keys from all the new account calls to the Shielder contract in `tx_hash`.
+async fn new_account_calls(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    shielder_address: &Address,
+    tx_hash: TxHash,
+) -> Result<Vec<EncryptedViewingKey>, Error> {
+    let tx = provider
+        .get_transaction_by_hash(tx_hash)
+        .await?
+        .ok_or(Error::MissingData)?;
+    if tx.to().eq(&Some(*shielder_address)) {
+        if let Some(key) = decode_new_account_call(tx.input()) {
+            debug!("Processing new account transaction {tx_hash}");
+            return Ok(vec![key]);
+        }
+    }
+
+    // The Shielder contract was called indirectly (e.g. through a proxy or a multicall).
+    debug!("Tracing new account transaction {tx_hash}");
+    let options = GethDebugTracingOptions {
+        tracer: Some(GethDebugTracerType::BuiltInTracer(
+            GethDebugBuiltInTracerType::CallTracer,
+        )),
+        ..Default::default()
+    };
+    let trace = provider
+        .debug_trace_transaction(tx_hash, options)
+        .await
+        .inspect_err(|err| warn!("Cannot trace new account transaction {tx_hash}: {err}"))?;
+    let GethTrace::CallTracer(trace) = trace else {
+        return Err(Error::MissingData);
+    };
+
+    let mut keys = vec![];
+    collect_new_account_calls(&trace, shielder_address, &mut keys);
+    Ok(keys)
+}
+
+fn collect_new_account_calls(
+    frame: &CallFrame,
+    shielder_address: &Address,
+    keys: &mut Vec<EncryptedViewingKey>,
+) {
+    // Reverted calls did not create accounts.
+    if frame.error.is_some() {
+        return;
+    }
+    if frame.to.eq(&Some(*shielder_address)) {
+        if let Some(key) = decode_new_account_call(&frame.input) {
+            keys.push(key);
+        }
+    }
+    for call in &frame.calls {
+        collect_new_account_calls(call, shielder_address, keys);
+    }
+}
+
+fn decode_new_account_call(input: &[u8]) -> Option<EncryptedViewingKey> {
+    if let Ok(newAccountNativeCall {
+        symKeyEncryptionC1X,
+        symKeyEncryptionC1Y,
+        symKeyEncryptionC2X,
+        symKeyEncryptionC2Y,
+        ..
+    }) = newAccountNativeCall::abi_decode(input, false)
+    {
+        return Some(EncryptedViewingKey {
+            c1x: symKeyEncryptionC1X,
+            c1y: symKeyEncryptionC1Y,
+            c2x: symKeyEncryptionC2X,
+            c2y: symKeyEncryptionC2Y,
+        });
+    }
+
+    if let Ok(newAccountERC20Call {
+        symKeyEncryptionC1X,
+        symKeyEncryptionC1Y,
+        symKeyEncryptionC2X,
+        symKeyEncryptionC2Y,
+        ..
+    }) = newAccountERC20Call::abi_decode(input, false)
+    {
+        return Some(EncryptedViewingKey {
+            c1x: symKeyEncryptionC1X,
+            c1y: symKeyEncryptionC1Y,
+            c2x: symKeyEncryptionC2X,
+            c2y: symKeyEncryptionC2Y,
+        });
+    }
+
+    None
+}
+
 fn decode_and_persist(
     connection: &Connection,
-    c1x: U256,
-    c1y: U256,
-    c2x: U256,
-    c2y: U256,
+    EncryptedViewingKey { c1x, c1y, c2x, c2y }: EncryptedViewingKey,
     private_key: grumpkin::Fr,
+    block_number: u64,
     redact_sensitive_data: bool,
 ) -> Result<(), Error> {
     let ciphertext1 = GrumpkinPointAffine::new(u256_to_field(c1x), u256_to_field(c1y));
@@ -152,6 +283,7 @@ fn decode_and_persist(
         ViewingKey {
             viewing_key: x.to_bytes().to_vec(),
         },
+        block_number,
     )?;
 
     Ok(())
@@ -102,6 +102,29 @@ pub fn query_events(
     results.into_iter().collect()
 }
 
+/// Delete the event emitted in `tx_hash`.
+pub fn delete_event(connection: &Connection, tx_hash: &[u8; 32]) -> Result<(), rusqlite::Error> {
+    connection.execute("DELETE FROM events WHERE tx_hash = ?1", [tx_hash])?;
+    Ok(())
+}
+
+/// How many recent checkpoint block hashes are kept to find the fork point after a reorg.
+const BLOCK_HASH_HISTORY: u64 = 64;
+
+/// Delete events from blocks after `block_number`.
+pub fn delete_events_after(
+    connection: &Connection,
+    block_number: u64,
+) -> Result<(), rusqlite::Error> {
+    connection.execute(
+        "DELETE FROM events WHERE block_number > ?1",
+        (&block_number,),
+    )?;
+    Ok(())
+}
+
+/// Creates the checkpoint table along with `{table}_hashes`, where hashes of the checkpointed
+/// blocks are kept.
 pub fn create_checkpoint_table(
     connection: &Connection,
     table: &str,
@@ -115,6 +138,15 @@ pub fn create_checkpoint_table(
         ),
         (),
     )?;
+    connection.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {table}_hashes (
+            block_number INTEGER PRIMARY KEY,
+            block_hash BLOB NOT NULL
+        )"
+        ),
+        (),
+    )?;
     Ok(())
 }
 
@@ -131,6 +163,52 @@ pub fn update_checkpoint(
     Ok(())
 }
 
+/// Record the hash of a checkpointed block, forgetting the oldest ones.
+pub fn insert_block_hash(
+    connection: &Connection,
+    table: &str,
+    block_number: u64,
+    block_hash: &[u8],
+) -> Result<(), rusqlite::Error> {
+    connection.execute(
+        &format!("REPLACE INTO {table}_hashes (block_number, block_hash) VALUES (?1, ?2)"),
+        (&block_number, block_hash),
+    )?;
+    connection.execute(
+        &format!(
+            "DELETE FROM {table}_hashes WHERE block_number NOT IN
+            (SELECT block_number FROM {table}_hashes ORDER BY block_number DESC LIMIT ?1)"
+        ),
+        (&BLOCK_HASH_HISTORY,),
+    )?;
+    Ok(())
+}
+
+/// Hashes of the checkpointed blocks, the latest first.
+pub fn query_block_hashes(
+    connection: &Connection,
+    table: &str,
+) -> Result<Vec<(u64, Vec<u8>)>, rusqlite::Error> {
+    let mut query = connection.prepare(&format!(
+        "SELECT block_number, block_hash FROM {table}_hashes ORDER BY block_number DESC"
+    ))?;
+    let result = query.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    result.collect()
+}
+
+/// Move the checkpoint back to `block_number`, forgetting hashes of the later blocks.
+pub fn rewind_checkpoint(
+    connection: &Connection,
+    table: &str,
+    block_number: u64,
+) -> Result<(), rusqlite::Error> {
+    connection.execute(
+        &format!("DELETE FROM {table}_hashes WHERE block_number > ?1"),
+        (&block_number,),
+    )?;
+    update_checkpoint(connection, table, block_number)
+}
+
 pub fn query_checkpoint(connection: &Connection, table: &str) -> Result<u64, rusqlite::Error> {
     match connection.query_row(
         &format!("SELECT last_block_number FROM {table} WHERE id = 0"),
@@ -151,25 +229,53 @@ pub struct ViewingKey {
 pub fn create_viewing_keys_table(connection: &Connection) -> Result<(), rusqlite::Error> {
     connection.execute(
         "CREATE TABLE IF NOT EXISTS viewing_keys (
-            viewing_key BLOB PRIMARY KEY
+            viewing_key BLOB PRIMARY KEY,
+            block_number INTEGER
         )",
         (),
     )?;
+
+    // Databases created by older versions lack the block number.
+    let has_block_number: bool = connection.query_row(
+        "SELECT COUNT(*) > 0 FROM pragma_table_info('viewing_keys') WHERE name = 'block_number'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_block_number {
+        connection.execute(
+            "ALTER TABLE viewing_keys ADD COLUMN block_number INTEGER",
+            (),
+        )?;
+    }
     Ok(())
 }
 
+/// Insert a viewing key collected from a transaction in `block_number`.
 pub fn upsert_viewing_key(
     connection: &Connection,
     viewing_key: ViewingKey,
+    block_number: u64,
 ) -> Result<(), rusqlite::Error> {
     connection.execute(
-        "REPLACE INTO viewing_keys (viewing_key) VALUES (?1)",
-        (viewing_key.viewing_key,),
+        "REPLACE INTO viewing_keys (viewing_key, block_number) VALUES (?1, ?2)",
+        (viewing_key.viewing_key, &block_number),
     )?;
 
     Ok(())
 }
 
+/// Delete viewing keys collected from blocks after `block_number`.
+pub fn delete_viewing_keys_after(
+    connection: &Connection,
+    block_number: u64,
+) -> Result<(), rusqlite::Error> {
+    connection.execute(
+        "DELETE FROM viewing_keys WHERE block_number > ?1",
+        (&block_number,),
+    )?;
+    Ok(())
+}
+
 pub fn query_viewing_keys(connection: &Connection) -> Result<Vec<ViewingKey>, rusqlite::Error> {
     let mut query = connection.prepare("SELECT viewing_key FROM viewing_keys")?;
     let result = query.query_map([], |row| {
@@ -1,47 +1,62 @@
-use std::{
-    cmp::{max, min},
-    path::PathBuf,
-};
+use std::{cmp::min, path::PathBuf, pin::pin};
 
 use alloy_primitives::Address;
 use alloy_provider::Provider;
 use alloy_rpc_types::{Filter, Log};
 use alloy_sol_types::SolEvent;
+use futures::StreamExt;
 use log::{debug, info, trace};
 use rusqlite::Connection;
 use shielder_circuits::Fr;
 use shielder_contract::{
+    events::{ShielderEventUpdate, ShielderEventWatcher},
     providers::create_simple_provider,
     ShielderContract::{Deposit, NewAccount, ShielderContractEvents, Withdraw},
 };
 use type_conversions::u256_to_field;
 
 use crate::{
+    checkpoint,
     db::{self, Event},
     error::Error,
 };
 
 const CHECKPOINT_TABLE_NAME: &str = "last_events_block";
 
+/// Index Shielder events from blocks with at least `confirmations` confirmations. Events from
+/// blocks that have been reorged out since the last run are removed first. With `follow`, new
+/// events are then indexed as they come (see `follow_events`).
 pub async fn run(
     rpc_url: &str,
     shielder_address: &Address,
     from_block: u64,
+    confirmations: u64,
     batch_size: usize,
+    follow: bool,
     db_path: &PathBuf,
 ) -> Result<(), Error> {
     let connection = db::init(db_path)?;
     let provider = create_simple_provider(rpc_url).await?;
-    let current_height = provider.get_block_number().await?;
+    let current_height = checkpoint::confirmed_head(&provider, confirmations).await?;
     let base_filter = Filter::new().address(*shielder_address);
 
     db::create_events_table(&connection)?;
     db::create_checkpoint_table(&connection, CHECKPOINT_TABLE_NAME)?;
 
-    let last_seen_block = db::query_checkpoint(&connection, CHECKPOINT_TABLE_NAME)?;
-    info!("last seen block: {last_seen_block}");
-
-    for block_number in (max(from_block, last_seen_block)..=current_height).step_by(batch_size) {
+    let first_block = checkpoint::resume_block(
+        &provider,
+        &connection,
+        CHECKPOINT_TABLE_NAME,
+        from_block,
+        db::delete_events_after,
+    )
+    .await?;
+    info!("resuming from block: {first_block}");
+    // Events after the checkpoint can only come from following, and their blocks might have been
+    // reorged out in the meantime. They are indexed again.
+    db::delete_events_after(&connection, first_block.saturating_sub(1))?;
+
+    for block_number in (first_block..=current_height).step_by(batch_size) {
         let last_batch_block = min(block_number + batch_size as u64 - 1, current_height);
         let filter = base_filter
             .clone()
@@ -56,10 +71,50 @@ pub async fn run(
         );
 
         process_logs(raw_logs, &connection)?;
+        let Some(block_hash) =
+            checkpoint::canonical_block_hash(&provider, last_batch_block).await?
+        else {
+            return Err(Error::MissingData);
+        };
         trace!("Updating last seen block: {last_batch_block}");
-        db::update_checkpoint(&connection, CHECKPOINT_TABLE_NAME, last_batch_block)?;
+        checkpoint::save(
+            &connection,
+            CHECKPOINT_TABLE_NAME,
+            last_batch_block,
+            block_hash,
+        )?;
     }
 
+    if follow {
+        follow_events(rpc_url, shielder_address, current_height + 1, &connection).await?;
+    }
+    Ok(())
+}
+
+/// Index events from `from_block` on as soon as they are emitted, until the connection fails.
+/// The checkpoint is not moved - the next run indexes these blocks again.
+async fn follow_events(
+    rpc_url: &str,
+    shielder_address: &Address,
+    from_block: u64,
+    connection: &Connection,
+) -> Result<(), Error> {
+    info!("following new events from block: {from_block}");
+    let provider = create_simple_provider(rpc_url).await?;
+    let mut updates =
+        pin!(ShielderEventWatcher::new(provider, *shielder_address, from_block).into_stream());
+
+    while let Some(update) = updates.next().await {
+        match update? {
+            ShielderEventUpdate::Added(log) => {
+                persist_event(connection, log.event, &log.tx_hash.0, log.block_number)?
+            }
+            ShielderEventUpdate::Removed(log) => {
+                info!("Removing event from a reorged out block: {}", log.tx_hash);
+                db::delete_event(connection, &log.tx_hash.0)?
+            }
+        }
+    }
     Ok(())
 }
 
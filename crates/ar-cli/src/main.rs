@@ -6,6 +6,7 @@ use cli::{ChainConfig, Cli};
 use error::Error;
 use log::info;
 
+mod checkpoint;
 mod cli;
 mod collect_viewing_keys;
 mod common;
@@ -59,6 +60,8 @@ async fn main() -> Result<(), Error> {
         }
         cli::Command::GenerateMnemonic => generate::run_gen_mnemonic()?,
         cli::Command::CollectKeys {
+            batch_size,
+            concurrency,
             private_key_file,
             redact_sensitive_data,
             common:
@@ -66,6 +69,7 @@ async fn main() -> Result<(), Error> {
                     rpc_url,
                     shielder_address,
                     from_block,
+                    confirmations,
                 },
             db,
         } => {
@@ -75,6 +79,9 @@ async fn main() -> Result<(), Error> {
                     shielder_address,
                     private_key_file,
                     *from_block,
+                    *confirmations,
+                    *batch_size,
+                    *concurrency,
                     &db.path,
                     *redact_sensitive_data,
                 )
@@ -88,16 +95,20 @@ async fn main() -> Result<(), Error> {
                     rpc_url,
                     shielder_address,
                     from_block,
+                    confirmations,
                 },
             db,
             batch_size,
+            follow,
         } => {
             retry_with_backoff(|| {
                 index_events::run(
                     rpc_url,
                     shielder_address,
                     *from_block,
+                    *confirmations,
                     *batch_size,
+                    *follow,
                     &db.path,
                 )
             })
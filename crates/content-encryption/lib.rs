@@ -1,4 +1,20 @@
 //! Small library for encrypting and decrypting content with a password.
+//!
+//! Content is encrypted with XChaCha20-Poly1305, with a key derived from the password with
+//! Argon2id. The result is an envelope:
+//!
+//! | field        | size                                        |
+//! |--------------|---------------------------------------------|
+//! | magic        | 4 bytes (`MAGIC`)                           |
+//! | version      | 1 byte (`VERSION`)                          |
+//! | Argon2 costs | 3 x 4 bytes (memory, time, lanes; LE)       |
+//! | salt         | 32 bytes (random)                           |
+//! | nonce        | 24 bytes (random)                           |
+//! | ciphertext   | the rest (authenticated with the header)    |
+//!
+//! Content encrypted by the previous versions of this library (with a constant salt and nonce, and
+//! without the header) can still be decrypted - use `is_legacy` to find such content and encrypt it
+//! again.
 
 #![deny(missing_docs)]
 #![cfg_attr(not(feature = "std"), no_std)]
@@ -7,29 +23,172 @@ extern crate alloc;
 
 use alloc::vec::Vec;
 
-use anyhow::{anyhow, Result};
-use chacha20poly1305::{aead::Aead, KeyInit, XChaCha20Poly1305};
+use anyhow::{anyhow, bail, Result};
+use chacha20poly1305::{
+    aead::{rand_core::RngCore, Aead, OsRng, Payload},
+    KeyInit, XChaCha20Poly1305,
+};
 
-const SALT: [u8; 32] = [41u8; 32];
-const NONCE: [u8; 24] = [41u8; 24];
+/// Bytes starting every envelope.
+pub const MAGIC: [u8; 4] = *b"ZKCE";
+/// Current version of the envelope format.
+pub const VERSION: u8 = 1;
 
-fn scheme_from_password(password: &[u8]) -> Result<XChaCha20Poly1305> {
-    let key = argon2::hash_raw(password, &SALT, &Default::default())
+const SALT_LEN: usize = 32;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+// Limits of the Argon2 costs accepted when decrypting, so that a crafted envelope cannot make us
+// allocate gigabytes of memory or spin forever.
+const MAX_MEMORY_COST: u32 = 1 << 20;
+const MAX_TIME_COST: u32 = 16;
+const MAX_LANES: u32 = 16;
+
+const LEGACY_SALT: [u8; 32] = [41u8; 32];
+const LEGACY_NONCE: [u8; 24] = [41u8; 24];
+
+/// Costs of the Argon2id key derivation.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Argon2Params {
+    /// Memory cost in KiB.
+    pub memory_cost: u32,
+    /// Number of passes.
+    pub time_cost: u32,
+    /// Degree of parallelism.
+    pub lanes: u32,
+}
+
+impl Default for Argon2Params {
+    /// The parameters recommended by OWASP.
+    fn default() -> Self {
+        Self {
+            memory_cost: 19 * 1024,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl Argon2Params {
+    fn to_bytes(self) -> [u8; 12] {
+        let mut bytes = [0u8; 12];
+        bytes[0..4].copy_from_slice(&self.memory_cost.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.time_cost.to_le_bytes());
+        bytes[8..12].copy_from_slice(&self.lanes.to_le_bytes());
+        bytes
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Self {
+        let word = |i: usize| u32::from_le_bytes(bytes[4 * i..4 * i + 4].try_into().unwrap());
+        Self {
+            memory_cost: word(0),
+            time_cost: word(1),
+            lanes: word(2),
+        }
+    }
+}
+
+fn scheme_from_password(
+    password: &[u8],
+    salt: &[u8],
+    params: Argon2Params,
+) -> Result<XChaCha20Poly1305> {
+    let config = argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        mem_cost: params.memory_cost,
+        time_cost: params.time_cost,
+        lanes: params.lanes,
+        ..Default::default()
+    };
+    let key = argon2::hash_raw(password, salt, &config)
+        .map_err(|e| anyhow!("Failed to derive key from password: {e}"))?;
+    Ok(XChaCha20Poly1305::new(key.as_slice().into()))
+}
+
+fn legacy_scheme_from_password(password: &[u8]) -> Result<XChaCha20Poly1305> {
+    let key = argon2::hash_raw(password, &LEGACY_SALT, &Default::default())
         .map_err(|e| anyhow!("Failed to derive key from password: {e}"))?;
     Ok(XChaCha20Poly1305::new(key.as_slice().into()))
 }
 
-/// Encrypt `content` with `password`.
+/// Encrypt `content` with `password`, with a random salt and nonce.
 pub fn encrypt(content: &[u8], password: &[u8]) -> Result<Vec<u8>> {
-    scheme_from_password(password)?
-        .encrypt(NONCE.as_slice().into(), content)
-        .map_err(|e| anyhow!("Failed to encrypt data: {e}"))
+    encrypt_with_params(content, password, Argon2Params::default())
 }
 
-/// Decrypt `content` with `password`.
+/// Encrypt `content` with `password`, deriving the key with `params`.
+pub fn encrypt_with_params(
+    content: &[u8],
+    password: &[u8],
+    params: Argon2Params,
+) -> Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut envelope = Vec::with_capacity(HEADER_LEN + content.len() + 16);
+    envelope.extend_from_slice(&MAGIC);
+    envelope.push(VERSION);
+    envelope.extend_from_slice(&params.to_bytes());
+    envelope.extend_from_slice(&salt);
+    envelope.extend_from_slice(&nonce);
+
+    let ciphertext = scheme_from_password(password, &salt, params)?
+        .encrypt(
+            nonce.as_slice().into(),
+            Payload {
+                msg: content,
+                aad: &envelope,
+            },
+        )
+        .map_err(|e| anyhow!("Failed to encrypt data: {e}"))?;
+    envelope.extend_from_slice(&ciphertext);
+    Ok(envelope)
+}
+
+/// Whether `content` was encrypted by an old version of this library (without an envelope).
+pub fn is_legacy(content: &[u8]) -> bool {
+    !content.starts_with(&MAGIC)
+}
+
+/// Decrypt `content` with `password`. Both the current and the legacy format are supported.
 pub fn decrypt(content: &[u8], password: &[u8]) -> Result<Vec<u8>> {
-    scheme_from_password(password)?
-        .decrypt(NONCE.as_slice().into(), content)
+    if is_legacy(content) {
+        return legacy_scheme_from_password(password)?
+            .decrypt(LEGACY_NONCE.as_slice().into(), content)
+            .map_err(|e| {
+                anyhow!("Failed to decrypt data - probably the password is incorrect: {e}")
+            });
+    }
+
+    if content.len() < HEADER_LEN {
+        bail!("Failed to decrypt data - the envelope is truncated");
+    }
+    let (header, ciphertext) = content.split_at(HEADER_LEN);
+    let version = header[MAGIC.len()];
+    if version != VERSION {
+        bail!("Failed to decrypt data - unsupported envelope version {version}");
+    }
+    let params_start = MAGIC.len() + 1;
+    let salt_start = params_start + 12;
+    let nonce_start = salt_start + SALT_LEN;
+    let params = Argon2Params::from_bytes(&header[params_start..salt_start]);
+    if params.memory_cost > MAX_MEMORY_COST
+        || params.time_cost > MAX_TIME_COST
+        || params.lanes > MAX_LANES
+    {
+        bail!("Failed to decrypt data - Argon2 costs {params:?} exceed the limits");
+    }
+
+    scheme_from_password(password, &header[salt_start..nonce_start], params)?
+        .decrypt(
+            header[nonce_start..].into(),
+            Payload {
+                msg: ciphertext,
+                aad: header,
+            },
+        )
         .map_err(|e| anyhow!("Failed to decrypt data - probably the password is incorrect: {e}"))
 }
 
@@ -40,3 +199,45 @@ pub fn decrypt_to_string(content: &[u8], password: &[u8]) -> Result<String> {
     String::from_utf8(decrypted)
         .map_err(|e| anyhow!("Failed to decrypt data - probably the password is incorrect: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARAMS: Argon2Params = Argon2Params {
+        memory_cost: 64,
+        time_cost: 1,
+        lanes: 1,
+    };
+
+    #[test]
+    fn content_is_encrypted_with_random_salt_and_nonce() {
+        let first = encrypt_with_params(b"content", b"password", PARAMS).unwrap();
+        let second = encrypt_with_params(b"content", b"password", PARAMS).unwrap();
+
+        assert_ne!(first, second);
+        assert!(!is_legacy(&first));
+        assert_eq!(decrypt(&first, b"password").unwrap(), b"content");
+        assert_eq!(decrypt(&second, b"password").unwrap(), b"content");
+        assert!(decrypt(&first, b"other password").is_err());
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        let mut envelope = encrypt_with_params(b"content", b"password", PARAMS).unwrap();
+        envelope[HEADER_LEN - 1] ^= 1;
+
+        assert!(decrypt(&envelope, b"password").is_err());
+    }
+
+    #[test]
+    fn legacy_content_can_be_decrypted() {
+        let legacy = legacy_scheme_from_password(b"password")
+            .unwrap()
+            .encrypt(LEGACY_NONCE.as_slice().into(), b"content".as_slice())
+            .unwrap();
+
+        assert!(is_legacy(&legacy));
+        assert_eq!(decrypt(&legacy, b"password").unwrap(), b"content");
+    }
+}
@@ -103,6 +103,7 @@ fn prepare_call(
         caller_address,
         protocol_fee,
         memo,
+        permit: None,
     };
 
     Ok(shielder_account.prepare_call::<DepositCallType>(&params, &pk, token, amount, &extra))
@@ -79,6 +79,7 @@ fn prepare_call(
         caller_address,
         protocol_fee,
         memo,
+        permit: None,
     };
 
     Ok(shielder_account.prepare_call::<NewAccountCallType>(&params, &pk, token, amount, &extra))
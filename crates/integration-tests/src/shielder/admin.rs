@@ -0,0 +1,279 @@
+use alloy_primitives::Address;
+use alloy_provider::RootProvider;
+use alloy_transport::BoxTransport;
+use shielder_contract::{ConnectionPolicy, ShielderAdmin};
+
+use crate::node::EvmNode;
+
+/// `ShielderAdmin` of the Shielder contract at `shielder_address`, sending its calls to `node` as
+/// `caller`.
+pub fn shielder_admin(
+    node: &EvmNode,
+    shielder_address: Address,
+    caller: Address,
+) -> ShielderAdmin<RootProvider<BoxTransport>> {
+    ShielderAdmin::new(
+        shielder_address,
+        ConnectionPolicy::Keep {
+            provider: node.provider(),
+            caller_address: caller,
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use alloy_primitives::{b256, Address, Bytes, U256};
+    use alloy_provider::RootProvider;
+    use alloy_transport::BoxTransport;
+    use rstest::rstest;
+    use shielder_circuits::GrumpkinPointAffine;
+    use shielder_contract::{
+        call_type::{DryRun, EstimateGas, Submit},
+        ShielderAdmin, ShielderContractError, ShielderRevert,
+    };
+
+    use crate::{
+        admin::shielder_admin,
+        ar_pubkey::get_ar_pubkey,
+        deploy::{
+            deploy_shielder_implementation, ACTOR_ADDRESS, DEPLOYER_ADDRESS, RECIPIENT_ADDRESS,
+        },
+        node::EvmNode,
+        shielder::deploy::{deployment, Deployment},
+    };
+
+    type Admin = ShielderAdmin<RootProvider<BoxTransport>>;
+
+    fn owner() -> Address {
+        Address::from_str(DEPLOYER_ADDRESS).unwrap()
+    }
+
+    fn actor() -> Address {
+        Address::from_str(ACTOR_ADDRESS).unwrap()
+    }
+
+    /// The node serving `deployment`, the Shielder address and admins acting as the owner and as
+    /// an unauthorized account.
+    fn setup(deployment: Deployment) -> (EvmNode, Address, Admin, Admin) {
+        let shielder = deployment.contract_suite.shielder;
+        let node = EvmNode::new(deployment.evm);
+        let owner = shielder_admin(&node, shielder, owner());
+        let anon = shielder_admin(&node, shielder, actor());
+        (node, shielder, owner, anon)
+    }
+
+    fn is_unauthorized<T>(result: Result<T, ShielderContractError>) -> bool {
+        matches!(
+            result,
+            Err(ShielderContractError::Reverted(ShielderRevert::OwnableUnauthorizedAccount { account }))
+                if account == actor()
+        )
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn owner_can_set_fee_receiver(deployment: Deployment) {
+        let (_, _, owner, _) = setup(deployment);
+        let new_receiver = Address::from_str(RECIPIENT_ADDRESS).unwrap();
+
+        owner
+            .set_protocol_fee_receiver::<Submit>(new_receiver)
+            .await
+            .unwrap();
+
+        let receiver = owner.protocol_fee_receiver::<DryRun>().await.unwrap();
+        assert_eq!(receiver, new_receiver);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn anon_cannot_set_fee_receiver(deployment: Deployment) {
+        let (_, _, _, anon) = setup(deployment);
+
+        let result = anon.set_protocol_fee_receiver::<Submit>(actor()).await;
+        assert!(is_unauthorized(result));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn owner_can_set_fee_bps(deployment: Deployment) {
+        let (_, _, owner, _) = setup(deployment);
+
+        owner
+            .set_protocol_deposit_fee_bps::<Submit>(U256::from(25))
+            .await
+            .unwrap();
+        owner
+            .set_protocol_withdraw_fee_bps::<Submit>(U256::from(50))
+            .await
+            .unwrap();
+
+        let deposit_fee = owner.protocol_deposit_fee_bps::<DryRun>().await.unwrap();
+        let withdraw_fee = owner.protocol_withdraw_fee_bps::<DryRun>().await.unwrap();
+        assert_eq!(deposit_fee, U256::from(25));
+        assert_eq!(withdraw_fee, U256::from(50));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn anon_cannot_set_fee_bps(deployment: Deployment) {
+        let (_, _, _, anon) = setup(deployment);
+
+        let result = anon
+            .set_protocol_deposit_fee_bps::<Submit>(U256::from(25))
+            .await;
+        assert!(is_unauthorized(result));
+        let result = anon
+            .set_protocol_withdraw_fee_bps::<Submit>(U256::from(50))
+            .await;
+        assert!(is_unauthorized(result));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn owner_can_pause_and_unpause(deployment: Deployment) {
+        let (_, _, owner, _) = setup(deployment);
+        assert!(!owner.paused::<DryRun>().await.unwrap());
+
+        owner.pause::<Submit>().await.unwrap();
+        assert!(owner.paused::<DryRun>().await.unwrap());
+
+        owner.unpause::<Submit>().await.unwrap();
+        assert!(!owner.paused::<DryRun>().await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn cannot_pause_twice(deployment: Deployment) {
+        let (_, _, owner, _) = setup(deployment);
+        owner.pause::<Submit>().await.unwrap();
+
+        let result = owner.pause::<Submit>().await;
+        assert!(matches!(
+            result,
+            Err(ShielderContractError::Reverted(
+                ShielderRevert::EnforcedPause
+            ))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn anon_cannot_pause(deployment: Deployment) {
+        let (_, _, _, anon) = setup(deployment);
+
+        assert!(is_unauthorized(anon.pause::<Submit>().await));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn owner_can_set_ar_key(deployment: Deployment) {
+        let (node, shielder, owner, _) = setup(deployment);
+        let new_key = GrumpkinPointAffine {
+            x: U256::from_limbs([12, 0, 0, 0]),
+            y: U256::from_limbs([
+                14992752028423476423,
+                1427195812150930086,
+                1006077260510187192,
+                2285284544284890360,
+            ]),
+        };
+
+        owner
+            .set_anonymity_revoker_pubkey::<Submit>(new_key)
+            .await
+            .unwrap();
+
+        let ar_key = node.with_evm(|evm| get_ar_pubkey(shielder, evm));
+        assert_eq!(ar_key, new_key);
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn ar_key_must_be_on_curve(deployment: Deployment) {
+        let (_, _, owner, _) = setup(deployment);
+        let new_key = GrumpkinPointAffine::new(U256::from(0), U256::from(1));
+
+        let result = owner.set_anonymity_revoker_pubkey::<Submit>(new_key).await;
+        assert!(matches!(
+            result,
+            Err(ShielderContractError::Reverted(
+                ShielderRevert::InvalidGrumpkinPoint
+            ))
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn ownership_is_transferred_in_two_steps(deployment: Deployment) {
+        let (_, _, owner, anon) = setup(deployment);
+
+        owner.transfer_ownership::<Submit>(actor()).await.unwrap();
+        assert_eq!(owner.owner::<DryRun>().await.unwrap(), owner.address());
+        assert_eq!(owner.pending_owner::<DryRun>().await.unwrap(), actor());
+
+        anon.accept_ownership::<Submit>().await.unwrap();
+        assert_eq!(owner.owner::<DryRun>().await.unwrap(), actor());
+
+        // The previous owner has lost its rights.
+        let result = owner.pause::<Submit>().await;
+        assert!(matches!(
+            result,
+            Err(ShielderContractError::Reverted(ShielderRevert::OwnableUnauthorizedAccount { account }))
+                if account == owner.address()
+        ));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn owner_can_upgrade(deployment: Deployment) {
+        let (node, shielder, owner, _) = setup(deployment);
+        // `bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)`
+        let implementation_slot =
+            b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+        let new_implementation = node.with_evm(deploy_shielder_implementation);
+
+        owner
+            .upgrade_to_and_call::<Submit>(new_implementation, Bytes::new())
+            .await
+            .unwrap();
+
+        let implementation = node.with_evm(|evm| {
+            evm.db.accounts[&shielder].storage[&U256::from_be_bytes(implementation_slot.0)]
+        });
+        assert_eq!(
+            implementation,
+            U256::from_be_slice(new_implementation.as_slice())
+        );
+        // The proxy still works.
+        assert!(!owner.paused::<DryRun>().await.unwrap());
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn anon_cannot_upgrade(deployment: Deployment) {
+        let (_, _, _, anon) = setup(deployment);
+
+        let result = anon
+            .upgrade_to_and_call::<Submit>(actor(), Bytes::new())
+            .await;
+        assert!(is_unauthorized(result));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn dry_run_and_estimation_do_not_change_state(deployment: Deployment) {
+        let (_, _, owner, anon) = setup(deployment);
+
+        owner.pause::<DryRun>().await.unwrap();
+        assert!(owner.pause::<EstimateGas>().await.unwrap() > 0);
+        assert!(!owner.paused::<DryRun>().await.unwrap());
+
+        // Failures are reported before anything is sent.
+        assert!(is_unauthorized(anon.pause::<DryRun>().await));
+        assert!(is_unauthorized(anon.pause::<EstimateGas>().await));
+    }
+}
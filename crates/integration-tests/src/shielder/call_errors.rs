@@ -20,6 +20,9 @@ pub enum ShielderCallErrors {
 
     InvalidGrumpkinPoint(ShielderContract::InvalidGrumpkinPoint),
     OwnableUnauthorizedAccount(ShielderContract::OwnableUnauthorizedAccount),
+    EnforcedPause(ShielderContract::EnforcedPause),
+    ERC20InsufficientAllowance(ShielderContract::ERC20InsufficientAllowance),
+    ERC20InsufficientBalance(ShielderContract::ERC20InsufficientBalance),
 
     DestinationTriggeredRevert(),
 }
@@ -63,6 +66,13 @@ impl From<ShielderContractErrors> for ShielderCallErrors {
             ShielderContractErrors::OwnableUnauthorizedAccount(e) => {
                 ShielderCallErrors::OwnableUnauthorizedAccount(e)
             }
+            ShielderContractErrors::EnforcedPause(e) => ShielderCallErrors::EnforcedPause(e),
+            ShielderContractErrors::ERC20InsufficientAllowance(e) => {
+                ShielderCallErrors::ERC20InsufficientAllowance(e)
+            }
+            ShielderContractErrors::ERC20InsufficientBalance(e) => {
+                ShielderCallErrors::ERC20InsufficientBalance(e)
+            }
         }
     }
 }
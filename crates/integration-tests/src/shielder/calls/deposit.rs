@@ -5,7 +5,9 @@ use shielder_account::{
     call_data::{DepositCall, DepositCallType, DepositExtra},
     ShielderAccount, Token,
 };
-use shielder_contract::ShielderContract::{depositERC20Call, depositNativeCall};
+use shielder_contract::ShielderContract::{
+    depositERC20Call, depositERC20WithPermitCall, depositNativeCall,
+};
 use shielder_setup::protocol_fee::compute_protocol_fee_from_gross;
 
 use crate::{
@@ -47,6 +49,7 @@ pub fn prepare_call(
             caller_address: Address::from_str(ACTOR_ADDRESS).unwrap(),
             protocol_fee,
             memo,
+            permit: None,
         },
     );
     (calldata, note_index)
@@ -63,6 +66,10 @@ pub fn invoke_call(
             let calldata: depositNativeCall = calldata.clone().try_into().unwrap();
             invoke_shielder_call(deployment, &calldata, amount)
         }
+        Token::ERC20(_) if calldata.permit.is_some() => {
+            let calldata: depositERC20WithPermitCall = calldata.clone().try_into().unwrap();
+            invoke_shielder_call(deployment, &calldata, None)
+        }
         Token::ERC20(_) => {
             deployment
                 .test_erc20
@@ -95,6 +102,7 @@ mod tests {
     use std::{assert_matches::assert_matches, mem, str::FromStr};
 
     use alloy_primitives::{Bytes, FixedBytes, U256};
+    use alloy_signer_local::PrivateKeySigner;
     use halo2_proofs::halo2curves::ff::PrimeField;
     use rstest::rstest;
     use shielder_account::{call_data::DepositCall, ShielderAccount};
@@ -110,7 +118,9 @@ mod tests {
         actor_balance_decreased_by,
         call_errors::ShielderCallErrors,
         calls::deposit::{invoke_call, prepare_call},
-        deploy::{MEMO_BYTES, PROTOCOL_FEES, ZERO_MEMO_BYTES, ZERO_PROTOCOL_FEES},
+        deploy::{
+            ACTOR_PRIVATE_KEY, MEMO_BYTES, PROTOCOL_FEES, ZERO_MEMO_BYTES, ZERO_PROTOCOL_FEES,
+        },
         protocol_fee_receiver_balance_increased_by,
         protocol_fees::ProtocolFeesBps,
         recipient_balance_increased_by,
@@ -241,6 +251,71 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    #[case::fresh_permit(false)]
+    #[case::already_submitted_permit(true)]
+    fn succeeds_with_permit(mut deployment: Deployment, #[case] front_run: bool) {
+        let token = TestToken::ERC20;
+        let initial_amount = U256::from(100000);
+        let mut shielder_account = new_account::create_account_and_call(
+            &mut deployment,
+            token,
+            U256::from(1),
+            initial_amount,
+            ZERO_MEMO_BYTES,
+        )
+        .unwrap();
+
+        let deposit_amount = U256::from(50000);
+        let (mut calldata, _) = prepare_call(
+            &mut deployment,
+            &mut shielder_account,
+            token,
+            deposit_amount,
+            ZERO_MEMO_BYTES,
+        );
+        let actor = PrivateKeySigner::from_str(ACTOR_PRIVATE_KEY).unwrap();
+        let shielder = deployment.contract_suite.shielder;
+        let permit =
+            deployment
+                .test_erc20
+                .sign_permit(&deployment.evm, &actor, shielder, deposit_amount);
+        if front_run {
+            // Anyone can submit a permit seen in the mempool before the deposit is mined.
+            deployment
+                .test_erc20
+                .permit(
+                    &mut deployment.evm,
+                    actor.address(),
+                    shielder,
+                    deposit_amount,
+                    &permit,
+                )
+                .unwrap();
+        }
+        calldata.permit = Some(permit);
+
+        let result = invoke_call(&mut deployment, &mut shielder_account, &calldata);
+
+        assert!(result.is_ok());
+        assert!(actor_balance_decreased_by(
+            &deployment,
+            token,
+            initial_amount + deposit_amount
+        ));
+        assert_eq!(
+            shielder_account.shielded_amount,
+            initial_amount + deposit_amount
+        );
+        assert_eq!(
+            deployment
+                .test_erc20
+                .get_allowance(&deployment.evm, actor.address(), shielder)
+                .unwrap(),
+            U256::ZERO
+        );
+    }
+
     #[rstest]
     #[case::native(TestToken::Native)]
     #[case::erc20(TestToken::ERC20)]
@@ -449,6 +524,7 @@ mod tests {
             mac_commitment: U256::ZERO,
             proof: Bytes::from(vec![]),
             memo: Bytes::from(vec![]),
+            permit: None,
         };
         let result = invoke_call(&mut deployment, &mut shielder_account, &calldata);
 
@@ -5,7 +5,9 @@ use shielder_account::{
     call_data::{NewAccountCall, NewAccountCallExtra, NewAccountCallType},
     ShielderAccount, Token,
 };
-use shielder_contract::ShielderContract::{newAccountERC20Call, newAccountNativeCall};
+use shielder_contract::ShielderContract::{
+    newAccountERC20Call, newAccountERC20WithPermitCall, newAccountNativeCall,
+};
 use shielder_setup::protocol_fee::compute_protocol_fee_from_gross;
 
 use crate::{
@@ -41,6 +43,7 @@ pub fn prepare_call(
             caller_address: Address::from_str(ACTOR_ADDRESS).unwrap(),
             protocol_fee,
             memo,
+            permit: None,
         },
     )
 }
@@ -56,6 +59,10 @@ pub fn invoke_call(
             let calldata: newAccountNativeCall = calldata.clone().try_into().unwrap();
             invoke_shielder_call(deployment, &calldata, amount)
         }
+        Token::ERC20(_) if calldata.permit.is_some() => {
+            let calldata: newAccountERC20WithPermitCall = calldata.clone().try_into().unwrap();
+            invoke_shielder_call(deployment, &calldata, None)
+        }
         Token::ERC20(_) => {
             deployment
                 .test_erc20
@@ -112,6 +119,7 @@ mod tests {
     use std::{assert_matches::assert_matches, mem, str::FromStr};
 
     use alloy_primitives::{Bytes, FixedBytes, U256};
+    use alloy_signer_local::PrivateKeySigner;
     use evm_utils::SuccessResult;
     use halo2_proofs::halo2curves::ff::PrimeField;
     use rstest::rstest;
@@ -127,7 +135,10 @@ mod tests {
     use crate::{
         call_errors::ShielderCallErrors,
         calls::new_account::{create_account_and_call, invoke_call, prepare_call, TestToken},
-        deploy::{deployment, MEMO_BYTES, PROTOCOL_FEES, ZERO_MEMO_BYTES, ZERO_PROTOCOL_FEES},
+        deploy::{
+            deployment, ACTOR_PRIVATE_KEY, MEMO_BYTES, PROTOCOL_FEES, ZERO_MEMO_BYTES,
+            ZERO_PROTOCOL_FEES,
+        },
         protocol_fee_receiver_balance_increased_by,
         protocol_fees::ProtocolFeesBps,
         shielder::{actor_balance_decreased_by, Deployment},
@@ -236,6 +247,45 @@ mod tests {
         ));
     }
 
+    #[rstest]
+    fn succeeds_with_permit(mut deployment: Deployment) {
+        let token = TestToken::ERC20;
+        let mut shielder_account = ShielderAccount::new(U256::from(1), token.token(&deployment));
+        let amount = U256::from(10);
+        let mut calldata = prepare_call(
+            &mut deployment,
+            &mut shielder_account,
+            token,
+            amount,
+            ZERO_MEMO_BYTES,
+        );
+        let actor = PrivateKeySigner::from_str(ACTOR_PRIVATE_KEY).unwrap();
+        calldata.permit = Some(deployment.test_erc20.sign_permit(
+            &deployment.evm,
+            &actor,
+            deployment.contract_suite.shielder,
+            amount,
+        ));
+
+        let result = invoke_call(&mut deployment, &mut shielder_account, &calldata);
+
+        assert!(result.is_ok());
+        assert!(actor_balance_decreased_by(&deployment, token, amount));
+        assert_eq!(shielder_account.shielded_amount, amount);
+        // The allowance granted by the permit is spent in the same transaction.
+        assert_eq!(
+            deployment
+                .test_erc20
+                .get_allowance(
+                    &deployment.evm,
+                    actor.address(),
+                    deployment.contract_suite.shielder
+                )
+                .unwrap(),
+            U256::ZERO
+        );
+    }
+
     #[rstest]
     #[case::native(TestToken::Native)]
     #[case::erc20(TestToken::ERC20)]
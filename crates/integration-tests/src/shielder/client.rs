@@ -0,0 +1,108 @@
+//! The amounts computed by `shielder-client` must be exactly the ones the contract expects.
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use rstest::rstest;
+    use shielder_client::ActionAmounts;
+
+    use crate::{
+        calls::{deposit, new_account, withdraw},
+        deploy::{deployment, Deployment, PROTOCOL_FEES, ZERO_MEMO_BYTES, ZERO_PROTOCOL_FEES},
+        protocol_fees::ProtocolFeesBps,
+        shielder::{
+            actor_balance_decreased_by, protocol_fee_receiver_balance_increased_by,
+            recipient_balance_increased_by, relayer_balance_increased_by,
+        },
+        TestToken,
+    };
+
+    #[rstest]
+    #[case::native(TestToken::Native, ZERO_PROTOCOL_FEES)]
+    #[case::erc20(TestToken::ERC20, ZERO_PROTOCOL_FEES)]
+    #[case::native_fees(TestToken::Native, PROTOCOL_FEES)]
+    #[case::erc20_fees(TestToken::ERC20, PROTOCOL_FEES)]
+    fn client_amounts_are_accepted(
+        mut deployment: Deployment,
+        #[case] token: TestToken,
+        #[case] protocol_fees_bps: ProtocolFeesBps,
+    ) {
+        deployment.set_protocol_fees(&protocol_fees_bps);
+
+        let new_account = ActionAmounts::deposit(
+            U256::from(20_000),
+            protocol_fees_bps.protocol_deposit_fee_bps,
+        );
+        let mut shielder_account = new_account::create_account_and_call(
+            &mut deployment,
+            token,
+            U256::from(1),
+            new_account.amount,
+            ZERO_MEMO_BYTES,
+        )
+        .unwrap();
+        assert_eq!(shielder_account.shielded_amount, U256::from(20_000));
+
+        let deposit = ActionAmounts::deposit(
+            U256::from(10_000),
+            protocol_fees_bps.protocol_deposit_fee_bps,
+        );
+        let (calldata, _) = deposit::prepare_call(
+            &mut deployment,
+            &mut shielder_account,
+            token,
+            deposit.amount,
+            ZERO_MEMO_BYTES,
+        );
+        deposit::invoke_call(&mut deployment, &mut shielder_account, &calldata).unwrap();
+        assert_eq!(shielder_account.shielded_amount, U256::from(30_000));
+
+        let relayer_fee = U256::from(17);
+        let pocket_money = match token {
+            TestToken::Native => U256::ZERO,
+            TestToken::ERC20 => U256::from(1),
+        };
+        let withdraw = ActionAmounts::withdraw(
+            U256::from(5_000),
+            relayer_fee,
+            protocol_fees_bps.protocol_withdraw_fee_bps,
+        );
+        let (calldata, _) = withdraw::prepare_call(
+            &mut deployment,
+            &mut shielder_account,
+            withdraw::prepare_args(
+                token,
+                withdraw.amount,
+                relayer_fee,
+                pocket_money,
+                ZERO_MEMO_BYTES,
+            ),
+        );
+        withdraw::invoke_call(&mut deployment, &mut shielder_account, &calldata).unwrap();
+
+        assert_eq!(
+            shielder_account.shielded_amount,
+            U256::from(30_000) - withdraw.amount
+        );
+        assert!(actor_balance_decreased_by(
+            &deployment,
+            token,
+            new_account.amount + deposit.amount
+        ));
+        assert!(recipient_balance_increased_by(
+            &deployment,
+            token,
+            U256::from(5_000)
+        ));
+        assert!(relayer_balance_increased_by(
+            &deployment,
+            token,
+            relayer_fee
+        ));
+        assert!(protocol_fee_receiver_balance_increased_by(
+            &deployment,
+            token,
+            new_account.protocol_fee + deposit.protocol_fee + withdraw.protocol_fee
+        ));
+    }
+}
@@ -238,7 +238,7 @@ pub fn deployment(
 ///
 /// This requires more steps than deploying a regular contract because Solc leaves placeholders
 /// in the bytecode, which has to be replaced with a deployed Poseidon2 contract address.
-fn deploy_shielder_implementation(evm: &mut EvmRunner) -> Address {
+pub fn deploy_shielder_implementation(evm: &mut EvmRunner) -> Address {
     // 1. Compile the Shielder implementation contract. It will contain placeholders.
     let solidity_code = read_contract("Shielder.sol");
     let implementation_bytecode = source_to_bytecode(solidity_code, "Shielder", false);
@@ -1,9 +1,17 @@
 use alloy_primitives::{Address, U256};
-use alloy_sol_types::{SolCall, SolValue};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{Eip712Domain, SolCall, SolValue};
 use evm_utils::{EvmRunner, EvmRunnerError};
+use shielder_contract::{
+    permit::{sign_permit, Permit},
+    ShielderContract::PermitSignature,
+};
 
 use crate::{deploy_contract_with_caller, ierc20::IERC20};
 
+/// The name of `RevmTestERC20`, which is also the name in its EIP-712 domain.
+const TEST_ERC20_NAME: &str = "TestERC20";
+
 pub struct TestERC20 {
     pub contract_address: Address,
     pub faucet_address: Address,
@@ -68,6 +76,74 @@ impl TestERC20 {
         )?;
         Ok(())
     }
+
+    pub fn get_allowance(
+        &self,
+        evm: &EvmRunner,
+        owner: Address,
+        spender: Address,
+    ) -> Result<U256, alloy_sol_types::Error> {
+        let calldata = IERC20::allowanceCall { owner, spender };
+        let result = evm
+            .dry_run(self.contract_address, calldata.abi_encode(), None, None)
+            .unwrap();
+        <U256>::abi_decode(&result.output, true)
+    }
+
+    /// Sign an EIP-2612 permit allowing `spender` to transfer `value` of `owner`'s tokens.
+    pub fn sign_permit(
+        &self,
+        evm: &EvmRunner,
+        owner: &PrivateKeySigner,
+        spender: Address,
+        value: U256,
+    ) -> PermitSignature {
+        let calldata = IERC20::noncesCall {
+            owner: owner.address(),
+        };
+        let result = evm
+            .dry_run(self.contract_address, calldata.abi_encode(), None, None)
+            .unwrap();
+        let nonce = <U256>::abi_decode(&result.output, true).unwrap();
+
+        let domain = Eip712Domain::new(
+            Some(TEST_ERC20_NAME.into()),
+            Some("1".into()),
+            Some(U256::from(1)),
+            Some(self.contract_address),
+            None,
+        );
+        let permit = Permit {
+            owner: owner.address(),
+            spender,
+            value,
+            nonce,
+            deadline: U256::MAX,
+        };
+        sign_permit(owner, &domain, &permit).unwrap()
+    }
+
+    /// Submit a permit signed with `sign_permit` directly to the token.
+    pub fn permit(
+        &self,
+        evm: &mut EvmRunner,
+        owner: Address,
+        spender: Address,
+        value: U256,
+        signature: &PermitSignature,
+    ) -> Result<(), EvmRunnerError> {
+        let calldata = IERC20::permitCall {
+            owner,
+            spender,
+            value,
+            deadline: signature.deadline,
+            v: signature.v,
+            r: signature.r,
+            s: signature.s,
+        };
+        evm.call(self.contract_address, calldata.abi_encode(), None, None)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -75,6 +151,7 @@ mod tests {
     use std::str::FromStr;
 
     use alloy_primitives::{Address, U256};
+    use alloy_signer_local::PrivateKeySigner;
     use alloy_sol_types::SolCall;
     use evm_utils::EvmRunner;
 
@@ -135,4 +212,42 @@ mod tests {
             U256::from(123)
         );
     }
+
+    #[test]
+    fn permit_works() {
+        let faucet_address = Address::from_str(FAUCET_ADDRESS).unwrap();
+        let owner = PrivateKeySigner::random();
+        let spender_address = Address::from_str(ACTOR_ADDRESS).unwrap();
+
+        let mut evm = EvmRunner::aleph_evm();
+        let token = TestERC20::deploy(&mut evm, faucet_address);
+
+        let signature = token.sign_permit(&evm, &owner, spender_address, U256::from(123));
+        assert!(token
+            .permit(
+                &mut evm,
+                owner.address(),
+                spender_address,
+                U256::from(123),
+                &signature
+            )
+            .is_ok());
+
+        assert_eq!(
+            token
+                .get_allowance(&evm, owner.address(), spender_address)
+                .unwrap(),
+            U256::from(123)
+        );
+        // The signature can't be used twice.
+        assert!(token
+            .permit(
+                &mut evm,
+                owner.address(),
+                spender_address,
+                U256::from(123),
+                &signature
+            )
+            .is_err());
+    }
 }
@@ -17,5 +17,17 @@ sol! {
         function approve(address spender, uint256 value) external returns (bool);
 
         function transferFrom(address from, address to, uint256 value) external returns (bool);
+
+        function permit(
+            address owner,
+            address spender,
+            uint256 value,
+            uint256 deadline,
+            uint8 v,
+            bytes32 r,
+            bytes32 s
+        ) external;
+
+        function nonces(address owner) external view returns (uint256);
     }
 }
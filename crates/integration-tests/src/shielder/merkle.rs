@@ -26,10 +26,15 @@ mod tests {
     use std::assert_matches::assert_matches;
 
     use alloy_primitives::{Bytes, U256};
+    use alloy_sol_types::SolCall;
     use rstest::rstest;
-    use shielder_contract::ShielderContract::getMerklePathCall;
+    use shielder_contract::{
+        state::merkle_root_exists,
+        ShielderContract::{getMerklePathCall, merkleTreeCall},
+    };
 
     use crate::{
+        node::EvmNode,
         shielder::{
             calls::new_account,
             deploy::{deployment, Deployment},
@@ -55,4 +60,50 @@ mod tests {
         assert_matches!(result, Ok(_));
         assert!(result.unwrap().0.is_empty())
     }
+
+    /// Pins the storage layout assumed by `merkle_root_exists` to the one of the real contract.
+    #[rstest]
+    #[tokio::test]
+    async fn merkle_root_exists_reads_contract_storage(mut deployment: Deployment) {
+        let shielder = deployment.contract_suite.shielder;
+        new_account::create_account_and_call(
+            &mut deployment,
+            TestToken::Native,
+            U256::from(1),
+            U256::from(10),
+            Bytes::from(vec![]),
+        )
+        .unwrap();
+        let output = deployment
+            .evm
+            .call(shielder, merkleTreeCall {}.abi_encode(), None, None)
+            .unwrap()
+            .output;
+        let root = merkleTreeCall::abi_decode_returns(&output, true)
+            .unwrap()
+            .root;
+
+        let node = EvmNode::new(deployment.evm);
+        let provider = node.any_network_provider();
+        let exists = merkle_root_exists(&provider, shielder, root).await.unwrap();
+        let unknown = merkle_root_exists(&provider, shielder, root + U256::from(1))
+            .await
+            .unwrap();
+
+        assert_eq!(exists, Some(true));
+        assert_eq!(unknown, Some(false));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn merkle_root_existence_is_unknown_for_empty_tree(deployment: Deployment) {
+        let shielder = deployment.contract_suite.shielder;
+        let node = EvmNode::new(deployment.evm);
+
+        let exists = merkle_root_exists(&node.any_network_provider(), shielder, U256::from(1))
+            .await
+            .unwrap();
+
+        assert_eq!(exists, None);
+    }
 }
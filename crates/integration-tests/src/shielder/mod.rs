@@ -18,14 +18,17 @@ use shielder_contract::ShielderContract::{
 };
 
 pub mod address_conversion;
+pub mod admin;
 pub mod ar_pubkey;
 pub mod call_errors;
 pub mod calls;
+pub mod client;
 pub mod deploy;
 pub mod erc1967proxy;
 pub mod erc20;
 pub mod ierc20;
 pub mod merkle;
+pub mod node;
 pub mod protocol_fees;
 
 fn unpause_shielder(shielder: Address, evm: &mut EvmRunner) {
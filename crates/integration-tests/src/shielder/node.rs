@@ -0,0 +1,179 @@
+//! A JSON-RPC node backed by `EvmRunner`, so that the `shielder-contract` clients (like
+//! `ShielderAdmin`) can be run against a `Deployment` exactly as against a real node.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use alloy_json_rpc::{ErrorPayload, RequestPacket, Response, ResponsePacket, ResponsePayload};
+use alloy_network::AnyNetwork;
+use alloy_primitives::{keccak256, Address, Bytes, U256};
+use alloy_provider::{ProviderBuilder, RootProvider};
+use alloy_rpc_client::RpcClient;
+use alloy_transport::{BoxTransport, TransportError, TransportErrorKind, TransportFut};
+use evm_utils::{EvmRunner, EvmRunnerError};
+use serde_json::{json, Value};
+use tower::Service;
+
+/// Chain id used by `EvmRunner`.
+const CHAIN_ID: u64 = 1;
+
+struct NodeState {
+    evm: EvmRunner,
+    /// Number of transactions sent so far, used for generating transaction hashes.
+    sent_transactions: u64,
+}
+
+/// Serves `eth_chainId`, `eth_call`, `eth_estimateGas`, `eth_getStorageAt` (of the latest state)
+/// and `eth_sendTransaction` (executed immediately, without signatures, nonces or fees).
+#[derive(Clone)]
+pub struct EvmNode(Arc<Mutex<NodeState>>);
+
+impl EvmNode {
+    pub fn new(evm: EvmRunner) -> Self {
+        Self(Arc::new(Mutex::new(NodeState {
+            evm,
+            sent_transactions: 0,
+        })))
+    }
+
+    pub fn provider(&self) -> RootProvider<BoxTransport> {
+        ProviderBuilder::new().on_client(self.client())
+    }
+
+    /// Provider for the functions expecting `AnyNetwork` (like the ones in
+    /// `shielder_contract::state`).
+    pub fn any_network_provider(&self) -> RootProvider<BoxTransport, AnyNetwork> {
+        ProviderBuilder::new()
+            .network::<AnyNetwork>()
+            .on_client(self.client())
+    }
+
+    fn client(&self) -> RpcClient<BoxTransport> {
+        RpcClient::new(BoxTransport::new(self.clone()), true)
+    }
+
+    /// Access the underlying EVM directly (e.g. to deploy a contract or read the storage).
+    pub fn with_evm<R>(&self, action: impl FnOnce(&mut EvmRunner) -> R) -> R {
+        action(&mut self.0.lock().unwrap().evm)
+    }
+
+    fn respond(&self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        let RequestPacket::Single(request) = request else {
+            return Err(TransportErrorKind::custom_str("Batches are not supported"));
+        };
+        let serialized = serde_json::from_str::<Value>(request.serialized().get()).unwrap();
+        let params = &serialized["params"];
+        let mut state = self.0.lock().unwrap();
+
+        let result = match request.method() {
+            "eth_chainId" => Ok(json!(format!("{CHAIN_ID:#x}"))),
+            "eth_call" => {
+                let tx = Transaction::parse(&params[0]);
+                state
+                    .evm
+                    .dry_run(tx.to, tx.input, Some(tx.from), Some(tx.value))
+                    .map(|result| json!(Bytes::from(result.output)))
+            }
+            "eth_estimateGas" => {
+                let tx = Transaction::parse(&params[0]);
+                state
+                    .evm
+                    .dry_run(tx.to, tx.input, Some(tx.from), Some(tx.value))
+                    .map(|result| json!(format!("{:#x}", result.gas_used)))
+            }
+            "eth_getStorageAt" => {
+                let address = Address::from_str(params[0].as_str().unwrap()).unwrap();
+                let slot = U256::from_str(params[1].as_str().unwrap()).unwrap();
+                let value = state
+                    .evm
+                    .db
+                    .accounts
+                    .get(&address)
+                    .and_then(|account| account.storage.get(&slot).copied())
+                    .unwrap_or_default();
+                Ok(json!(value))
+            }
+            "eth_sendTransaction" => {
+                let tx = Transaction::parse(&params[0]);
+                state.sent_transactions += 1;
+                let tx_hash = keccak256(state.sent_transactions.to_be_bytes());
+                state
+                    .evm
+                    .call(tx.to, tx.input, Some(tx.from), Some(tx.value))
+                    .map(|_| json!(tx_hash))
+            }
+            method => {
+                return Err(TransportErrorKind::custom_str(&format!(
+                    "Unsupported method {method}"
+                )))
+            }
+        };
+
+        let payload = match result {
+            Ok(result) => {
+                ResponsePayload::Success(serde_json::value::to_raw_value(&result).unwrap())
+            }
+            Err(err) => ResponsePayload::Failure(error_payload(err)),
+        };
+        Ok(ResponsePacket::Single(Response {
+            id: request.id().clone(),
+            payload,
+        }))
+    }
+}
+
+/// The error a real node returns for a failed execution. Reverts carry the revert data, so that
+/// they are decoded by the clients.
+fn error_payload(err: EvmRunnerError) -> ErrorPayload {
+    let error = match err {
+        EvmRunnerError::Revert(result) => json!({
+            "code": 3,
+            "message": "execution reverted",
+            "data": result.output().cloned().unwrap_or_default(),
+        }),
+        err => json!({
+            "code": -32000,
+            "message": err.to_string(),
+        }),
+    };
+    serde_json::from_value(error).unwrap()
+}
+
+impl Service<RequestPacket> for EvmNode {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let response = self.respond(request);
+        Box::pin(async move { response })
+    }
+}
+
+/// The fields of a transaction request that matter for the execution.
+struct Transaction {
+    from: Address,
+    to: Address,
+    input: Vec<u8>,
+    value: U256,
+}
+
+impl Transaction {
+    fn parse(request: &Value) -> Self {
+        let field = |name: &str| request[name].as_str();
+        let input = field("input").or(field("data")).unwrap_or("0x");
+        Self {
+            from: Address::from_str(field("from").expect("Missing sender")).unwrap(),
+            to: Address::from_str(field("to").expect("Missing recipient")).unwrap(),
+            input: Bytes::from_str(input).unwrap().to_vec(),
+            value: field("value").map_or(U256::ZERO, |value| U256::from_str(value).unwrap()),
+        }
+    }
+}
@@ -12,8 +12,9 @@ use shielder_circuits::{
 use shielder_contract::{
     DepositCommitment, NewAccountCommitment,
     ShielderContract::{
-        depositERC20Call, depositNativeCall, newAccountERC20Call, newAccountNativeCall,
-        withdrawERC20Call, withdrawNativeCall,
+        depositERC20Call, depositERC20WithPermitCall, depositNativeCall, newAccountERC20Call,
+        newAccountERC20WithPermitCall, newAccountNativeCall, withdrawERC20Call, withdrawNativeCall,
+        PermitSignature,
     },
     WithdrawCommitment,
 };
@@ -70,6 +71,8 @@ pub struct NewAccountCall {
     pub mac_commitment: U256,
     pub proof: Bytes,
     pub memo: Bytes,
+    /// EIP-2612 permit granting the allowance for `amount` (ERC20 only).
+    pub permit: Option<PermitSignature>,
 }
 
 impl TryFrom<NewAccountCall> for newAccountNativeCall {
@@ -120,6 +123,32 @@ impl TryFrom<NewAccountCall> for newAccountERC20Call {
     }
 }
 
+impl TryFrom<NewAccountCall> for newAccountERC20WithPermitCall {
+    type Error = CallTypeConversionError;
+
+    fn try_from(calldata: NewAccountCall) -> Result<Self, Self::Error> {
+        match (calldata.token, calldata.permit) {
+            (Token::ERC20(token_address), Some(permit)) => Ok(Self {
+                tokenAddress: token_address,
+                amount: calldata.amount,
+                expectedContractVersion: calldata.expected_contract_version,
+                newNote: calldata.new_note,
+                prenullifier: calldata.prenullifier,
+                symKeyEncryptionC1X: calldata.sym_key_encryption_c1.x,
+                symKeyEncryptionC1Y: calldata.sym_key_encryption_c1.y,
+                symKeyEncryptionC2X: calldata.sym_key_encryption_c2.x,
+                symKeyEncryptionC2Y: calldata.sym_key_encryption_c2.y,
+                macSalt: calldata.mac_salt,
+                macCommitment: calldata.mac_commitment,
+                proof: calldata.proof,
+                memo: calldata.memo,
+                permit,
+            }),
+            _ => Err(CallTypeConversionError),
+        }
+    }
+}
+
 pub struct NewAccountCallExtra {
     pub anonymity_revoker_public_key: GrumpkinPointAffine<U256>,
     pub encryption_salt: U256,
@@ -127,6 +156,9 @@ pub struct NewAccountCallExtra {
     pub caller_address: Address,
     pub protocol_fee: U256,
     pub memo: Bytes,
+    /// EIP-2612 permit signed by `caller_address` for the whole amount. Without it, the contract
+    /// needs an allowance granted beforehand.
+    pub permit: Option<PermitSignature>,
 }
 
 pub enum NewAccountCallType {}
@@ -186,6 +218,7 @@ impl CallType for NewAccountCallType {
             mac_commitment: field_to_u256(prover_knowledge.compute_public_input(MacCommitment)),
             proof: Bytes::from(proof),
             memo: extra.memo.clone(),
+            permit: extra.permit.clone(),
         }
     }
 }
@@ -202,6 +235,8 @@ pub struct DepositCall {
     pub mac_commitment: U256,
     pub proof: Bytes,
     pub memo: Bytes,
+    /// EIP-2612 permit granting the allowance for `amount` (ERC20 only).
+    pub permit: Option<PermitSignature>,
 }
 
 impl TryFrom<DepositCall> for depositNativeCall {
@@ -246,12 +281,38 @@ impl TryFrom<DepositCall> for depositERC20Call {
     }
 }
 
+impl TryFrom<DepositCall> for depositERC20WithPermitCall {
+    type Error = CallTypeConversionError;
+
+    fn try_from(calldata: DepositCall) -> Result<Self, Self::Error> {
+        match (calldata.token, calldata.permit) {
+            (Token::ERC20(token_address), Some(permit)) => Ok(Self {
+                expectedContractVersion: calldata.expected_contract_version,
+                tokenAddress: token_address,
+                amount: calldata.amount,
+                oldNullifierHash: calldata.old_nullifier_hash,
+                newNote: calldata.new_note,
+                merkleRoot: calldata.merkle_root,
+                macSalt: calldata.mac_salt,
+                macCommitment: calldata.mac_commitment,
+                proof: calldata.proof,
+                memo: calldata.memo,
+                permit,
+            }),
+            _ => Err(CallTypeConversionError),
+        }
+    }
+}
+
 pub struct DepositExtra {
     pub merkle_path: [[U256; ARITY]; NOTE_TREE_HEIGHT],
     pub mac_salt: U256,
     pub caller_address: Address,
     pub protocol_fee: U256,
     pub memo: Bytes,
+    /// EIP-2612 permit signed by `caller_address` for the whole amount. Without it, the contract
+    /// needs an allowance granted beforehand.
+    pub permit: Option<PermitSignature>,
 }
 
 pub enum DepositCallType {}
@@ -309,6 +370,7 @@ impl CallType for DepositCallType {
             mac_commitment: field_to_u256(pk.compute_public_input(MacCommitment)),
             proof: Bytes::from(proof.to_vec()),
             memo: extra.memo.clone(),
+            permit: extra.permit.clone(),
         }
     }
 }
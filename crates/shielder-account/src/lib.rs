@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "contract")]
 pub mod call_data;
+#[cfg(feature = "contract")]
+mod recovery;
 pub mod secrets;
 mod shielder_action;
 
@@ -96,14 +98,18 @@ impl ShielderAccount {
         if self.nonce == 0 {
             return None;
         }
+        Some(self.compute_note(self.previous_nullifier(), self.shielded_amount, token))
+    }
+
+    fn compute_note(&self, nullifier: U256, account_balance: U256, token: Token) -> U256 {
         let raw_note: Fr = note_hash(&Note {
             version: contract_version().note_version(),
             id: u256_to_field(self.id),
-            nullifier: u256_to_field(self.previous_nullifier()),
-            account_balance: u256_to_field(self.shielded_amount),
+            nullifier: u256_to_field(nullifier),
+            account_balance: u256_to_field(account_balance),
             token_address: address_to_field(token.address()),
         });
-        Some(field_to_u256(raw_note))
+        field_to_u256(raw_note)
     }
 
     /// Get the prenullifier (the nullifier of the first action - new account).
@@ -0,0 +1,175 @@
+use alloy_primitives::{TxHash, U256};
+use shielder_contract::ShielderContract::{Deposit, NewAccount, ShielderContractEvents, Withdraw};
+
+use crate::{ShielderAccount, Token};
+
+impl ShielderAccount {
+    /// Go through `events` (in the order of emission) and register every action that belongs to
+    /// this account. Returns the number of registered actions.
+    ///
+    /// An event is recognized by its new note: the note commits to the account id and to the
+    /// nullifier of the next action, so only the account owner can produce a matching one.
+    pub fn register_events(
+        &mut self,
+        events: impl IntoIterator<Item = (TxHash, ShielderContractEvents)>,
+    ) -> usize {
+        let mut registered = 0;
+        for (tx_hash, event) in events {
+            if self.is_next_action(&event) {
+                self.register_action((tx_hash, event));
+                registered += 1;
+            }
+        }
+        registered
+    }
+
+    fn is_next_action(&self, event: &ShielderContractEvents) -> bool {
+        let (token_address, new_balance) = match event {
+            ShielderContractEvents::NewAccount(NewAccount {
+                tokenAddress,
+                amount,
+                protocolFee,
+                ..
+            }) if self.nonce == 0 => (
+                tokenAddress,
+                self.balance_after_deposit(*amount, *protocolFee),
+            ),
+            ShielderContractEvents::Deposit(Deposit {
+                tokenAddress,
+                amount,
+                protocolFee,
+                ..
+            }) if self.nonce > 0 => (
+                tokenAddress,
+                self.balance_after_deposit(*amount, *protocolFee),
+            ),
+            ShielderContractEvents::Withdraw(Withdraw {
+                tokenAddress,
+                amount,
+                ..
+            }) if self.nonce > 0 => (tokenAddress, self.shielded_amount.checked_sub(*amount)),
+            _ => return false,
+        };
+
+        let Some(new_balance) = new_balance else {
+            return false;
+        };
+        Token::from(*token_address) == self.token
+            && self.compute_note(self.next_nullifier(), new_balance, self.token) == event.note()
+    }
+
+    fn balance_after_deposit(&self, amount: U256, protocol_fee: U256) -> Option<U256> {
+        amount
+            .checked_sub(protocol_fee)
+            .and_then(|net_amount| self.shielded_amount.checked_add(net_amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, Bytes, TxHash, U256};
+    use shielder_contract::ShielderContract::{
+        Deposit, NewAccount, ShielderContractEvents, Withdraw,
+    };
+    use shielder_setup::version::contract_version;
+
+    use crate::{ShielderAccount, ShielderAction, Token};
+
+    fn note_after(account: &ShielderAccount, action: ShielderAction) -> U256 {
+        let mut account = account.clone();
+        account.register_action(action);
+        account.note(account.token).unwrap()
+    }
+
+    fn new_account_event(account: &ShielderAccount, amount: u64) -> ShielderContractEvents {
+        let amount = U256::from(amount);
+        let action = ShielderAction::new_account(
+            amount,
+            U256::ZERO,
+            TxHash::ZERO,
+            account.token,
+            U256::ZERO,
+        );
+        ShielderContractEvents::NewAccount(NewAccount {
+            contractVersion: contract_version().to_bytes(),
+            prenullifier: U256::ZERO,
+            tokenAddress: account.token.address(),
+            amount,
+            newNote: note_after(account, action),
+            newNoteIndex: U256::ZERO,
+            macSalt: U256::ZERO,
+            macCommitment: U256::ZERO,
+            protocolFee: U256::ZERO,
+            memo: Bytes::new(),
+        })
+    }
+
+    fn deposit_event(account: &ShielderAccount, amount: u64) -> ShielderContractEvents {
+        let amount = U256::from(amount);
+        let action =
+            ShielderAction::deposit(amount, U256::ZERO, TxHash::ZERO, account.token, U256::ZERO);
+        ShielderContractEvents::Deposit(Deposit {
+            contractVersion: contract_version().to_bytes(),
+            tokenAddress: account.token.address(),
+            amount,
+            newNote: note_after(account, action),
+            newNoteIndex: U256::ZERO,
+            macSalt: U256::ZERO,
+            macCommitment: U256::ZERO,
+            protocolFee: U256::ZERO,
+            memo: Bytes::new(),
+        })
+    }
+
+    fn withdraw_event(account: &ShielderAccount, amount: u64) -> ShielderContractEvents {
+        let amount = U256::from(amount);
+        let action = ShielderAction::withdraw(
+            amount,
+            U256::ZERO,
+            TxHash::ZERO,
+            Address::ZERO,
+            account.token,
+            U256::ZERO,
+        );
+        ShielderContractEvents::Withdraw(Withdraw {
+            contractVersion: contract_version().to_bytes(),
+            tokenAddress: account.token.address(),
+            amount,
+            withdrawalAddress: Address::ZERO,
+            newNote: note_after(account, action),
+            newNoteIndex: U256::ZERO,
+            relayerAddress: Address::ZERO,
+            fee: U256::ZERO,
+            macSalt: U256::ZERO,
+            macCommitment: U256::ZERO,
+            pocketMoney: U256::ZERO,
+            protocolFee: U256::ZERO,
+            memo: Bytes::new(),
+        })
+    }
+
+    #[test]
+    fn recovers_only_own_actions() {
+        let mut owner = ShielderAccount::new(U256::from(1), Token::Native);
+        let stranger = ShielderAccount::new(U256::from(2), Token::Native);
+
+        // Build the expected history step by step, interleaved with somebody else's events.
+        let mut expected = owner.clone();
+        let mut events = vec![(TxHash::ZERO, new_account_event(&stranger, 5))];
+        let steps: [(fn(&ShielderAccount, u64) -> ShielderContractEvents, u64); 3] = [
+            (new_account_event, 100),
+            (deposit_event, 50),
+            (withdraw_event, 30),
+        ];
+        for (make_event, amount) in steps {
+            let event = make_event(&expected, amount);
+            expected.register_action((TxHash::ZERO, event.clone()));
+            events.push((TxHash::ZERO, event));
+            events.push((TxHash::ZERO, deposit_event(&stranger, 100)));
+        }
+
+        assert_eq!(owner.register_events(events), 3);
+        assert_eq!(owner, expected);
+        assert_eq!(owner.shielded_amount, U256::from(120));
+    }
+}
@@ -1,5 +1,8 @@
-use alloy_primitives::U256;
+use alloy_primitives::{Address, U256};
+use halo2curves::bn256::Fr;
 use sha3::Digest;
+use shielder_circuits::poseidon::off_circuit::hash;
+use type_conversions::{address_to_field, field_to_u256, u256_to_field};
 
 enum Label {
     Nullifier,
@@ -55,14 +58,32 @@ pub fn derive_id(private_key: U256, chain_id: u64, account_nonce: u32) -> U256 {
     finalize_hash(hasher)
 }
 
+/// Private-key-dependent derivation of a per-token ID seed for the account with `account_nonce`.
+///
+/// The first account (`account_nonce = 0`) is derived directly from `private_key` (and
+/// `chain_id` is ignored), so that ids created before multiple accounts per token were supported
+/// stay the same. The next ones start from [`derive_id`].
+pub fn derive_token_id(
+    private_key: U256,
+    chain_id: u64,
+    token: Address,
+    account_nonce: u32,
+) -> U256 {
+    let seed = match account_nonce {
+        0 => private_key,
+        account_nonce => derive_id(private_key, chain_id, account_nonce),
+    };
+    field_to_u256(hash(&[u256_to_field::<Fr>(seed), address_to_field(token)]))
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
 
-    use alloy_primitives::U256;
+    use alloy_primitives::{Address, U256};
     use halo2curves::{bn256::Fr, ff::PrimeField};
 
-    use crate::secrets::{derive_id, nonced::derive_nullifier, FIELD_MODULUS};
+    use crate::secrets::{derive_id, derive_token_id, nonced::derive_nullifier, FIELD_MODULUS};
 
     #[test]
     pub fn modulus_constant_is_correct() {
@@ -104,4 +125,28 @@ mod tests {
         assert_ne!(expected_before_modulo, actual);
         assert_eq!(expected_before_modulo.reduce_mod(FIELD_MODULUS), actual)
     }
+
+    #[test]
+    pub fn derive_token_id_separates_tokens_and_accounts() {
+        let key = U256::from(16);
+        let (first_token, second_token) = (Address::repeat_byte(1), Address::repeat_byte(2));
+
+        // The first account doesn't depend on the chain.
+        assert_eq!(
+            derive_token_id(key, 26, first_token, 0),
+            derive_token_id(key, 27, first_token, 0)
+        );
+        assert_eq!(
+            derive_token_id(key, 26, first_token, 1),
+            derive_token_id(derive_id(key, 26, 1), 26, first_token, 0)
+        );
+        assert_ne!(
+            derive_token_id(key, 26, first_token, 1),
+            derive_token_id(key, 26, second_token, 1)
+        );
+        assert_ne!(
+            derive_token_id(key, 26, first_token, 1),
+            derive_token_id(key, 26, first_token, 2)
+        );
+    }
 }
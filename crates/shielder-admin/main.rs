@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_signer_local::PrivateKeySigner;
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use shielder_contract::{
+    call_type::{Call, DryRun, EstimateGas},
+    ConnectionPolicy, ShielderAdmin,
+};
+use shielder_setup::shielder_circuits::GrumpkinPointAffine;
+
+#[derive(Parser)]
+pub struct Config {
+    /// The URL of the node to connect to. By default, it connects to a local node.
+    #[clap(long, default_value = "http://localhost:8545")]
+    pub node_rpc_url: String,
+
+    /// Address of the Shielder contract (the proxy).
+    #[clap(long)]
+    pub contract_address: Address,
+
+    /// Private key of the contract owner (or of the pending owner, for `accept-ownership`).
+    #[clap(long, env = "SHIELDER_ADMIN_KEY", hide_env_values = true, value_parser = parse_signer)]
+    pub signing_key: PrivateKeySigner,
+
+    /// What to do with the transaction.
+    #[clap(long, value_enum, default_value = "dry-run")]
+    pub mode: Mode,
+
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum Mode {
+    /// Send the transaction and wait for it to be included in a block.
+    Send,
+    /// Only check that the transaction would succeed.
+    DryRun,
+    /// Only estimate the gas usage of the transaction.
+    Estimate,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Print the owner, the pending owner, the fees, the fee receiver and whether the contract is
+    /// paused. Does not send any transaction.
+    Status,
+    Pause,
+    Unpause,
+    SetDepositFeeBps {
+        fee_bps: U256,
+    },
+    SetWithdrawFeeBps {
+        fee_bps: U256,
+    },
+    SetFeeReceiver {
+        receiver: Address,
+    },
+    SetAnonymityRevokerPubkey {
+        x: U256,
+        y: U256,
+    },
+    /// Offer the ownership to `new_owner`. It has to be accepted with `accept-ownership`, signed
+    /// by the new owner.
+    TransferOwnership {
+        new_owner: Address,
+    },
+    AcceptOwnership,
+    /// Upgrade the contract to a new implementation (already deployed at `implementation`).
+    Upgrade {
+        implementation: Address,
+        /// Hex-encoded calldata for the new implementation (e.g. a reinitializer call).
+        #[clap(long, default_value = "0x")]
+        data: Bytes,
+    },
+}
+
+fn parse_signer(string: &str) -> Result<PrivateKeySigner> {
+    PrivateKeySigner::from_str(string).map_err(|e| anyhow!(e))
+}
+
+/// Run `$method` on `$admin` in the given `$mode` and report the result.
+macro_rules! execute {
+    ($admin:expr, $mode:expr, $method:ident($($arg:expr),*)) => {
+        match $mode {
+            Mode::Send => {
+                let (tx_hash, block_hash) = $admin.$method::<Call>($($arg),*).await?;
+                println!("✅ Transaction {tx_hash} included in block {block_hash}");
+            }
+            Mode::DryRun => {
+                $admin.$method::<DryRun>($($arg),*).await?;
+                println!("✅ Dry run succeeded");
+            }
+            Mode::Estimate => {
+                let gas = $admin.$method::<EstimateGas>($($arg),*).await?;
+                println!("✅ Estimated gas: {gas}");
+            }
+        }
+    };
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = Config::parse();
+
+    let admin = ShielderAdmin::new(
+        config.contract_address,
+        ConnectionPolicy::OnDemand {
+            rpc_url: config.node_rpc_url,
+            signer: config.signing_key,
+        },
+    );
+    let mode = config.mode;
+
+    match config.command {
+        Command::Status => print_status(&admin).await?,
+        Command::Pause => execute!(admin, mode, pause()),
+        Command::Unpause => execute!(admin, mode, unpause()),
+        Command::SetDepositFeeBps { fee_bps } => {
+            execute!(admin, mode, set_protocol_deposit_fee_bps(fee_bps))
+        }
+        Command::SetWithdrawFeeBps { fee_bps } => {
+            execute!(admin, mode, set_protocol_withdraw_fee_bps(fee_bps))
+        }
+        Command::SetFeeReceiver { receiver } => {
+            execute!(admin, mode, set_protocol_fee_receiver(receiver))
+        }
+        Command::SetAnonymityRevokerPubkey { x, y } => {
+            execute!(
+                admin,
+                mode,
+                set_anonymity_revoker_pubkey(GrumpkinPointAffine::new(x, y))
+            )
+        }
+        Command::TransferOwnership { new_owner } => {
+            execute!(admin, mode, transfer_ownership(new_owner))
+        }
+        Command::AcceptOwnership => execute!(admin, mode, accept_ownership()),
+        Command::Upgrade {
+            implementation,
+            data,
+        } => execute!(admin, mode, upgrade_to_and_call(implementation, data)),
+    }
+
+    Ok(())
+}
+
+async fn print_status(admin: &ShielderAdmin) -> Result<()> {
+    println!("Owner:                 {}", admin.owner::<DryRun>().await?);
+    println!(
+        "Pending owner:         {}",
+        admin.pending_owner::<DryRun>().await?
+    );
+    println!("Paused:                {}", admin.paused::<DryRun>().await?);
+    println!(
+        "Deposit fee (bps):     {}",
+        admin.protocol_deposit_fee_bps::<DryRun>().await?
+    );
+    println!(
+        "Withdraw fee (bps):    {}",
+        admin.protocol_withdraw_fee_bps::<DryRun>().await?
+    );
+    println!(
+        "Fee receiver:          {}",
+        admin.protocol_fee_receiver::<DryRun>().await?
+    );
+    Ok(())
+}
@@ -1,21 +1,17 @@
-use std::{
-    collections::{hash_map::Entry, HashMap},
-    str::FromStr,
-};
+use std::{collections::HashMap, str::FromStr};
 
 use alloy_primitives::{Address, U256};
 use alloy_provider::{network::AnyNetwork, Provider};
 use alloy_signer_local::PrivateKeySigner;
 use alloy_transport::BoxTransport;
-use anyhow::anyhow;
+use anyhow::{anyhow, bail, Result};
 use serde::{Deserialize, Serialize};
-use shielder_account::{ShielderAccount, Token};
-use shielder_circuits::poseidon::off_circuit::hash;
+use shielder_account::{secrets, ShielderAccount, Token};
 use shielder_contract::{
-    providers::create_simple_provider, ConnectionPolicy, ShielderContractError, ShielderUser,
+    note_tree::NoteTree, providers::create_simple_provider, ConnectionPolicy,
+    ShielderContractError, ShielderUser,
 };
 use tracing::{debug, warn};
-use type_conversions::{address_to_field, field_to_u256, u256_to_field};
 
 /// The URL of the relayer RPC.
 #[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
@@ -32,19 +28,15 @@ impl RelayerRpcUrl {
         format!("{}/health", self.base_url)
     }
 
-    pub fn relay_url(&self) -> String {
-        format!("{}/relay", self.base_url)
-    }
-
-    pub fn fees_url(&self) -> String {
-        format!("{}/quote_fees", self.base_url)
+    pub fn base_url(&self) -> &str {
+        &self.base_url
     }
 
-    pub fn fee_address_url(&self) -> String {
-        format!("{}/fee_address", self.base_url)
+    pub fn relay_url(&self) -> String {
+        format!("{}/relay", self.base_url)
     }
 
-    pub async fn check_connection(&self) -> anyhow::Result<()> {
+    pub async fn check_connection(&self) -> Result<()> {
         let response = reqwest::get(self.healthcheck_url()).await?;
         if response.status().is_success() {
             debug!("Relayer healthcheck succeeded.");
@@ -56,24 +48,80 @@ impl RelayerRpcUrl {
     }
 }
 
+/// Protocol fees used to be cached here. They are now read from the contract before every action
+/// (they can change at any time), but the field is kept so that the existing state files can be
+/// read.
 #[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
 pub struct ProtocolFees {
     pub deposit_fee: Option<U256>,
     pub withdraw_fee: Option<U256>,
 }
 
+/// All the shielded accounts kept for a single token. Accounts are indexed by their
+/// `account_nonce`, i.e. the position in the list. Exactly one of them is selected and used for
+/// contract interactions.
+#[derive(Clone, Eq, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "TokenAccountsRepr")]
+pub struct TokenAccounts {
+    accounts: Vec<ShielderAccount>,
+    selected: usize,
+}
+
+/// Before multiple accounts per token were supported, a single `ShielderAccount` was kept.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TokenAccountsRepr {
+    Current {
+        accounts: Vec<ShielderAccount>,
+        selected: usize,
+    },
+    Legacy(ShielderAccount),
+}
+
+impl From<TokenAccountsRepr> for TokenAccounts {
+    fn from(repr: TokenAccountsRepr) -> Self {
+        match repr {
+            TokenAccountsRepr::Current { accounts, selected } => Self { accounts, selected },
+            TokenAccountsRepr::Legacy(account) => Self {
+                accounts: vec![account],
+                selected: 0,
+            },
+        }
+    }
+}
+
+impl TokenAccounts {
+    /// The currently selected account.
+    pub fn selected(&self) -> &ShielderAccount {
+        &self.accounts[self.selected]
+    }
+
+    /// The index of the currently selected account.
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    /// All the accounts, in the order of their `account_nonce`.
+    pub fn all(&self) -> &[ShielderAccount] {
+        &self.accounts
+    }
+}
+
 /// Application info that is kept locally.
 ///
 /// WARNING: You SHOULD NOT use `Self::Default` in production, as this will set the seed to
 /// zero, which is insecure and might get in conflict with other accounts (similarly set up)
 #[derive(Clone, Eq, PartialEq, Debug, Default, Deserialize, Serialize)]
 pub struct AppState {
-    pub accounts: HashMap<Address, ShielderAccount>,
+    pub accounts: HashMap<Address, TokenAccounts>,
     pub node_rpc_url: String,
     pub contract_address: Address,
     pub relayer_rpc_url: RelayerRpcUrl,
     pub signing_key: String,
     pub protocol_fees: ProtocolFees,
+    /// Local mirror of the contract's note tree, used to compute merkle paths.
+    #[serde(default)]
+    pub note_tree: NoteTree,
 }
 
 impl AppState {
@@ -90,22 +138,103 @@ impl AppState {
         }
     }
 
-    /// If the account for `token` does not exist, create a new one. For ZK ID use either the
-    /// provided `zkid_seed` or the default one derived from the signing key.
-    pub fn ensure_account_exist(&mut self, token: Token, zkid_seed: Option<U256>) {
-        let zkid_seed = zkid_seed.unwrap_or_else(|| self.default_zkid_seed(token));
-        if let Entry::Vacant(e) = self.accounts.entry(token.address()) {
-            e.insert(ShielderAccount::new(zkid_seed, token));
+    /// If there is no account for `token`, create the first one (with `account_nonce` 0). For ZK ID
+    /// use either the provided `zkid_seed` or the default one derived from the signing key.
+    pub async fn ensure_account_exist(
+        &mut self,
+        token: Token,
+        zkid_seed: Option<U256>,
+    ) -> Result<()> {
+        if !self.accounts.contains_key(&token.address()) {
+            self.create_account(token, zkid_seed).await?;
+        }
+        Ok(())
+    }
+
+    /// Create a new account for `token` with the next free `account_nonce` and select it. For ZK ID
+    /// use either the provided `zkid_seed` or the default one derived from the signing key and the
+    /// `account_nonce` (for the next accounts the node is asked for the chain id). Returns the
+    /// index of the new account.
+    pub async fn create_account(&mut self, token: Token, zkid_seed: Option<U256>) -> Result<usize> {
+        let account_nonce = self
+            .accounts
+            .get(&token.address())
+            .map_or(0, |accounts| accounts.accounts.len());
+        let zkid_seed = match (zkid_seed, account_nonce) {
+            (Some(zkid_seed), _) => zkid_seed,
+            (None, 0) => self.default_zkid_seed(0, token, 0),
+            (None, account_nonce) => {
+                let chain_id = self.create_simple_provider().await?.get_chain_id().await?;
+                self.default_zkid_seed(chain_id, token, account_nonce as u32)
+            }
+        };
+
+        let accounts = self.accounts.entry(token.address()).or_default();
+        accounts
+            .accounts
+            .push(ShielderAccount::new(zkid_seed, token));
+        accounts.selected = account_nonce;
+        Ok(account_nonce)
+    }
+
+    /// Make sure that accounts up to `index` (inclusive) exist for `token`.
+    pub async fn ensure_accounts_up_to(&mut self, token: Token, index: usize) -> Result<()> {
+        while self
+            .accounts
+            .get(&token.address())
+            .is_none_or(|accounts| accounts.accounts.len() <= index)
+        {
+            self.create_account(token, None).await?;
+        }
+        Ok(())
+    }
+
+    /// Select the account with `index` to be used for `token`.
+    pub fn select_account(&mut self, token: Token, index: usize) -> Result<()> {
+        let Some(accounts) = self.accounts.get_mut(&token.address()) else {
+            bail!("No accounts for token {token:?}");
+        };
+        if index >= accounts.accounts.len() {
+            bail!(
+                "Account {index} does not exist for token {token:?} (there are {})",
+                accounts.accounts.len()
+            );
         }
+        accounts.selected = index;
+        Ok(())
+    }
+
+    /// The currently selected account for `token`.
+    ///
+    /// Panics if there is no account for `token`.
+    pub fn account(&self, token: Token) -> &ShielderAccount {
+        self.accounts[&token.address()].selected()
+    }
+
+    /// The currently selected account for `token` (mutable).
+    ///
+    /// Panics if there is no account for `token`.
+    pub fn account_mut(&mut self, token: Token) -> &mut ShielderAccount {
+        let accounts = self
+            .accounts
+            .get_mut(&token.address())
+            .expect("Account must exist");
+        &mut accounts.accounts[accounts.selected]
     }
 
-    fn default_zkid_seed(&self, token: Token) -> U256 {
-        let seed =
+    /// The account with `index` for `token` (mutable), if it exists.
+    pub fn account_at_mut(&mut self, token: Token, index: usize) -> Option<&mut ShielderAccount> {
+        self.accounts
+            .get_mut(&token.address())
+            .and_then(|accounts| accounts.accounts.get_mut(index))
+    }
+
+    /// Derived with the shared [`secrets::derive_token_id`]: the first account keeps the original
+    /// derivation (so that existing states can still be recovered) and doesn't need `chain_id`.
+    fn default_zkid_seed(&self, chain_id: u64, token: Token, account_nonce: u32) -> U256 {
+        let private_key =
             U256::from_str(&self.signing_key).expect("Invalid key format - cannot cast to U256");
-        field_to_u256(hash(&[
-            u256_to_field(seed),
-            address_to_field(token.address()),
-        ]))
+        secrets::derive_token_id(private_key, chain_id, token.address(), account_nonce)
     }
 
     pub fn display_app_config(&self) -> String {
@@ -122,21 +251,105 @@ Depositor signing key: {}",
         )
     }
 
+    pub fn signer(&self) -> PrivateKeySigner {
+        PrivateKeySigner::from_str(&self.signing_key)
+            .expect("Invalid key format - cannot cast to PrivateKeySigner")
+    }
+
     pub fn create_shielder_user(&self) -> ShielderUser {
-        let signer = PrivateKeySigner::from_str(&self.signing_key)
-            .expect("Invalid key format - cannot cast to PrivateKeySigner");
         ShielderUser::new(
             self.contract_address,
             ConnectionPolicy::OnDemand {
                 rpc_url: self.node_rpc_url.clone(),
-                signer,
+                signer: self.signer(),
             },
         )
     }
 
+    /// Bring the local note tree up to date with the contract. If the tree turns out to have
+    /// diverged from the chain (e.g. because of a reorg), it is rebuilt from scratch.
+    pub async fn sync_note_tree(&mut self) -> Result<(), ShielderContractError> {
+        let provider = self.create_simple_provider().await?;
+        match self.note_tree.sync(&provider, self.contract_address).await {
+            Err(
+                err @ (ShielderContractError::NoteTreeRootMismatch { .. }
+                | ShielderContractError::NoteTreeDiverged { .. }),
+            ) => {
+                warn!("{err}. Rebuilding the note tree.");
+                self.note_tree = NoteTree::default();
+                self.note_tree
+                    .sync(&provider, self.contract_address)
+                    .await?;
+            }
+            result => {
+                result?;
+            }
+        }
+        debug!(
+            "Note tree synced up to block {:?} ({} notes).",
+            self.note_tree.synced_block(),
+            self.note_tree.len()
+        );
+        Ok(())
+    }
+
     pub async fn create_simple_provider(
         &self,
     ) -> Result<impl Provider<BoxTransport, AnyNetwork>, ShielderContractError> {
         create_simple_provider(&self.node_rpc_url).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, U256};
+    use shielder_account::{secrets, ShielderAccount, Token};
+    use shielder_circuits::{poseidon::off_circuit::hash, Fr};
+    use type_conversions::{address_to_field, field_to_u256, u256_to_field};
+
+    use super::{AppState, TokenAccounts};
+
+    const SIGNING_KEY: &str = "0x2b6ff73c42d4be0a2e3cd4bb5fbdd6eb6b2f4e5dc2bd19e5bd3ba7e8e0b5bd42";
+
+    #[test]
+    fn legacy_single_account_is_loaded() {
+        let account = ShielderAccount::new(U256::from(1), Token::Native);
+        let legacy = serde_json::to_string(&account).unwrap();
+
+        let accounts: TokenAccounts = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(accounts.all(), &[account]);
+        assert_eq!(accounts.selected_index(), 0);
+
+        let current = serde_json::to_string(&accounts).unwrap();
+        assert_eq!(
+            serde_json::from_str::<TokenAccounts>(&current).unwrap(),
+            accounts
+        );
+    }
+
+    #[test]
+    fn zkid_seeds_match_shared_derivation() {
+        let app_state = AppState::new(SIGNING_KEY);
+        let private_key: U256 = SIGNING_KEY.parse().unwrap();
+        let token = Token::ERC20(Address::repeat_byte(7));
+
+        // The first account keeps the derivation from before multiple accounts were supported.
+        let original = field_to_u256(hash(&[
+            u256_to_field::<Fr>(private_key),
+            address_to_field(token.address()),
+        ]));
+        assert_eq!(app_state.default_zkid_seed(1, token, 0), original);
+        assert_eq!(app_state.default_zkid_seed(2, token, 0), original);
+
+        for account_nonce in 0..3 {
+            assert_eq!(
+                app_state.default_zkid_seed(1, token, account_nonce),
+                secrets::derive_token_id(private_key, 1, token.address(), account_nonce)
+            );
+        }
+        assert_eq!(
+            app_state.default_zkid_seed(1, token, 2),
+            secrets::derive_token_id(secrets::derive_id(private_key, 1, 2), 1, token.address(), 0)
+        );
+    }
+}
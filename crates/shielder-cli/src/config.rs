@@ -106,14 +106,37 @@ pub enum StateWriteCommand {
         token: Token,
         /// Optional seed for the ZK ID. If not provided, will be derived from the private key.
         zkid_seed: Option<U256>,
+        /// Index (account nonce) of the account to recover. The first account is used by default.
+        #[clap(long)]
+        account_index: Option<usize>,
+    },
+    /// Create a new local account for the token and select it. The account has to be registered
+    /// on-chain with `new-account` (or `new-account-erc20`) afterwards.
+    CreateAccount {
+        /// Token of the account.
+        #[clap(value_parser = parsing::parse_token)]
+        token: Token,
+        /// Optional seed for the ZK ID. If not provided, will be derived from the private key and
+        /// the account index.
+        zkid_seed: Option<U256>,
+    },
+    /// Select the account to be used for the token.
+    SelectAccount {
+        /// Token of the account.
+        #[clap(value_parser = parsing::parse_token)]
+        token: Token,
+        /// Index (account nonce) of the account.
+        index: usize,
     },
 }
 
 #[derive(Clone, Eq, PartialEq, Debug, Subcommand)]
 pub enum StateReadCommand {
-    /// Display account details.
+    /// Display details of the selected accounts.
     DisplayAccount,
-    /// Display full account history.
+    /// List all the accounts, for every token.
+    ListAccounts,
+    /// Display full history of the selected accounts.
     History,
     /// Display application configuration.
     AppConfig,
@@ -61,6 +61,7 @@ async fn perform_state_write_action(
         StateWriteCommand::ContractAddress { address } => {
             info!("Setting contract address to {address}");
             app_state.contract_address = address;
+            app_state.note_tree = Default::default();
         }
         StateWriteCommand::RelayerUrl { url } => {
             let relayer_rpc_url = RelayerRpcUrl::new(url.clone());
@@ -69,8 +70,20 @@ async fn perform_state_write_action(
             app_state.relayer_rpc_url = relayer_rpc_url;
         }
         // for now we support only native recovery
-        StateWriteCommand::RecoverState { token, zkid_seed } => {
-            recover_state(app_state, token, zkid_seed).await?;
+        StateWriteCommand::RecoverState {
+            token,
+            zkid_seed,
+            account_index,
+        } => {
+            recover_state(app_state, token, zkid_seed, account_index).await?;
+        }
+        StateWriteCommand::CreateAccount { token, zkid_seed } => {
+            let index = app_state.create_account(token, zkid_seed).await?;
+            info!("Created account {index} for {token:?}");
+        }
+        StateWriteCommand::SelectAccount { token, index } => {
+            app_state.select_account(token, index)?;
+            info!("Selected account {index} for {token:?}");
         }
     };
     Ok(())
@@ -79,13 +92,25 @@ async fn perform_state_write_action(
 fn perform_state_read_action(app_state: &AppState, command: StateReadCommand) -> Result<()> {
     match command {
         StateReadCommand::DisplayAccount => {
-            for account in app_state.accounts.values() {
-                println!("{}", account)
+            for accounts in app_state.accounts.values() {
+                println!("{}", accounts.selected())
+            }
+        }
+        StateReadCommand::ListAccounts => {
+            for accounts in app_state.accounts.values() {
+                for (index, account) in accounts.all().iter().enumerate() {
+                    let marker = if index == accounts.selected_index() {
+                        "*"
+                    } else {
+                        " "
+                    };
+                    println!("{marker} [{index}] {account}")
+                }
             }
         }
         StateReadCommand::History => {
-            for account in app_state.accounts.values() {
-                println!("{:#?}", account.history)
+            for accounts in app_state.accounts.values() {
+                println!("{:#?}", accounts.selected().history)
             }
         }
         StateReadCommand::AppConfig => {
@@ -179,7 +204,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let mut app_state = get_app_state(&cli_config.state_file, &password)?;
 
         if let Some(token) = cli_config.command.token() {
-            app_state.ensure_account_exist(token, cli_config.command.zkid_seed());
+            app_state
+                .ensure_account_exist(token, cli_config.command.zkid_seed())
+                .await?;
         }
 
         match cli_config.command {
@@ -2,7 +2,10 @@ use alloy_primitives::U256;
 use anyhow::Result;
 use shielder_account::Token;
 use shielder_circuits::poseidon::off_circuit::hash;
-use shielder_contract::{providers::create_simple_provider, recovery::get_shielder_action};
+use shielder_contract::{
+    providers::create_simple_provider, recovery::get_shielder_events_since_nullifier,
+};
+use tracing::info;
 use type_conversions::{field_to_u256, u256_to_field};
 
 use crate::app_state::AppState;
@@ -11,28 +14,39 @@ pub async fn recover_state(
     app_state: &mut AppState,
     token: Token,
     zkid_seed: Option<U256>,
+    account_index: Option<usize>,
 ) -> Result<()> {
     let shielder_user = app_state.create_shielder_user();
-    app_state.ensure_account_exist(token, zkid_seed);
-    let AppState {
-        accounts,
-        node_rpc_url,
-        ..
-    } = app_state;
-    let provider = create_simple_provider(node_rpc_url).await?;
+    let account_index = account_index.unwrap_or(0);
+    if app_state.account_at_mut(token, account_index).is_none() {
+        if account_index > 0 {
+            app_state
+                .ensure_accounts_up_to(token, account_index - 1)
+                .await?;
+        }
+        app_state.create_account(token, zkid_seed).await?;
+    }
+    app_state.select_account(token, account_index)?;
+    let provider = create_simple_provider(&app_state.node_rpc_url).await?;
+    let contract_address = app_state.contract_address;
 
-    let account = accounts
-        .get_mut(&token.address())
+    let account = app_state
+        .account_at_mut(token, account_index)
         .expect("We have just ensured the account exists");
 
-    loop {
-        let expected_nullifier = account.previous_nullifier();
-        let expected_nullifier_hash = field_to_u256(hash(&[u256_to_field(expected_nullifier)]));
+    // The next action of the account spends the hash of its previous nullifier. Every later action
+    // must have happened in the same block or afterwards, so we only need logs from that point on.
+    let expected_nullifier = account.previous_nullifier();
+    let expected_nullifier_hash = field_to_u256(hash(&[u256_to_field(expected_nullifier)]));
+    let events = get_shielder_events_since_nullifier(
+        &provider,
+        &shielder_user,
+        contract_address,
+        expected_nullifier_hash,
+    )
+    .await?;
 
-        match get_shielder_action(&provider, &shielder_user, expected_nullifier_hash).await? {
-            Some(action) => account.register_action(action),
-            None => break,
-        }
-    }
+    let recovered = account.register_events(events);
+    info!("Recovered {recovered} actions for account {account_index}");
     Ok(())
 }
@@ -1,17 +1,39 @@
-use alloy_primitives::{
-    private::rand::{rngs::OsRng, Rng},
-    U256,
-};
+use std::sync::Arc;
+
+use anyhow::Result;
 pub use deposit::deposit;
 pub use new_account::new_account;
+use shielder_account::Token;
+use shielder_client::{ClientConfig, ShielderClient};
 pub use withdraw::withdraw;
 
+use crate::{app_state::AppState, shielder_ops::pk::get_proving_keys};
+
 mod deposit;
 mod new_account;
 mod pk;
 mod withdraw;
 
-fn get_mac_salt() -> U256 {
-    let mut rng = OsRng;
-    U256::from_limbs([rng.gen(), rng.gen(), rng.gen(), rng.gen()])
+/// `ShielderClient` acting on the selected account for `token`, starting from the local note tree.
+fn shielder_client(app_state: &AppState, token: Token) -> Result<ShielderClient> {
+    let relayer_url = app_state.relayer_rpc_url.base_url();
+    let config = ClientConfig {
+        rpc_url: app_state.node_rpc_url.clone(),
+        contract_address: app_state.contract_address,
+        relayer_url: (!relayer_url.is_empty()).then(|| relayer_url.to_string()),
+    };
+    Ok(ShielderClient::new(
+        config,
+        app_state.signer(),
+        app_state.account(token).clone(),
+        Arc::new(get_proving_keys()?),
+    )
+    .with_note_tree(app_state.note_tree.clone()))
+}
+
+/// Keep the account and the note tree from `client` in the local state. Done also after a failed
+/// action, as the note tree might have been synced before the failure.
+fn save_client(app_state: &mut AppState, token: Token, client: ShielderClient) {
+    app_state.note_tree = client.note_tree().clone();
+    *app_state.account_mut(token) = client.into_account();
 }
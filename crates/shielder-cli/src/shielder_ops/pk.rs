@@ -11,6 +11,7 @@ use shielder_circuits::{
     withdraw::WithdrawCircuit,
     Params as _, MAX_K,
 };
+use shielder_client::{ProvingEquipment, ProvingKeys};
 use tracing::debug;
 
 const NEW_ACCOUNT_PK_FILE: &str = "~/shielder-cli/new_account_pk";
@@ -60,9 +61,18 @@ impl CircuitType {
     }
 }
 
-pub fn get_proving_equipment(circuit_type: CircuitType) -> Result<(Params, ProvingKey)> {
+/// Proving equipment for all the circuits. Generated (and saved) on the first use.
+pub fn get_proving_keys() -> Result<ProvingKeys> {
     let full_params = get_params()?;
-    get_equipment(circuit_type, full_params)
+    let equipment = |circuit_type| {
+        get_equipment(circuit_type, full_params.clone())
+            .map(|(params, pk)| ProvingEquipment { params, pk })
+    };
+    Ok(ProvingKeys {
+        new_account: equipment(CircuitType::NewAccount)?,
+        deposit: equipment(CircuitType::Deposit)?,
+        withdraw: equipment(CircuitType::Withdraw)?,
+    })
 }
 
 fn get_params() -> Result<Params> {
@@ -1,12 +1,8 @@
-use std::{
-    fs,
-    fs::File,
-    path::{Path, PathBuf},
-};
+use std::{fs, fs::File, path::PathBuf};
 
 use anyhow::{anyhow, bail, Result};
-use content_encryption::{decrypt_to_string, encrypt};
-use tracing::debug;
+use content_encryption::{decrypt_to_string, encrypt, is_legacy};
+use tracing::{debug, info};
 
 use crate::app_state::AppState;
 
@@ -34,11 +30,20 @@ pub fn save_app_state(app_state: &AppState, path: &PathBuf, password: &str) -> R
 }
 
 /// Read `AppState` from `path` (decrypting the content with `password`).
-fn read_from(path: &Path, password: &str) -> Result<AppState> {
+///
+/// A file encrypted in the legacy format (with a constant salt and nonce) is encrypted again and
+/// saved right away.
+fn read_from(path: &PathBuf, password: &str) -> Result<AppState> {
     let file_content = fs::read(path).map_err(|e| anyhow!("Failed to read file content: {e}"))?;
     let decrypted_content = decrypt_to_string(&file_content, password.as_bytes())?;
-    serde_json::from_str::<AppState>(&decrypted_content)
-        .map_err(|e| anyhow!("Failed to deserialize application state: {e}"))
+    let app_state = serde_json::from_str::<AppState>(&decrypted_content)
+        .map_err(|e| anyhow!("Failed to deserialize application state: {e}"))?;
+
+    if is_legacy(&file_content) {
+        info!("Migrating the state file {path:?} to the current encryption format.");
+        save_app_state(&app_state, path, password)?;
+    }
+    Ok(app_state)
 }
 
 /// Create a new `AppState`, save it to `path` and return it.
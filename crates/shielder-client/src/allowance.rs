@@ -0,0 +1,61 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use alloy_primitives::{Address, U256};
+use alloy_signer_local::PrivateKeySigner;
+use shielder_contract::{
+    call_type::{Call, DryRun},
+    permit::{permit_domain, sign_permit, Permit},
+    ShielderContract::PermitSignature,
+    ShielderUser,
+};
+use tracing::info;
+
+use crate::error::ClientResult;
+
+/// How long a permit signature stays valid. It has to cover proof generation.
+const PERMIT_VALIDITY: Duration = Duration::from_secs(60 * 60);
+
+/// Make sure that the Shielder contract will be able to transfer `amount` of `token` from `user`
+/// in the upcoming deposit.
+///
+/// If the current allowance is too low and the token supports EIP-2612, a permit for exactly
+/// `amount` is signed and returned - it must be passed to the deposit call, which grants the
+/// allowance and spends it in the same transaction. Otherwise, the allowance is set to unlimited
+/// with `approve`, so that the following deposits don't need any extra transaction. `signer` must
+/// be the signer of `user`.
+pub async fn prepare_allowance(
+    user: &ShielderUser,
+    signer: &PrivateKeySigner,
+    contract_address: Address,
+    chain_id: u64,
+    token: Address,
+    amount: U256,
+) -> ClientResult<Option<PermitSignature>> {
+    let owner = user.address();
+    let allowance = user
+        .erc20_allowance::<DryRun>(token, owner, contract_address)
+        .await?;
+    if allowance >= amount {
+        return Ok(None);
+    }
+
+    if let Some(domain) = permit_domain(user, token, chain_id).await {
+        let deadline = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            + PERMIT_VALIDITY;
+        let permit = Permit {
+            owner,
+            spender: contract_address,
+            value: amount,
+            nonce: user.erc20_permit_nonce::<DryRun>(token, owner).await?,
+            deadline: U256::from(deadline.as_secs()),
+        };
+        return Ok(Some(sign_permit(signer, &domain, &permit)?));
+    }
+
+    info!("Token does not support permits, approving unlimited allowance");
+    user.approve_erc20::<Call>(token, contract_address, U256::MAX)
+        .await?;
+    Ok(None)
+}
@@ -0,0 +1,94 @@
+use alloy_primitives::U256;
+use shielder_setup::protocol_fee::compute_protocol_fee_from_net;
+
+/// Amounts of a single action, computed from the net amount the user asked for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ActionAmounts {
+    /// The amount passed to the contract. It includes all the fees.
+    pub amount: U256,
+    /// The protocol fee included in `amount`.
+    pub protocol_fee: U256,
+}
+
+impl ActionAmounts {
+    /// New account or deposit, after which `net_amount` ends up in the account. The protocol fee is
+    /// added on top of it.
+    pub fn deposit(net_amount: U256, protocol_fee_bps: U256) -> Self {
+        let protocol_fee = compute_protocol_fee_from_net(net_amount, protocol_fee_bps);
+        Self {
+            amount: net_amount + protocol_fee,
+            protocol_fee,
+        }
+    }
+
+    /// Withdrawal after which the recipient gets `net_amount` and the relayer gets
+    /// `relayer_fee`. The contract charges the protocol fee on the whole amount, so the relayer
+    /// fee is subject to it as well.
+    pub fn withdraw(net_amount: U256, relayer_fee: U256, protocol_fee_bps: U256) -> Self {
+        let without_protocol_fee = net_amount + relayer_fee;
+        let protocol_fee = compute_protocol_fee_from_net(without_protocol_fee, protocol_fee_bps);
+        Self {
+            amount: without_protocol_fee + protocol_fee,
+            protocol_fee,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use shielder_setup::protocol_fee::compute_protocol_fee_from_gross;
+
+    use super::ActionAmounts;
+
+    #[test]
+    fn withdraw_fees_are_added_on_top_of_net_amount() {
+        let amounts = ActionAmounts::withdraw(U256::from(10_000), U256::from(100), U256::from(100));
+
+        // 1% of 10_203 (rounded up) is 103, which leaves exactly 10_100 for the recipient and the
+        // relayer.
+        assert_eq!(
+            amounts,
+            ActionAmounts {
+                amount: U256::from(10_203),
+                protocol_fee: U256::from(103),
+            }
+        );
+    }
+
+    #[test]
+    fn withdraw_without_protocol_fee_only_adds_relayer_fee() {
+        let amounts = ActionAmounts::withdraw(U256::from(5_000), U256::from(7), U256::ZERO);
+
+        assert_eq!(amounts.amount, U256::from(5_007));
+        assert_eq!(amounts.protocol_fee, U256::ZERO);
+    }
+
+    #[test]
+    fn protocol_fee_matches_the_one_computed_by_contract() {
+        for protocol_fee_bps in [1, 30, 70, 2_500] {
+            let protocol_fee_bps = U256::from(protocol_fee_bps);
+            for net_amount in (1..2_000).step_by(37).chain([1_000_000_007]) {
+                let net_amount = U256::from(net_amount);
+                let relayer_fee = U256::from(13);
+
+                let withdraw = ActionAmounts::withdraw(net_amount, relayer_fee, protocol_fee_bps);
+                assert_eq!(
+                    withdraw.protocol_fee,
+                    compute_protocol_fee_from_gross(withdraw.amount, protocol_fee_bps)
+                );
+                assert_eq!(
+                    withdraw.amount - withdraw.protocol_fee - relayer_fee,
+                    net_amount
+                );
+
+                let deposit = ActionAmounts::deposit(net_amount, protocol_fee_bps);
+                assert_eq!(
+                    deposit.protocol_fee,
+                    compute_protocol_fee_from_gross(deposit.amount, protocol_fee_bps)
+                );
+                assert_eq!(deposit.amount - deposit.protocol_fee, net_amount);
+            }
+        }
+    }
+}
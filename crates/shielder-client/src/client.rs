@@ -0,0 +1,451 @@
+use std::{sync::Arc, time::Duration};
+
+use alloy_primitives::{
+    private::rand::{rngs::OsRng, Rng},
+    Address, BlockHash, Bytes, TxHash, U256,
+};
+use alloy_provider::{network::AnyNetwork, Provider};
+use alloy_signer_local::PrivateKeySigner;
+use alloy_transport::BoxTransport;
+use shielder_account::{
+    call_data::{
+        DepositCallType, DepositExtra, NewAccountCallExtra, NewAccountCallType, WithdrawCallType,
+        WithdrawExtra,
+    },
+    ShielderAccount, ShielderAction, Token,
+};
+use shielder_circuits::poseidon::off_circuit::hash;
+use shielder_contract::{
+    call_type::{Call, DryRun},
+    events::get_event,
+    note_tree::NoteTree,
+    providers::create_simple_provider,
+    recovery::get_shielder_events_since_nullifier,
+    ConnectionPolicy,
+    ShielderContract::{Deposit, NewAccount, PermitSignature, Withdraw},
+    ShielderContractError, ShielderUser,
+};
+use shielder_relayer::{RelayCalldata, RelayQuery};
+use shielder_setup::version::contract_version;
+use tokio::time::sleep;
+use tracing::{debug, info, warn};
+use type_conversions::{field_to_u256, u256_to_field};
+
+use crate::{
+    allowance::prepare_allowance,
+    amounts::ActionAmounts,
+    error::{ClientError, ClientResult},
+    proving::ProvingKeys,
+    relayer::RelayerClient,
+};
+
+/// How many times (every second) to look for the receipt of a relayed transaction.
+const RECEIPT_ATTEMPTS: usize = 5;
+
+/// Where to find the Shielder contract and the relayer.
+#[derive(Clone, Debug)]
+pub struct ClientConfig {
+    pub rpc_url: String,
+    pub contract_address: Address,
+    /// Base URL of the relayer. Only needed for withdrawals.
+    pub relayer_url: Option<String>,
+}
+
+/// A single Shielder account together with everything needed to act on it: the contract
+/// connection, the relayer and the local note tree.
+///
+/// All amounts passed to the actions are net amounts - protocol and relayer fees are computed and
+/// added on top of them.
+pub struct ShielderClient {
+    account: ShielderAccount,
+    signer: PrivateKeySigner,
+    user: ShielderUser,
+    rpc_url: String,
+    contract_address: Address,
+    relayer: Option<RelayerClient>,
+    note_tree: NoteTree,
+    proving_keys: Arc<ProvingKeys>,
+}
+
+impl ShielderClient {
+    /// Create a client for `account`. Transactions are signed (and paid for) by `signer`.
+    pub fn new(
+        config: ClientConfig,
+        signer: PrivateKeySigner,
+        account: ShielderAccount,
+        proving_keys: Arc<ProvingKeys>,
+    ) -> Self {
+        let user = ShielderUser::new(
+            config.contract_address,
+            ConnectionPolicy::OnDemand {
+                rpc_url: config.rpc_url.clone(),
+                signer: signer.clone(),
+            },
+        );
+        Self {
+            account,
+            signer,
+            user,
+            rpc_url: config.rpc_url,
+            contract_address: config.contract_address,
+            relayer: config.relayer_url.map(RelayerClient::new),
+            note_tree: NoteTree::default(),
+            proving_keys,
+        }
+    }
+
+    /// Start from a previously saved note tree instead of syncing it from scratch.
+    pub fn with_note_tree(mut self, note_tree: NoteTree) -> Self {
+        self.note_tree = note_tree;
+        self
+    }
+
+    pub fn account(&self) -> &ShielderAccount {
+        &self.account
+    }
+
+    pub fn note_tree(&self) -> &NoteTree {
+        &self.note_tree
+    }
+
+    pub fn user(&self) -> &ShielderUser {
+        &self.user
+    }
+
+    /// Take the account (e.g. to persist it) and drop the client.
+    pub fn into_account(self) -> ShielderAccount {
+        self.account
+    }
+
+    /// Bring the note tree and the account up to date with the contract. Actions of the account
+    /// that were made elsewhere (e.g. by another instance with the same seed) are registered.
+    /// Returns the number of recovered actions.
+    pub async fn sync(&mut self) -> ClientResult<usize> {
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        self.sync_note_tree(&provider).await?;
+
+        // The next action of the account spends the hash of its previous nullifier. Every later
+        // action must have happened in the same block or afterwards.
+        let nullifier = self.account.previous_nullifier();
+        let nullifier_hash = field_to_u256(hash(&[u256_to_field(nullifier)]));
+        let events = get_shielder_events_since_nullifier(
+            &provider,
+            &self.user,
+            self.contract_address,
+            nullifier_hash,
+        )
+        .await?;
+
+        let recovered = self.account.register_events(events);
+        info!("Recovered {recovered} actions");
+        Ok(recovered)
+    }
+
+    /// Create the account with an initial deposit of `amount`.
+    pub async fn new_account(&mut self, amount: U256, memo: Bytes) -> ClientResult<TxHash> {
+        if self.account.nonce > 0 {
+            return Err(ClientError::AccountAlreadyCreated);
+        }
+        let token = self.account.token;
+        let protocol_fee_bps = self.user.protocol_deposit_fee_bps::<DryRun>().await?;
+        let ActionAmounts {
+            amount,
+            protocol_fee,
+        } = ActionAmounts::deposit(amount, protocol_fee_bps);
+
+        let extra = NewAccountCallExtra {
+            anonymity_revoker_public_key: self.user.anonymity_revoker_pubkey::<DryRun>().await?,
+            encryption_salt: random_salt(),
+            mac_salt: random_salt(),
+            caller_address: self.user.address(),
+            protocol_fee,
+            memo,
+            permit: self.prepare_allowance(token, amount).await?,
+        };
+        let equipment = &self.proving_keys.new_account;
+        let call = self.account.prepare_call::<NewAccountCallType>(
+            &equipment.params,
+            &equipment.pk,
+            token,
+            amount,
+            &extra,
+        );
+
+        let (tx_hash, block_hash) = match token {
+            Token::Native => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user.new_account_native::<Call>(call, amount).await?
+            }
+            Token::ERC20(_) if call.permit.is_some() => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user
+                    .new_account_erc20_with_permit::<Call>(call)
+                    .await?
+            }
+            Token::ERC20(_) => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user.new_account_erc20::<Call>(call).await?
+            }
+        };
+
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        let event = get_event::<NewAccount>(&provider, tx_hash, block_hash).await?;
+        debug!("New account event: {event:?}");
+
+        self.account.register_action(ShielderAction::new_account(
+            amount,
+            event.newNoteIndex,
+            tx_hash,
+            token,
+            protocol_fee,
+        ));
+        info!("Created new account with {amount} tokens");
+        Ok(tx_hash)
+    }
+
+    /// Deposit `amount` to the existing account.
+    pub async fn deposit(&mut self, amount: U256, memo: Bytes) -> ClientResult<TxHash> {
+        let Some(leaf_index) = self.account.current_leaf_index() else {
+            return Err(ClientError::AccountNotCreated);
+        };
+        let token = self.account.token;
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        self.sync_note_tree(&provider).await?;
+        let (_, merkle_path) = self.note_tree.merkle_path(leaf_index)?;
+
+        let protocol_fee_bps = self.user.protocol_deposit_fee_bps::<DryRun>().await?;
+        let ActionAmounts {
+            amount,
+            protocol_fee,
+        } = ActionAmounts::deposit(amount, protocol_fee_bps);
+
+        let extra = DepositExtra {
+            merkle_path,
+            mac_salt: random_salt(),
+            caller_address: self.user.address(),
+            protocol_fee,
+            memo,
+            permit: self.prepare_allowance(token, amount).await?,
+        };
+        let equipment = &self.proving_keys.deposit;
+        let call = self.account.prepare_call::<DepositCallType>(
+            &equipment.params,
+            &equipment.pk,
+            token,
+            amount,
+            &extra,
+        );
+
+        let (tx_hash, block_hash) = match token {
+            Token::Native => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user.deposit_native::<Call>(call, amount).await?
+            }
+            Token::ERC20(_) if call.permit.is_some() => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user.deposit_erc20_with_permit::<Call>(call).await?
+            }
+            Token::ERC20(_) => {
+                let call = call.try_into().expect("Call data matches the token");
+                self.user.deposit_erc20::<Call>(call).await?
+            }
+        };
+
+        let event = get_event::<Deposit>(&provider, tx_hash, block_hash).await?;
+        debug!("Deposit event: {event:?}");
+
+        self.account.register_action(ShielderAction::deposit(
+            amount,
+            event.newNoteIndex,
+            tx_hash,
+            token,
+            protocol_fee,
+        ));
+        info!("Deposited {amount} tokens");
+        Ok(tx_hash)
+    }
+
+    /// Withdraw `amount` to `to` through the relayer. The relayer fee is paid in the account
+    /// token. For ERC20 accounts, `pocket_money` of the native token is additionally sent to `to`
+    /// by the relayer (and paid for in the account token).
+    pub async fn withdraw(
+        &mut self,
+        amount: U256,
+        to: Address,
+        pocket_money: U256,
+        memo: Bytes,
+    ) -> ClientResult<TxHash> {
+        let relayer = self.relayer.clone().ok_or(ClientError::NoRelayer)?;
+        let token = self.account.token;
+        let (query, amounts) = self
+            .build_relay_query(&relayer, amount, to, pocket_money, memo)
+            .await?;
+        let tx_hash = relayer.relay(&query).await?.tx_hash;
+
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        let block_hash = wait_for_block_hash(&provider, tx_hash).await?;
+        let event = get_event::<Withdraw>(&provider, tx_hash, block_hash).await?;
+        debug!("Withdraw event: {event:?}");
+
+        self.account.register_action(ShielderAction::withdraw(
+            amounts.amount,
+            event.newNoteIndex,
+            tx_hash,
+            to,
+            token,
+            amounts.protocol_fee,
+        ));
+        info!("Withdrawn {} tokens", amounts.amount);
+        Ok(tx_hash)
+    }
+
+    /// Prepare (quote and prove) the same withdrawal as `withdraw`, but don't send it. The
+    /// returned query can be sent to the relayer's `/relay` endpoint later - the account is not
+    /// updated, so it must be synced afterwards.
+    pub async fn prepare_withdraw(
+        &mut self,
+        amount: U256,
+        to: Address,
+        pocket_money: U256,
+        memo: Bytes,
+    ) -> ClientResult<RelayQuery> {
+        let relayer = self.relayer.clone().ok_or(ClientError::NoRelayer)?;
+        let (query, _) = self
+            .build_relay_query(&relayer, amount, to, pocket_money, memo)
+            .await?;
+        Ok(query)
+    }
+
+    async fn build_relay_query(
+        &mut self,
+        relayer: &RelayerClient,
+        amount: U256,
+        to: Address,
+        pocket_money: U256,
+        memo: Bytes,
+    ) -> ClientResult<(RelayQuery, ActionAmounts)> {
+        let Some(leaf_index) = self.account.current_leaf_index() else {
+            return Err(ClientError::AccountNotCreated);
+        };
+        let token = self.account.token;
+
+        let quoted_fee = relayer.quote_fees(token, pocket_money).await?;
+        let relayer_fee = quoted_fee.fee_details.total_cost_fee_token;
+        let protocol_fee_bps = self.user.protocol_withdraw_fee_bps::<DryRun>().await?;
+        let amounts = ActionAmounts::withdraw(amount, relayer_fee, protocol_fee_bps);
+
+        let available = self.account.shielded_amount;
+        if amounts.amount > available {
+            return Err(ClientError::InsufficientFunds {
+                required: amounts.amount,
+                available,
+            });
+        }
+
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        self.sync_note_tree(&provider).await?;
+        let (merkle_root, merkle_path) = self.note_tree.merkle_path(leaf_index)?;
+
+        let extra = WithdrawExtra {
+            merkle_path,
+            to,
+            relayer_address: relayer.fee_address().await?,
+            relayer_fee,
+            contract_version: contract_version(),
+            chain_id: U256::from(provider.get_chain_id().await?),
+            mac_salt: random_salt(),
+            pocket_money,
+            protocol_fee: amounts.protocol_fee,
+            memo: memo.clone(),
+        };
+        let equipment = &self.proving_keys.withdraw;
+        let call = self.account.prepare_call::<WithdrawCallType>(
+            &equipment.params,
+            &equipment.pk,
+            token,
+            amounts.amount,
+            &extra,
+        );
+
+        let query = RelayQuery {
+            calldata: RelayCalldata {
+                expected_contract_version: contract_version().to_bytes(),
+                amount: amounts.amount,
+                withdraw_address: to,
+                merkle_root,
+                nullifier_hash: call.old_nullifier_hash,
+                new_note: call.new_note,
+                proof: call.proof,
+                fee_token: token,
+                fee_amount: call.relayer_fee,
+                mac_salt: call.mac_salt,
+                mac_commitment: call.mac_commitment,
+                pocket_money,
+                memo,
+            },
+            quote: quoted_fee.into(),
+        };
+        Ok((query, amounts))
+    }
+
+    /// Sync the local note tree. If it turns out to have diverged from the chain (e.g. because of
+    /// a reorg), it is rebuilt from scratch.
+    async fn sync_note_tree(
+        &mut self,
+        provider: &impl Provider<BoxTransport, AnyNetwork>,
+    ) -> ClientResult<()> {
+        match self.note_tree.sync(provider, self.contract_address).await {
+            Err(
+                err @ (ShielderContractError::NoteTreeRootMismatch { .. }
+                | ShielderContractError::NoteTreeDiverged { .. }),
+            ) => {
+                warn!("{err}. Rebuilding the note tree.");
+                self.note_tree = NoteTree::default();
+                self.note_tree.sync(provider, self.contract_address).await?;
+            }
+            result => {
+                result?;
+            }
+        }
+        Ok(())
+    }
+
+    /// For ERC20 deposits, get the permit to include in the call (if the allowance is missing).
+    async fn prepare_allowance(
+        &self,
+        token: Token,
+        amount: U256,
+    ) -> ClientResult<Option<PermitSignature>> {
+        let Token::ERC20(token) = token else {
+            return Ok(None);
+        };
+        let provider = create_simple_provider(&self.rpc_url).await?;
+        prepare_allowance(
+            &self.user,
+            &self.signer,
+            self.contract_address,
+            provider.get_chain_id().await?,
+            token,
+            amount,
+        )
+        .await
+    }
+}
+
+fn random_salt() -> U256 {
+    let mut rng = OsRng;
+    U256::from_limbs([rng.gen(), rng.gen(), rng.gen(), rng.gen()])
+}
+
+async fn wait_for_block_hash(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    tx_hash: TxHash,
+) -> ClientResult<BlockHash> {
+    for _ in 0..RECEIPT_ATTEMPTS {
+        if let Some(receipt) = provider.get_transaction_receipt(tx_hash).await? {
+            return receipt.block_hash.ok_or(ClientError::NotMined(tx_hash));
+        }
+        sleep(Duration::from_secs(1)).await;
+    }
+    Err(ClientError::NotMined(tx_hash))
+}
@@ -0,0 +1,32 @@
+use alloy_primitives::{TxHash, U256};
+use alloy_transport::TransportError;
+use shielder_contract::ShielderContractError;
+
+/// Errors that can occur when using `ShielderClient`.
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error(transparent)]
+    Contract(#[from] ShielderContractError),
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] TransportError),
+    #[error("Relayer request failed: {0}")]
+    Relayer(#[from] reqwest::Error),
+    #[error("Relayer rejected the request ({status}): {message}")]
+    RelayerRejected { status: u16, message: String },
+    #[error("Relayer returned an invalid fee address: {0}")]
+    InvalidRelayerAddress(String),
+    #[error("No relayer is configured - it is required for withdrawals")]
+    NoRelayer,
+    #[error("Couldn't sign the permit: {0}")]
+    Signing(#[from] alloy_signer::Error),
+    #[error("The account has already been created")]
+    AccountAlreadyCreated,
+    #[error("The account has not been created yet")]
+    AccountNotCreated,
+    #[error("Not enough funds: {required} required, {available} shielded")]
+    InsufficientFunds { required: U256, available: U256 },
+    #[error("Transaction {0} has not been included in any block")]
+    NotMined(TxHash),
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
@@ -0,0 +1,30 @@
+//! High-level client of the Shielder.
+//!
+//! `ShielderClient` wraps a `ShielderAccount`, the contract connection (`ShielderUser`) and the
+//! relayer, and takes care of everything around a Shielder action: fetching protocol fees and the
+//! anonymity revoker key, computing Merkle paths from a local note tree, quoting the relayer,
+//! proving, submitting and registering the action in the account.
+//!
+//! ```ignore
+//! let mut client = ShielderClient::new(config, signer, account, proving_keys);
+//! client.sync().await?;
+//! client.deposit(U256::from(100), Bytes::new()).await?;
+//! client.withdraw(U256::from(50), recipient, U256::ZERO, Bytes::new()).await?;
+//! ```
+//!
+//! Proof generation is CPU-heavy and blocks the current thread - in a server, run the actions on a
+//! dedicated thread or a multi-threaded runtime.
+
+mod allowance;
+mod amounts;
+mod client;
+mod error;
+mod proving;
+mod relayer;
+
+pub use allowance::prepare_allowance;
+pub use amounts::ActionAmounts;
+pub use client::{ClientConfig, ShielderClient};
+pub use error::{ClientError, ClientResult};
+pub use proving::{ProvingEquipment, ProvingKeys};
+pub use relayer::RelayerClient;
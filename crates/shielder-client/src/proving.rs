@@ -0,0 +1,17 @@
+use shielder_circuits::circuits::{Params, ProvingKey};
+
+/// Parameters and proving key of a single circuit.
+pub struct ProvingEquipment {
+    pub params: Params,
+    pub pk: ProvingKey,
+}
+
+/// Proving equipment for all the circuits used by `ShielderClient`.
+///
+/// Generating (or deserializing) the keys is expensive, so it is left to the caller - the same keys
+/// can be shared by many clients.
+pub struct ProvingKeys {
+    pub new_account: ProvingEquipment,
+    pub deposit: ProvingEquipment,
+    pub withdraw: ProvingEquipment,
+}
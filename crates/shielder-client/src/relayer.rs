@@ -0,0 +1,104 @@
+use std::str::FromStr;
+
+use alloy_primitives::{Address, U256};
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use shielder_account::Token;
+use shielder_relayer::{
+    QuoteFeeQuery, QuoteFeeResponse, RelayQuery, RelayResponse, SimpleServiceResponse,
+};
+
+use crate::error::{ClientError, ClientResult};
+
+/// HTTP client of the Shielder relayer.
+#[derive(Clone, Debug)]
+pub struct RelayerClient {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl RelayerClient {
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Check that the relayer is up.
+    pub async fn health(&self) -> ClientResult<()> {
+        let response = self.http.get(self.url("health")).send().await?;
+        check_status(response).await.map(|_| ())
+    }
+
+    /// Get the address to which the relayer fee should be paid.
+    pub async fn fee_address(&self) -> ClientResult<Address> {
+        let response = self.http.get(self.url("fee_address")).send().await?;
+        let address = parse::<SimpleServiceResponse>(response).await?.message;
+        Address::from_str(&address).map_err(|_| ClientError::InvalidRelayerAddress(address))
+    }
+
+    /// Get a signed fee quote for a withdrawal paid in `fee_token`.
+    pub async fn quote_fees(
+        &self,
+        fee_token: Token,
+        pocket_money: U256,
+    ) -> ClientResult<QuoteFeeResponse> {
+        let response = self
+            .http
+            .post(self.url("quote_fees"))
+            .json(&QuoteFeeQuery {
+                fee_token,
+                pocket_money,
+            })
+            .send()
+            .await?;
+        parse(response).await
+    }
+
+    /// Ask the relayer to submit a withdrawal.
+    pub async fn relay(&self, query: &RelayQuery) -> ClientResult<RelayResponse> {
+        let response = self.http.post(self.url("relay")).json(query).send().await?;
+        parse(response).await
+    }
+
+    fn url(&self, endpoint: &str) -> String {
+        format!("{}/{endpoint}", self.base_url)
+    }
+}
+
+async fn check_status(response: Response) -> ClientResult<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    Err(ClientError::RelayerRejected {
+        status: status.as_u16(),
+        message: response.text().await.unwrap_or_default(),
+    })
+}
+
+async fn parse<T: DeserializeOwned>(response: Response) -> ClientResult<T> {
+    Ok(check_status(response).await?.json::<T>().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RelayerClient;
+
+    #[test]
+    fn endpoint_urls_are_built_from_base_url() {
+        let relayer = RelayerClient::new("http://localhost:4141/");
+
+        assert_eq!(relayer.base_url(), "http://localhost:4141");
+        assert_eq!(relayer.url("relay"), "http://localhost:4141/relay");
+        assert_eq!(
+            relayer.url("quote_fees"),
+            "http://localhost:4141/quote_fees"
+        );
+    }
+}
@@ -0,0 +1,166 @@
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_provider::Provider;
+use shielder_setup::shielder_circuits::GrumpkinPointAffine;
+
+use crate::{
+    call_type::CallType,
+    connection::{Connection, ConnectionPolicy, NoProvider, TxOverrides},
+    ContractResult,
+    ShielderContract::{
+        acceptOwnershipCall, ownerCall, pauseCall, pausedCall, pendingOwnerCall,
+        protocolDepositFeeBpsCall, protocolFeeReceiverCall, protocolWithdrawFeeBpsCall,
+        setAnonymityRevokerPubkeyCall, setProtocolDepositFeeBpsCall, setProtocolFeeReceiverCall,
+        setProtocolWithdrawFeeBpsCall, transferOwnershipCall, unpauseCall, upgradeToAndCallCall,
+    },
+};
+
+/// Owner of the Shielder contract. Can pause the contract, change the protocol fees and the
+/// anonymity revoker key, transfer the ownership and upgrade the implementation.
+///
+/// Like `ShielderUser`, this is a thin wrapper around the `Connection` struct. Every operation can
+/// be sent, dry-run or estimated, depending on the `CallType`.
+#[derive(Clone)]
+pub struct ShielderAdmin<Provider = NoProvider> {
+    connection: Connection<Provider>,
+}
+
+impl<P: Provider + Clone> ShielderAdmin<P> {
+    /// Create a new `ShielderAdmin` instance.
+    pub fn new(contract_address: Address, connection_policy: ConnectionPolicy<P>) -> Self {
+        let connection = Connection::new(contract_address, connection_policy);
+        Self { connection }
+    }
+
+    /// Get the address of the admin.
+    pub fn address(&self) -> Address {
+        self.connection.caller_address()
+    }
+
+    /// Return a copy of the admin that sends all transactions with `overrides` applied.
+    pub fn with_overrides(&self, overrides: TxOverrides) -> Self {
+        Self {
+            connection: self.connection.with_overrides(overrides),
+        }
+    }
+
+    /// Stop accepting new accounts, deposits and withdrawals.
+    pub async fn pause<C: CallType<pauseCall>>(&self) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(pauseCall {}).await
+    }
+
+    /// Start accepting new accounts, deposits and withdrawals again.
+    pub async fn unpause<C: CallType<unpauseCall>>(&self) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(unpauseCall {}).await
+    }
+
+    pub async fn paused<C: CallType<pausedCall>>(&self) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(pausedCall {}).await
+    }
+
+    pub async fn protocol_deposit_fee_bps<C: CallType<protocolDepositFeeBpsCall>>(
+        &self,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(protocolDepositFeeBpsCall {})
+            .await
+    }
+
+    pub async fn protocol_withdraw_fee_bps<C: CallType<protocolWithdrawFeeBpsCall>>(
+        &self,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(protocolWithdrawFeeBpsCall {})
+            .await
+    }
+
+    pub async fn set_protocol_deposit_fee_bps<C: CallType<setProtocolDepositFeeBpsCall>>(
+        &self,
+        fee_bps: U256,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(setProtocolDepositFeeBpsCall { _0: fee_bps })
+            .await
+    }
+
+    pub async fn set_protocol_withdraw_fee_bps<C: CallType<setProtocolWithdrawFeeBpsCall>>(
+        &self,
+        fee_bps: U256,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(setProtocolWithdrawFeeBpsCall { _0: fee_bps })
+            .await
+    }
+
+    pub async fn set_protocol_fee_receiver<C: CallType<setProtocolFeeReceiverCall>>(
+        &self,
+        receiver: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(setProtocolFeeReceiverCall {
+                newProtocolFeeReceiver: receiver,
+            })
+            .await
+    }
+
+    pub async fn protocol_fee_receiver<C: CallType<protocolFeeReceiverCall>>(
+        &self,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(protocolFeeReceiverCall {})
+            .await
+    }
+
+    pub async fn set_anonymity_revoker_pubkey<C: CallType<setAnonymityRevokerPubkeyCall>>(
+        &self,
+        pubkey: GrumpkinPointAffine<U256>,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(setAnonymityRevokerPubkeyCall {
+                anonymityRevokerPubkeyX: pubkey.x,
+                anonymityRevokerPubkeyY: pubkey.y,
+            })
+            .await
+    }
+
+    pub async fn owner<C: CallType<ownerCall>>(&self) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(ownerCall {}).await
+    }
+
+    /// Get the address that has been offered the ownership, but has not accepted it yet.
+    pub async fn pending_owner<C: CallType<pendingOwnerCall>>(&self) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(pendingOwnerCall {}).await
+    }
+
+    /// Offer the ownership to `new_owner`. It has to be accepted by `new_owner` with
+    /// `accept_ownership`.
+    pub async fn transfer_ownership<C: CallType<transferOwnershipCall>>(
+        &self,
+        new_owner: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(transferOwnershipCall {
+                newOwner: new_owner,
+            })
+            .await
+    }
+
+    pub async fn accept_ownership<C: CallType<acceptOwnershipCall>>(
+        &self,
+    ) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(acceptOwnershipCall {}).await
+    }
+
+    /// Upgrade the proxy to `new_implementation` (UUPS) and call it with `data` (unless empty).
+    pub async fn upgrade_to_and_call<C: CallType<upgradeToAndCallCall>>(
+        &self,
+        new_implementation: Address,
+        data: Bytes,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call::<C, _>(upgradeToAndCallCall {
+                newImplementation: new_implementation,
+                data,
+            })
+            .await
+    }
+}
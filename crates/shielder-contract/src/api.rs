@@ -3,14 +3,17 @@ use alloy_provider::Provider;
 use alloy_sol_types::SolCall;
 
 #[cfg(feature = "erc20")]
-use crate::erc20::ERC20::{allowanceCall, approveCall};
+use crate::erc20::ERC20::{
+    allowanceCall, approveCall, eip712DomainCall, nameCall, noncesCall, DOMAIN_SEPARATORCall,
+};
 use crate::{
     call_type::CallType,
-    connection::{Connection, ConnectionPolicy, NoProvider},
+    connection::{Connection, ConnectionPolicy, NoProvider, TxOverrides},
     ContractResult,
     ShielderContract::{
-        anonymityRevokerPubkeyCall, depositERC20Call, depositNativeCall, getMerklePathCall,
-        newAccountERC20Call, newAccountNativeCall, nullifiersCall, protocolDepositFeeBpsCall,
+        anonymityRevokerPubkeyCall, depositERC20Call, depositERC20WithPermitCall,
+        depositNativeCall, getMerklePathCall, newAccountERC20Call, newAccountERC20WithPermitCall,
+        newAccountNativeCall, nullifiersCall, protocolDepositFeeBpsCall,
         protocolWithdrawFeeBpsCall, withdrawERC20Call, withdrawNativeCall,
     },
 };
@@ -37,6 +40,14 @@ impl<P: Provider + Clone> ShielderUser<P> {
         self.connection.caller_address()
     }
 
+    /// Return a copy of the user that sends all transactions with `overrides` applied (e.g. to
+    /// replace a pending transaction).
+    pub fn with_overrides(&self, overrides: TxOverrides) -> Self {
+        Self {
+            connection: self.connection.with_overrides(overrides),
+        }
+    }
+
     /// Create new account.
     pub async fn new_account_native<C: CallType<newAccountNativeCall>>(
         &self,
@@ -54,6 +65,15 @@ impl<P: Provider + Clone> ShielderUser<P> {
         self.connection.call::<C, _>(call).await
     }
 
+    /// Create new account with ERC20 token, granting the allowance with the EIP-2612 permit
+    /// included in `call` (see `permit::sign_permit`).
+    pub async fn new_account_erc20_with_permit<C: CallType<newAccountERC20WithPermitCall>>(
+        &self,
+        call: newAccountERC20WithPermitCall,
+    ) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(call).await
+    }
+
     /// Deposit native currency into the contract.
     pub async fn deposit_native<C: CallType<depositNativeCall>>(
         &self,
@@ -71,6 +91,15 @@ impl<P: Provider + Clone> ShielderUser<P> {
         self.connection.call::<C, _>(call).await
     }
 
+    /// Deposit ERC20 token into the contract, granting the allowance with the EIP-2612 permit
+    /// included in `call` (see `permit::sign_permit`).
+    pub async fn deposit_erc20_with_permit<C: CallType<depositERC20WithPermitCall>>(
+        &self,
+        call: depositERC20WithPermitCall,
+    ) -> ContractResult<C::Result> {
+        self.connection.call::<C, _>(call).await
+    }
+
     /// Withdraw native currency from the contract.
     pub async fn withdraw_native<C: CallType<withdrawNativeCall>>(
         &self,
@@ -160,4 +189,47 @@ impl<P: Provider + Clone> ShielderUser<P> {
             .call_with_address::<C, _>(contract_address, allowanceCall { owner, spender })
             .await
     }
+
+    /// Get the next EIP-2612 permit nonce of `owner`.
+    #[cfg(feature = "erc20")]
+    pub async fn erc20_permit_nonce<C: CallType<noncesCall>>(
+        &self,
+        contract_address: Address,
+        owner: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call_with_address::<C, _>(contract_address, noncesCall { owner })
+            .await
+    }
+
+    #[cfg(feature = "erc20")]
+    pub async fn erc20_domain_separator<C: CallType<DOMAIN_SEPARATORCall>>(
+        &self,
+        contract_address: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call_with_address::<C, _>(contract_address, DOMAIN_SEPARATORCall {})
+            .await
+    }
+
+    /// Get the EIP-712 domain of the token (EIP-5267).
+    #[cfg(feature = "erc20")]
+    pub async fn erc20_eip712_domain<C: CallType<eip712DomainCall>>(
+        &self,
+        contract_address: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call_with_address::<C, _>(contract_address, eip712DomainCall {})
+            .await
+    }
+
+    #[cfg(feature = "erc20")]
+    pub async fn erc20_name<C: CallType<nameCall>>(
+        &self,
+        contract_address: Address,
+    ) -> ContractResult<C::Result> {
+        self.connection
+            .call_with_address::<C, _>(contract_address, nameCall {})
+            .await
+    }
 }
@@ -1,11 +1,11 @@
 use std::marker::PhantomData;
 
-use alloy_contract::CallDecoder;
+use alloy_contract::{CallBuilder, CallDecoder};
 use alloy_network::Ethereum;
 use alloy_primitives::{Address, U256};
 use alloy_provider::{Provider, RootProvider};
 use alloy_signer_local::PrivateKeySigner;
-use alloy_transport::BoxTransport;
+use alloy_transport::{BoxTransport, Transport};
 
 #[cfg(feature = "erc20")]
 use crate::erc20::ERC20;
@@ -47,10 +47,45 @@ impl<P: Provider> ConnectionPolicy<P> {
     }
 }
 
+/// Transaction fields that should be set explicitly instead of being filled by the provider.
+/// Useful for replacing a pending transaction (same nonce, higher fees).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct TxOverrides {
+    pub nonce: Option<u64>,
+    /// Legacy gas price.
+    pub gas_price: Option<u128>,
+    /// EIP-1559 fee cap.
+    pub max_fee_per_gas: Option<u128>,
+    /// EIP-1559 tip.
+    pub max_priority_fee_per_gas: Option<u128>,
+}
+
+impl TxOverrides {
+    fn apply<T: Transport + Clone, P: Provider<T>, D: CallDecoder>(
+        &self,
+        mut call_builder: CallBuilder<T, P, D>,
+    ) -> CallBuilder<T, P, D> {
+        if let Some(nonce) = self.nonce {
+            call_builder = call_builder.nonce(nonce);
+        }
+        if let Some(gas_price) = self.gas_price {
+            call_builder = call_builder.gas_price(gas_price);
+        }
+        if let Some(max_fee_per_gas) = self.max_fee_per_gas {
+            call_builder = call_builder.max_fee_per_gas(max_fee_per_gas);
+        }
+        if let Some(max_priority_fee_per_gas) = self.max_priority_fee_per_gas {
+            call_builder = call_builder.max_priority_fee_per_gas(max_priority_fee_per_gas);
+        }
+        call_builder
+    }
+}
+
 #[derive(Clone)]
 pub struct Connection<Provider = NoProvider> {
     contract_address: Address,
     policy: ConnectionPolicy<Provider>,
+    overrides: TxOverrides,
 }
 
 // We require `Provider` to be `Clone`. Otherwise, it is extremely hard to satisfy `Send` bounds in
@@ -61,6 +96,7 @@ impl<P: Provider + Clone> Connection<P> {
         Self {
             contract_address,
             policy,
+            overrides: TxOverrides::default(),
         }
     }
 
@@ -68,6 +104,14 @@ impl<P: Provider + Clone> Connection<P> {
         self.policy.caller_address()
     }
 
+    /// Return a copy of the connection that sends all transactions with `overrides` applied.
+    pub fn with_overrides(&self, overrides: TxOverrides) -> Self {
+        Self {
+            overrides,
+            ..self.clone()
+        }
+    }
+
     #[cfg(feature = "erc20")]
     pub async fn call_with_address<CT: CallType<Call>, Call: ShielderContractCall + Unpin>(
         &self,
@@ -145,7 +189,7 @@ impl<P: Provider + Clone> Connection<P> {
                 .call_builder(&call)
                 .from(self.caller_address())
                 .value(value.unwrap_or_default());
-            CT::action(call_builder).await
+            CT::action(self.overrides.apply(call_builder)).await
         } else {
             #[cfg(not(feature = "erc20"))]
             return Err(ShielderContractError::Other(
@@ -158,7 +202,7 @@ impl<P: Provider + Clone> Connection<P> {
                     .call_builder(&call)
                     .from(self.caller_address())
                     .value(value.unwrap_or_default());
-                CT::action(call_builder).await
+                CT::action(self.overrides.apply(call_builder)).await
             }
         }
     }
@@ -1,8 +1,12 @@
-use alloy_primitives::U256;
-use alloy_sol_types::sol;
+use alloy_primitives::{B256, U256};
+use alloy_sol_types::{sol, Eip712Domain};
 
 use crate::{
-    erc20::ERC20::{allowanceCall, allowanceReturn, approveCall, approveReturn},
+    erc20::ERC20::{
+        allowanceCall, allowanceReturn, approveCall, approveReturn, eip712DomainCall,
+        eip712DomainReturn, nameCall, nameReturn, noncesCall, noncesReturn, DOMAIN_SEPARATORCall,
+        DOMAIN_SEPARATORReturn,
+    },
     ShielderContractCall,
 };
 
@@ -21,6 +25,24 @@ sol! {
         function approve(address spender, uint256 amount) external returns (bool);
 
         function transferFrom(address sender, address recipient, uint256 amount) external returns (bool);
+
+        function name() external view returns (string memory);
+
+        // EIP-2612
+        function nonces(address owner) external view returns (uint256);
+
+        function DOMAIN_SEPARATOR() external view returns (bytes32);
+
+        // EIP-5267
+        function eip712Domain() external view returns (
+            bytes1 fields,
+            string memory name,
+            string memory version,
+            uint256 chainId,
+            address verifyingContract,
+            bytes32 salt,
+            uint256[] memory extensions
+        );
     }
 }
 
@@ -37,3 +59,39 @@ impl ShielderContractCall for allowanceCall {
         result._0
     }
 }
+
+impl ShielderContractCall for nameCall {
+    type UnwrappedResult = String;
+    fn unwrap_result(result: nameReturn) -> Self::UnwrappedResult {
+        result._0
+    }
+}
+
+impl ShielderContractCall for noncesCall {
+    type UnwrappedResult = U256;
+    fn unwrap_result(result: noncesReturn) -> Self::UnwrappedResult {
+        result._0
+    }
+}
+
+impl ShielderContractCall for DOMAIN_SEPARATORCall {
+    type UnwrappedResult = B256;
+    fn unwrap_result(result: DOMAIN_SEPARATORReturn) -> Self::UnwrappedResult {
+        result._0
+    }
+}
+
+impl ShielderContractCall for eip712DomainCall {
+    type UnwrappedResult = Eip712Domain;
+    fn unwrap_result(result: eip712DomainReturn) -> Self::UnwrappedResult {
+        // Only the fields marked in the `fields` bitmap are part of the domain.
+        let has = |bit: u8| result.fields[0] & (1 << bit) != 0;
+        Eip712Domain::new(
+            has(0).then(|| result.name.into()),
+            has(1).then(|| result.version.into()),
+            has(2).then_some(result.chainId),
+            has(3).then_some(result.verifyingContract),
+            has(4).then_some(result.salt),
+        )
+    }
+}
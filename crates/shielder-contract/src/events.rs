@@ -1,11 +1,26 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    pin::Pin,
+    time::Duration,
+};
+
 use alloy_network::AnyNetwork;
-use alloy_primitives::{BlockHash, TxHash};
+use alloy_primitives::{Address, BlockHash, BlockNumber, TxHash};
 use alloy_provider::Provider;
-use alloy_rpc_types::Filter;
-use alloy_sol_types::SolEvent;
+use alloy_rpc_types::{BlockNumberOrTag, BlockTransactionsKind, Filter, Log};
+use alloy_sol_types::{SolEvent, SolEventInterface};
 use alloy_transport::BoxTransport;
+use futures::{Stream, StreamExt};
+use tracing::{debug, warn};
+
+use crate::{ContractResult, ShielderContract::ShielderContractEvents, ShielderContractError};
 
-use crate::{ContractResult, ShielderContractError};
+/// How many blocks are queried for logs in a single `eth_getLogs` call.
+pub const LOG_BATCH_SIZE: u64 = 10_000;
+/// How many recent blocks `ShielderEventWatcher` remembers to handle reorgs.
+pub const REORG_DEPTH: u64 = 64;
+/// How often `ShielderEventWatcher` polls for new logs if it cannot subscribe to them.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
 
 /// Look at the logs of `tx_hash` in `block_hash` and return the first event of type `Event`.
 pub async fn get_event<Event: SolEvent>(
@@ -29,3 +44,702 @@ pub async fn get_event<Event: SolEvent>(
         .next()
         .ok_or(ShielderContractError::EventNotFound)
 }
+
+/// Fetch all `NewAccount`, `Deposit` and `Withdraw` events emitted by the contract at
+/// `contract_address` within `from_block..=to_block`, in the order of emission. Logs are queried in
+/// batches of `LOG_BATCH_SIZE` blocks.
+pub async fn get_shielder_events(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    contract_address: Address,
+    from_block: BlockNumber,
+    to_block: BlockNumber,
+) -> ContractResult<Vec<(TxHash, ShielderContractEvents)>> {
+    let base_filter = Filter::new().address(contract_address);
+    let mut events = vec![];
+
+    let mut batch_start = from_block;
+    while batch_start <= to_block {
+        let batch_end = to_block.min(batch_start + LOG_BATCH_SIZE - 1);
+        let filter = base_filter
+            .clone()
+            .from_block(batch_start)
+            .to_block(batch_end);
+        let logs = provider
+            .get_logs(&filter)
+            .await
+            .map_err(ShielderContractError::ProviderError)?;
+
+        for log in logs.iter().filter(|log| !log.removed) {
+            // Other events (like ownership or fee changes) are not interesting here.
+            let Ok(event) =
+                ShielderContractEvents::decode_raw_log(log.topics(), &log.data().data, true)
+            else {
+                continue;
+            };
+            let tx_hash = log.transaction_hash.ok_or(ShielderContractError::Other(
+                "Transaction hash not found".into(),
+            ))?;
+            events.push((tx_hash, event));
+        }
+
+        batch_start = batch_end + 1;
+    }
+
+    Ok(events)
+}
+
+/// Shielder event together with the place where it was emitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ShielderEventLog {
+    pub event: ShielderContractEvents,
+    pub block_number: BlockNumber,
+    pub block_hash: BlockHash,
+    pub tx_hash: TxHash,
+    pub log_index: u64,
+}
+
+impl ShielderEventLog {
+    /// Decode `log`. Returns `None` for events other than `NewAccount`, `Deposit` and `Withdraw`
+    /// and for logs without block and transaction data (pending ones).
+    pub fn decode(log: &Log) -> Option<Self> {
+        let event =
+            ShielderContractEvents::decode_raw_log(log.topics(), &log.data().data, true).ok()?;
+        Some(Self {
+            event,
+            block_number: log.block_number?,
+            block_hash: log.block_hash?,
+            tx_hash: log.transaction_hash?,
+            log_index: log.log_index?,
+        })
+    }
+
+    fn same_log(&self, other: &Self) -> bool {
+        self.block_hash == other.block_hash && self.log_index == other.log_index
+    }
+}
+
+/// Change of the Shielder event history, as seen by `ShielderEventWatcher`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShielderEventUpdate {
+    /// A new event.
+    Added(ShielderEventLog),
+    /// A previously added event whose block was reorged out.
+    Removed(ShielderEventLog),
+}
+
+/// Recently processed blocks and events, kept to detect and undo reorgs.
+#[derive(Debug, Default)]
+struct RecentHistory {
+    blocks: BTreeMap<BlockNumber, BlockHash>,
+    events: Vec<ShielderEventLog>,
+}
+
+impl RecentHistory {
+    fn remember_block(&mut self, number: BlockNumber, hash: BlockHash) {
+        self.blocks.insert(number, hash);
+    }
+
+    /// Remember `event` and return whether it is new.
+    fn remember_event(&mut self, event: &ShielderEventLog) -> bool {
+        if self.events.iter().any(|known| known.same_log(event)) {
+            return false;
+        }
+        self.remember_block(event.block_number, event.block_hash);
+        self.events.push(event.clone());
+        true
+    }
+
+    /// Forget `event` (if it is known) and return it.
+    fn forget_event(&mut self, event: ShielderEventLog) -> ShielderEventLog {
+        match self.events.iter().position(|known| known.same_log(&event)) {
+            Some(position) => self.events.remove(position),
+            None => event,
+        }
+    }
+
+    /// Forget everything from `block` on and return the forgotten events, the latest first.
+    fn rewind(&mut self, block: BlockNumber) -> Vec<ShielderEventLog> {
+        self.blocks.retain(|&number, _| number < block);
+        let (removed, kept) = std::mem::take(&mut self.events)
+            .into_iter()
+            .partition::<Vec<_>, _>(|event| event.block_number >= block);
+        self.events = kept;
+        removed.into_iter().rev().collect()
+    }
+
+    /// Forget blocks and events too old to be reorged out.
+    fn prune(&mut self, head: BlockNumber) {
+        let oldest = head.saturating_sub(REORG_DEPTH);
+        self.blocks.retain(|&number, _| number >= oldest);
+        self.events.retain(|event| event.block_number >= oldest);
+    }
+}
+
+type LogStream = Pin<Box<dyn Stream<Item = Log> + Send>>;
+
+/// Follows `NewAccount`, `Deposit` and `Withdraw` events of a Shielder contract as they are
+/// emitted.
+///
+/// The watcher first catches up from the starting block with `eth_getLogs`. Then, if the provider
+/// supports subscriptions (WebSocket and IPC ones do), it subscribes to new logs. Otherwise (or
+/// once the subscription is closed), it keeps polling every `poll_interval`.
+///
+/// Events from blocks that are reorged out are reported as removed - from subscriptions as they
+/// come, and when polling, by checking whether the recently processed blocks are still canonical.
+/// After a reorg, events from the rescanned blocks may be reported as removed and then added
+/// again.
+pub struct ShielderEventWatcher<P> {
+    provider: P,
+    contract_address: Address,
+    poll_interval: Duration,
+    start_block: BlockNumber,
+    /// First block that has not been processed yet.
+    next_block: BlockNumber,
+    history: RecentHistory,
+    subscription: Option<LogStream>,
+    try_subscribing: bool,
+}
+
+impl<P: Provider<BoxTransport, AnyNetwork>> ShielderEventWatcher<P> {
+    /// Watch events emitted by the contract at `contract_address` from `from_block` on.
+    pub fn new(provider: P, contract_address: Address, from_block: BlockNumber) -> Self {
+        Self {
+            provider,
+            contract_address,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            start_block: from_block,
+            next_block: from_block,
+            history: RecentHistory::default(),
+            subscription: None,
+            try_subscribing: true,
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Never subscribe, always poll.
+    pub fn polling_only(mut self) -> Self {
+        self.try_subscribing = false;
+        self
+    }
+
+    /// Wait for the next updates (in the order of emission).
+    pub async fn next_updates(&mut self) -> ContractResult<Vec<ShielderEventUpdate>> {
+        loop {
+            if self.subscription.is_some() {
+                let updates = self.receive().await;
+                if !updates.is_empty() {
+                    return Ok(updates);
+                }
+                continue;
+            }
+
+            let (mut updates, caught_up) = self.poll().await?;
+            if caught_up && self.try_subscribing && self.subscribe().await {
+                // Cover the blocks mined before the subscription started.
+                updates.extend(self.poll().await?.0);
+            }
+            if !updates.is_empty() {
+                return Ok(updates);
+            }
+            if caught_up && self.subscription.is_none() {
+                tokio::time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    /// Turn the watcher into a stream of single updates. The stream ends after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = ContractResult<ShielderEventUpdate>> {
+        futures::stream::unfold(
+            (Some(self), VecDeque::new()),
+            |(mut watcher, mut buffered)| async move {
+                if buffered.is_empty() {
+                    match watcher.as_mut()?.next_updates().await {
+                        Ok(updates) => buffered.extend(updates),
+                        Err(err) => return Some((Err(err), (None, buffered))),
+                    }
+                }
+                let update = buffered.pop_front()?;
+                Some((Ok(update), (watcher, buffered)))
+            },
+        )
+    }
+
+    fn filter(&self) -> Filter {
+        Filter::new().address(self.contract_address)
+    }
+
+    async fn block_hash(&self, number: BlockNumber) -> ContractResult<Option<BlockHash>> {
+        Ok(self
+            .provider
+            .get_block_by_number(
+                BlockNumberOrTag::Number(number),
+                BlockTransactionsKind::Hashes,
+            )
+            .await
+            .map_err(ShielderContractError::ProviderError)?
+            .map(|block| block.header.hash))
+    }
+
+    /// Process the next batch of blocks. Returns the updates and whether the head was reached.
+    async fn poll(&mut self) -> ContractResult<(Vec<ShielderEventUpdate>, bool)> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(ShielderContractError::ProviderError)?;
+        let mut updates = self.undo_reorg().await?;
+        if self.next_block > head {
+            return Ok((updates, true));
+        }
+
+        let to_block = head.min(self.next_block + LOG_BATCH_SIZE - 1);
+        let filter = self.filter().from_block(self.next_block).to_block(to_block);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(ShielderContractError::ProviderError)?;
+        for event in logs
+            .iter()
+            .filter(|log| !log.removed)
+            .filter_map(ShielderEventLog::decode)
+        {
+            if self.history.remember_event(&event) {
+                updates.push(ShielderEventUpdate::Added(event));
+            }
+        }
+
+        if let Some(hash) = self.block_hash(to_block).await? {
+            self.history.remember_block(to_block, hash);
+        }
+        self.next_block = to_block + 1;
+        self.history.prune(head);
+        Ok((updates, to_block == head))
+    }
+
+    /// If the recently processed blocks are no longer canonical, go back to the last one that is.
+    async fn undo_reorg(&mut self) -> ContractResult<Vec<ShielderEventUpdate>> {
+        let Some((&newest, &hash)) = self.history.blocks.last_key_value() else {
+            return Ok(vec![]);
+        };
+        if self.block_hash(newest).await? == Some(hash) {
+            return Ok(vec![]);
+        }
+
+        let mut resume_from = newest.saturating_sub(REORG_DEPTH);
+        for (&number, &hash) in self.history.blocks.iter().rev().skip(1) {
+            if self.block_hash(number).await? == Some(hash) {
+                resume_from = number + 1;
+                break;
+            }
+        }
+        let resume_from = resume_from.max(self.start_block);
+        warn!("Chain reorganization detected, rescanning from block {resume_from}");
+
+        self.next_block = resume_from;
+        Ok(self
+            .history
+            .rewind(resume_from)
+            .into_iter()
+            .map(ShielderEventUpdate::Removed)
+            .collect())
+    }
+
+    /// Try to subscribe to the contract logs. Returns whether it succeeded.
+    async fn subscribe(&mut self) -> bool {
+        match self.provider.subscribe_logs(&self.filter()).await {
+            Ok(subscription) => {
+                debug!("Subscribed to Shielder logs");
+                self.subscription = Some(subscription.into_stream().boxed());
+                true
+            }
+            Err(err) => {
+                debug!("Cannot subscribe to Shielder logs, polling instead: {err}");
+                self.try_subscribing = false;
+                false
+            }
+        }
+    }
+
+    async fn receive(&mut self) -> Vec<ShielderEventUpdate> {
+        let Some(log) = self.subscription.as_mut().expect("subscribed").next().await else {
+            warn!("Shielder log subscription closed, polling instead");
+            self.subscription = None;
+            // The last block might have been received partially - check it again.
+            self.next_block = self
+                .history
+                .blocks
+                .last_key_value()
+                .map_or(self.next_block, |(&number, _)| number.min(self.next_block));
+            return vec![];
+        };
+        let Some(event) = ShielderEventLog::decode(&log) else {
+            return vec![];
+        };
+
+        if log.removed {
+            return vec![ShielderEventUpdate::Removed(
+                self.history.forget_event(event),
+            )];
+        }
+        // The event might have already been seen when polling.
+        if !self.history.remember_event(&event) {
+            return vec![];
+        }
+        self.next_block = self.next_block.max(event.block_number + 1);
+        self.history.prune(event.block_number);
+        vec![ShielderEventUpdate::Added(event)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        task::{Context, Poll},
+    };
+
+    use alloy_json_rpc::{RequestPacket, Response, ResponsePacket, ResponsePayload};
+    use alloy_network::AnyNetwork;
+    use alloy_primitives::{Address, BlockHash, Bloom, TxHash, U256};
+    use alloy_provider::{Provider, ProviderBuilder};
+    use alloy_rpc_client::RpcClient;
+    use alloy_rpc_types::Log;
+    use alloy_sol_types::SolEvent;
+    use alloy_transport::{BoxTransport, TransportError, TransportErrorKind, TransportFut};
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use tower::Service;
+
+    use super::{
+        RecentHistory, ShielderEventLog, ShielderEventUpdate, ShielderEventWatcher, REORG_DEPTH,
+    };
+    use crate::ShielderContract::{Deposit, ShielderContractEvents};
+
+    const CONTRACT: Address = Address::repeat_byte(0x5e);
+
+    struct MockBlock {
+        /// Incremented on every reorg, so that the replaced blocks get new hashes.
+        fork: u8,
+        /// Note indices of the `Deposit` events emitted in the block.
+        notes: Vec<u64>,
+    }
+
+    #[derive(Default)]
+    struct MockChainState {
+        /// Blocks starting from block 0.
+        blocks: Vec<MockBlock>,
+        fork: u8,
+        down: bool,
+    }
+
+    impl MockChainState {
+        fn hash(&self, number: u64) -> BlockHash {
+            BlockHash::left_padding_from(&[self.blocks[number as usize].fork, number as u8])
+        }
+
+        fn log(&self, number: u64, index: usize) -> Log {
+            let note = self.blocks[number as usize].notes[index];
+            let deposit = Deposit {
+                newNoteIndex: U256::from(note),
+                ..Default::default()
+            };
+            Log {
+                inner: alloy_primitives::Log {
+                    address: CONTRACT,
+                    data: deposit.encode_log_data(),
+                },
+                block_hash: Some(self.hash(number)),
+                block_number: Some(number),
+                block_timestamp: None,
+                transaction_hash: Some(TxHash::with_last_byte(note as u8)),
+                transaction_index: Some(0),
+                log_index: Some(index as u64),
+                removed: false,
+            }
+        }
+
+        fn block(&self, number: u64) -> Value {
+            if number as usize >= self.blocks.len() {
+                return Value::Null;
+            }
+            json!({
+                "hash": self.hash(number),
+                "parentHash": BlockHash::ZERO,
+                "sha3Uncles": BlockHash::ZERO,
+                "miner": Address::ZERO,
+                "stateRoot": BlockHash::ZERO,
+                "transactionsRoot": BlockHash::ZERO,
+                "receiptsRoot": BlockHash::ZERO,
+                "logsBloom": Bloom::ZERO,
+                "difficulty": "0x0",
+                "number": format!("{number:#x}"),
+                "gasLimit": "0x0",
+                "gasUsed": "0x0",
+                "timestamp": "0x0",
+                "extraData": "0x",
+                "mixHash": BlockHash::ZERO,
+                "nonce": "0x0000000000000000",
+                "baseFeePerGas": "0x0",
+                "size": "0x0",
+                "uncles": [],
+                "transactions": [],
+            })
+        }
+
+        fn logs(&self, from: u64, to: u64) -> Value {
+            let logs = (from..=to.min(self.blocks.len() as u64 - 1))
+                .flat_map(|number| {
+                    (0..self.blocks[number as usize].notes.len()).map(move |index| (number, index))
+                })
+                .map(|(number, index)| self.log(number, index))
+                .collect::<Vec<_>>();
+            json!(logs)
+        }
+    }
+
+    /// In-memory chain serving the RPC methods used by `ShielderEventWatcher`.
+    #[derive(Clone, Default)]
+    struct MockChain(Arc<Mutex<MockChainState>>);
+
+    impl MockChain {
+        fn new(blocks: &[&[u64]]) -> Self {
+            let chain = Self::default();
+            for notes in blocks {
+                chain.push(notes);
+            }
+            chain
+        }
+
+        fn push(&self, notes: &[u64]) {
+            let mut state = self.0.lock().unwrap();
+            let fork = state.fork;
+            state.blocks.push(MockBlock {
+                fork,
+                notes: notes.to_vec(),
+            });
+        }
+
+        /// Replace the blocks from `from` on with `blocks`.
+        fn reorg(&self, from: u64, blocks: &[&[u64]]) {
+            {
+                let mut state = self.0.lock().unwrap();
+                state.blocks.truncate(from as usize);
+                state.fork += 1;
+            }
+            for notes in blocks {
+                self.push(notes);
+            }
+        }
+
+        fn set_down(&self, down: bool) {
+            self.0.lock().unwrap().down = down;
+        }
+
+        fn log(&self, number: u64, index: usize) -> Log {
+            self.0.lock().unwrap().log(number, index)
+        }
+
+        fn added(&self, number: u64, index: usize) -> ShielderEventUpdate {
+            ShielderEventUpdate::Added(self.event(number, index))
+        }
+
+        fn event(&self, number: u64, index: usize) -> ShielderEventLog {
+            ShielderEventLog::decode(&self.log(number, index)).unwrap()
+        }
+
+        fn provider(&self) -> impl Provider<BoxTransport, AnyNetwork> {
+            ProviderBuilder::new()
+                .network::<AnyNetwork>()
+                .on_client(RpcClient::new(BoxTransport::new(self.clone()), true))
+        }
+
+        fn respond(&self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+            let RequestPacket::Single(request) = request else {
+                panic!("The watcher doesn't send batches");
+            };
+            let state = self.0.lock().unwrap();
+            if state.down {
+                return Err(TransportErrorKind::custom_str("The node is down"));
+            }
+            let serialized = serde_json::from_str::<Value>(request.serialized().get()).unwrap();
+            let params = &serialized["params"];
+            let number = |value: &Value| {
+                u64::from_str_radix(value.as_str().unwrap().trim_start_matches("0x"), 16).unwrap()
+            };
+
+            let result = match request.method() {
+                "eth_blockNumber" => json!(format!("{:#x}", state.blocks.len() - 1)),
+                "eth_getBlockByNumber" => state.block(number(&params[0])),
+                "eth_getLogs" => state.logs(
+                    number(&params[0]["fromBlock"]),
+                    number(&params[0]["toBlock"]),
+                ),
+                method => {
+                    return Err(TransportErrorKind::custom_str(&format!(
+                        "Unsupported method {method}"
+                    )))
+                }
+            };
+            Ok(ResponsePacket::Single(Response {
+                id: request.id().clone(),
+                payload: ResponsePayload::Success(
+                    serde_json::value::to_raw_value(&result).unwrap(),
+                ),
+            }))
+        }
+    }
+
+    impl Service<RequestPacket> for MockChain {
+        type Response = ResponsePacket;
+        type Error = TransportError;
+        type Future = TransportFut<'static>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: RequestPacket) -> Self::Future {
+            let response = self.respond(request);
+            Box::pin(async move { response })
+        }
+    }
+
+    #[tokio::test]
+    async fn events_from_reorged_out_blocks_are_removed() {
+        let chain = MockChain::new(&[&[], &[1]]);
+        let mut watcher = ShielderEventWatcher::new(chain.provider(), CONTRACT, 0).polling_only();
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![chain.added(1, 0)]
+        );
+        for note in [2, 3] {
+            chain.push(&[note]);
+            assert_eq!(
+                watcher.next_updates().await.unwrap(),
+                vec![chain.added(note, 0)]
+            );
+        }
+
+        // Blocks 2 and 3 are replaced; the third note lands in the new block 4.
+        let (reorged_2, reorged_3) = (chain.event(2, 0), chain.event(3, 0));
+        chain.reorg(2, &[&[], &[], &[3]]);
+
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![
+                ShielderEventUpdate::Removed(reorged_3),
+                ShielderEventUpdate::Removed(reorged_2),
+                chain.added(4, 0),
+            ]
+        );
+        assert_eq!(watcher.next_block, 5);
+    }
+
+    #[tokio::test]
+    async fn watcher_falls_back_to_polling_when_subscription_closes() {
+        let chain = MockChain::new(&[&[], &[1]]);
+        let mut watcher = ShielderEventWatcher::new(chain.provider(), CONTRACT, 0).polling_only();
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![chain.added(1, 0)]
+        );
+
+        // Block 2 has two events, but the subscription closes after delivering the first one.
+        chain.push(&[2, 3]);
+        watcher.subscription = Some(futures::stream::iter([chain.log(2, 0)]).boxed());
+
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![chain.added(2, 0)]
+        );
+        assert_eq!(watcher.next_block, 3);
+
+        // Block 2 is polled again, only the missing event is new.
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![chain.added(2, 1)]
+        );
+        assert!(watcher.subscription.is_none());
+        assert_eq!(watcher.next_block, 3);
+
+        let mut removed = chain.log(2, 1);
+        removed.removed = true;
+        watcher.subscription = Some(futures::stream::iter([removed]).boxed());
+        assert_eq!(
+            watcher.next_updates().await.unwrap(),
+            vec![ShielderEventUpdate::Removed(chain.event(2, 1))]
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_yields_single_updates_and_ends_after_error() {
+        let chain = MockChain::new(&[&[1, 2], &[3]]);
+        let mut updates = Box::pin(
+            ShielderEventWatcher::new(chain.provider(), CONTRACT, 0)
+                .polling_only()
+                .into_stream(),
+        );
+
+        for (number, index) in [(0, 0), (0, 1), (1, 0)] {
+            assert_eq!(
+                updates.next().await.unwrap().unwrap(),
+                chain.added(number, index)
+            );
+        }
+
+        chain.set_down(true);
+        assert!(updates.next().await.unwrap().is_err());
+        assert!(updates.next().await.is_none());
+    }
+
+    fn event(block_number: u64, log_index: u64) -> ShielderEventLog {
+        ShielderEventLog {
+            event: ShielderContractEvents::Deposit(Deposit::default()),
+            block_number,
+            block_hash: BlockHash::with_last_byte(block_number as u8),
+            tx_hash: TxHash::ZERO,
+            log_index,
+        }
+    }
+
+    #[test]
+    fn events_are_deduplicated() {
+        let mut history = RecentHistory::default();
+
+        assert!(history.remember_event(&event(1, 0)));
+        assert!(history.remember_event(&event(1, 1)));
+        assert!(!history.remember_event(&event(1, 0)));
+    }
+
+    #[test]
+    fn rewind_returns_latest_events_first() {
+        let mut history = RecentHistory::default();
+        for e in [event(1, 0), event(2, 0), event(3, 0), event(3, 1)] {
+            history.remember_event(&e);
+        }
+
+        assert_eq!(
+            history.rewind(2),
+            vec![event(3, 1), event(3, 0), event(2, 0)]
+        );
+        assert_eq!(history.events, vec![event(1, 0)]);
+        assert_eq!(history.blocks.keys().copied().collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn old_blocks_are_forgotten() {
+        let mut history = RecentHistory::default();
+        history.remember_event(&event(1, 0));
+        history.remember_event(&event(10, 0));
+
+        history.prune(REORG_DEPTH + 5);
+
+        assert_eq!(history.events, vec![event(10, 0)]);
+        assert_eq!(history.blocks.keys().copied().collect::<Vec<_>>(), vec![10]);
+    }
+}
@@ -1,3 +1,4 @@
+pub use admin::ShielderAdmin;
 use alloy_contract::Error;
 pub use alloy_primitives;
 use alloy_primitives::{keccak256, Address, Bytes, TxHash, U256};
@@ -5,11 +6,13 @@ use alloy_signer_local::LocalSignerError;
 use alloy_sol_types::SolValue;
 use alloy_transport::TransportError;
 pub use api::ShielderUser;
-pub use connection::{ConnectionPolicy, NoProvider};
+pub use connection::{ConnectionPolicy, NoProvider, TxOverrides};
+pub use revert::ShielderRevert;
 use shielder_setup::version::ContractVersion;
 use type_conversions::address_to_u256;
 pub use types::*;
 
+mod admin;
 mod api;
 pub mod call_type;
 mod connection;
@@ -17,9 +20,14 @@ mod connection;
 pub mod erc20;
 pub mod events;
 pub mod merkle_path;
+pub mod note_tree;
+#[cfg(feature = "erc20")]
+pub mod permit;
 pub mod protocol_fee;
 pub mod providers;
 pub mod recovery;
+mod revert;
+pub mod state;
 mod types;
 
 /// Errors that can occur when interacting with the Shielder contract.
@@ -32,6 +40,8 @@ pub enum ShielderContractError {
     SignerConflict,
     #[error("Call failed: {0:?}")]
     CallError(Error),
+    #[error("Call reverted: {0}")]
+    Reverted(ShielderRevert),
     #[error("Couldn't track the transaction")]
     WatchError,
     #[error("Event was not found for the provided transaction coordinates")]
@@ -43,12 +53,19 @@ pub enum ShielderContractError {
         version: ContractVersion,
         sdk_version: ContractVersion,
     },
+    #[error("Local note tree root {local} does not match the contract root {contract}")]
+    NoteTreeRootMismatch { local: U256, contract: U256 },
+    #[error("Note at index {index} diverges from the local note tree")]
+    NoteTreeDiverged { index: U256 },
     #[error("Other error: {0}")]
     Other(String),
 }
 
 impl From<Error> for ShielderContractError {
     fn from(e: Error) -> Self {
+        if let Some(revert) = ShielderRevert::from_call_error(&e) {
+            return ShielderContractError::Reverted(revert);
+        }
         let e_str = e.to_string();
         if e_str.contains("nonce too low")
             || e_str.contains("transaction already imported")
@@ -0,0 +1,295 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use alloy_network::AnyNetwork;
+use alloy_primitives::{Address, BlockNumber, U256};
+use alloy_provider::Provider;
+use alloy_rpc_types::BlockId;
+use alloy_transport::BoxTransport;
+use serde::{Deserialize, Serialize};
+use shielder_setup::{
+    consts::{ARITY, TREE_HEIGHT},
+    shielder_circuits::{poseidon::off_circuit::hash, Fr},
+};
+use type_conversions::{field_to_u256, u256_to_field};
+
+use crate::{events::get_shielder_events, ContractResult, ShielderContract, ShielderContractError};
+
+/// Node ids follow the numbering of `MerkleTree.sol`: the root has id 1, the children of node `p`
+/// are `p * ARITY - (ARITY - 2) .. p * ARITY + 2`, and the leaves occupy the last level.
+const fn tree_bounds() -> (u64, u64) {
+    let mut size = 1;
+    let mut level_size = 1;
+    let mut level = 0;
+    while level < TREE_HEIGHT {
+        level_size *= ARITY as u64;
+        size += level_size;
+        level += 1;
+    }
+    (size - level_size + 1, size)
+}
+
+const FIRST_LEAF_ID: u64 = tree_bounds().0;
+const MAX_LEAF_ID: u64 = tree_bounds().1;
+
+fn parent(node_id: u64) -> u64 {
+    (node_id + ARITY as u64 - 2) / ARITY as u64
+}
+
+/// Off-chain mirror of the Shielder note tree.
+///
+/// The tree is rebuilt from `NewAccount`, `Deposit` and `Withdraw` events, so that Merkle paths can
+/// be computed locally instead of asking the contract about a particular leaf (which reveals to the
+/// RPC provider which note is about to be spent). Only the nodes that were ever written are kept;
+/// all the others are zero, exactly as in the contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteTree {
+    nodes: HashMap<u64, U256>,
+    leaves: u64,
+    synced_block: Option<BlockNumber>,
+}
+
+impl NoteTree {
+    /// Number of notes in the tree.
+    pub fn len(&self) -> u64 {
+        self.leaves
+    }
+
+    /// Whether the tree contains no notes.
+    pub fn is_empty(&self) -> bool {
+        self.leaves == 0
+    }
+
+    /// The last block whose events have been applied to the tree.
+    pub fn synced_block(&self) -> Option<BlockNumber> {
+        self.synced_block
+    }
+
+    /// Current root of the tree.
+    pub fn root(&self) -> U256 {
+        self.node(1)
+    }
+
+    /// Append `note` as the next leaf and return its index.
+    pub fn add_note(&mut self, note: U256) -> ContractResult<U256> {
+        let mut node_id = FIRST_LEAF_ID + self.leaves;
+        if node_id > MAX_LEAF_ID {
+            return Err("Note tree is full".into());
+        }
+
+        self.nodes.insert(node_id, note);
+        for _ in 0..TREE_HEIGHT {
+            node_id = parent(node_id);
+            let preimage = self.children(node_id).map(u256_to_field::<Fr>);
+            self.nodes.insert(node_id, field_to_u256(hash(&preimage)));
+        }
+
+        self.leaves += 1;
+        Ok(U256::from(self.leaves - 1))
+    }
+
+    /// Put `note` at `leaf_index`. Notes that are already in the tree are accepted (and checked),
+    /// so replaying overlapping event ranges is harmless. A different note at a known index (e.g.
+    /// after a reorg) or a gap means that the tree has diverged from the chain.
+    pub fn insert_note(&mut self, leaf_index: U256, note: U256) -> ContractResult<()> {
+        match leaf_index.cmp(&U256::from(self.leaves)) {
+            Ordering::Less => match self.node(FIRST_LEAF_ID + leaf_index.to::<u64>()) == note {
+                true => Ok(()),
+                false => Err(ShielderContractError::NoteTreeDiverged { index: leaf_index }),
+            },
+            Ordering::Equal => self.add_note(note).map(|_| ()),
+            Ordering::Greater => Err(ShielderContractError::NoteTreeDiverged { index: leaf_index }),
+        }
+    }
+
+    /// Compute the merkle path to the leaf at `leaf_index`. The result has the same shape as
+    /// [`crate::merkle_path::reorganize_merkle_path`] output: the current root and, for every
+    /// level, all `ARITY` siblings (including the node on the path itself).
+    pub fn merkle_path(
+        &self,
+        leaf_index: U256,
+    ) -> ContractResult<(U256, [[U256; ARITY]; TREE_HEIGHT])> {
+        if leaf_index >= U256::from(self.leaves) {
+            return Err(ShielderContractError::Other(format!(
+                "Leaf {leaf_index} does not exist in the local note tree"
+            )));
+        }
+
+        let mut node_id = FIRST_LEAF_ID + leaf_index.to::<u64>();
+        let mut path = [[U256::ZERO; ARITY]; TREE_HEIGHT];
+        for level in path.iter_mut() {
+            node_id = parent(node_id);
+            *level = self.children(node_id);
+        }
+
+        Ok((self.root(), path))
+    }
+
+    /// Apply all the note-creating events emitted by the contract at `contract_address` since the
+    /// last synced block, up to the current chain head. Afterwards, compare the local root with the
+    /// one kept by the contract at the same block. Returns the current chain head.
+    pub async fn sync(
+        &mut self,
+        provider: &impl Provider<BoxTransport, AnyNetwork>,
+        contract_address: Address,
+    ) -> ContractResult<BlockNumber> {
+        let last_block = provider
+            .get_block_number()
+            .await
+            .map_err(ShielderContractError::ProviderError)?;
+        let from_block = self.synced_block.map_or(0, |block| block + 1);
+        if from_block <= last_block {
+            let events =
+                get_shielder_events(provider, contract_address, from_block, last_block).await?;
+            for (_, event) in events {
+                self.insert_note(event.note_index(), event.note())?;
+            }
+            self.synced_block = Some(last_block);
+        }
+
+        self.verify_root(provider, contract_address).await?;
+        Ok(last_block)
+    }
+
+    /// Check that the local root is the same as the contract root at the synced block.
+    pub async fn verify_root(
+        &self,
+        provider: &impl Provider<BoxTransport, AnyNetwork>,
+        contract_address: Address,
+    ) -> ContractResult<()> {
+        let Some(block) = self.synced_block else {
+            return Ok(());
+        };
+
+        let contract = ShielderContract::new(contract_address, provider);
+        let tree = contract
+            .merkleTree()
+            .block(BlockId::number(block))
+            .call()
+            .await?;
+
+        let local = self.root();
+        match tree.root == local {
+            true => Ok(()),
+            false => Err(ShielderContractError::NoteTreeRootMismatch {
+                local,
+                contract: tree.root,
+            }),
+        }
+    }
+
+    fn node(&self, node_id: u64) -> U256 {
+        self.nodes.get(&node_id).copied().unwrap_or_default()
+    }
+
+    fn children(&self, node_id: u64) -> [U256; ARITY] {
+        let first_child = node_id * ARITY as u64 - (ARITY as u64 - 2);
+        core::array::from_fn(|i| self.node(first_child + i as u64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use shielder_setup::{
+        consts::TREE_HEIGHT,
+        shielder_circuits::{poseidon::off_circuit::hash, Fr},
+    };
+    use type_conversions::{field_to_u256, u256_to_field};
+
+    use super::{NoteTree, FIRST_LEAF_ID, MAX_LEAF_ID};
+    use crate::ShielderContractError;
+
+    fn hash_level(level: &[U256; 7]) -> U256 {
+        field_to_u256(hash(&level.map(u256_to_field::<Fr>)))
+    }
+
+    fn tree_with_notes(count: u64) -> NoteTree {
+        let mut tree = NoteTree::default();
+        for i in 0..count {
+            tree.add_note(U256::from(1000 + i)).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn tree_bounds_match_contract() {
+        // `MerkleTree.sol`: firstLeafId = 1 + 7 + ... + 7^12 + 1, size = firstLeafId + 7^13 - 1
+        assert_eq!(FIRST_LEAF_ID, 16_148_168_402);
+        assert_eq!(MAX_LEAF_ID, 113_037_178_808);
+    }
+
+    #[test]
+    fn merkle_path_leads_to_root() {
+        let tree = tree_with_notes(10);
+
+        for leaf_index in 0..10 {
+            let (root, path) = tree.merkle_path(U256::from(leaf_index)).unwrap();
+            assert_eq!(root, tree.root());
+            assert!(path[0].contains(&U256::from(1000 + leaf_index)));
+
+            for level in 0..TREE_HEIGHT - 1 {
+                assert!(path[level + 1].contains(&hash_level(&path[level])));
+            }
+            assert_eq!(hash_level(&path[TREE_HEIGHT - 1]), root);
+        }
+    }
+
+    #[test]
+    fn merkle_path_to_missing_leaf_fails() {
+        let tree = tree_with_notes(3);
+        assert!(tree.merkle_path(U256::from(3)).is_err());
+        assert!(NoteTree::default().merkle_path(U256::ZERO).is_err());
+    }
+
+    #[test]
+    fn insert_note_replays_events_by_index() {
+        let mut tree = NoteTree::default();
+        tree.insert_note(U256::ZERO, U256::from(1)).unwrap();
+        tree.insert_note(U256::from(1), U256::from(2)).unwrap();
+        // Replaying a known note is fine, changing it is not.
+        tree.insert_note(U256::ZERO, U256::from(1)).unwrap();
+        assert!(matches!(
+            tree.insert_note(U256::ZERO, U256::from(3)),
+            Err(ShielderContractError::NoteTreeDiverged { index }) if index == U256::ZERO
+        ));
+        // Gaps are not allowed.
+        assert!(matches!(
+            tree.insert_note(U256::from(3), U256::from(4)),
+            Err(ShielderContractError::NoteTreeDiverged { index }) if index == U256::from(3)
+        ));
+
+        assert_eq!(tree.len(), 2);
+        assert_eq!(tree, {
+            let mut expected = NoteTree::default();
+            expected.add_note(U256::from(1)).unwrap();
+            expected.add_note(U256::from(2)).unwrap();
+            expected
+        });
+    }
+
+    #[test]
+    fn replaced_note_is_reported_as_divergence() {
+        // The tree has seen notes 0..3, but a reorg replaced the note at index 1.
+        let mut tree = tree_with_notes(3);
+        let replayed = [(0, 1000), (1, 2001), (2, 1002)];
+
+        let diverged = replayed
+            .iter()
+            .map(|&(index, note)| tree.insert_note(U256::from(index), U256::from(note)))
+            .find_map(Result::err);
+        assert!(matches!(
+            diverged,
+            Some(ShielderContractError::NoteTreeDiverged { index }) if index == U256::from(1)
+        ));
+
+        // Rebuilding from scratch accepts the new history.
+        let mut rebuilt = NoteTree::default();
+        for (index, note) in replayed {
+            rebuilt
+                .insert_note(U256::from(index), U256::from(note))
+                .unwrap();
+        }
+        assert_eq!(rebuilt.len(), 3);
+        assert_ne!(rebuilt.root(), tree_with_notes(3).root());
+    }
+}
@@ -0,0 +1,112 @@
+//! EIP-2612 permits: allowances granted with an off-chain signature instead of an `approve` call.
+//!
+//! The signature is submitted together with the deposit (`newAccountERC20WithPermit` and
+//! `depositERC20WithPermit`), so no separate transaction is needed and no allowance is left over.
+
+use alloy_primitives::{Address, B256, U256};
+use alloy_provider::Provider;
+use alloy_signer::SignerSync;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_sol_types::{sol, Eip712Domain, SolStruct};
+
+use crate::{call_type::DryRun, ShielderContract::PermitSignature, ShielderUser};
+
+sol! {
+    /// The EIP-2612 message signed by the token owner.
+    #[derive(Debug, PartialEq, Eq)]
+    struct Permit {
+        address owner;
+        address spender;
+        uint256 value;
+        uint256 nonce;
+        uint256 deadline;
+    }
+}
+
+/// Find the EIP-712 domain that `token` uses for permits. Returns `None` if the token does not
+/// support EIP-2612 (or its domain cannot be determined), in which case `approve` should be used.
+///
+/// The domain is read with EIP-5267 `eip712Domain()` if the token implements it. Otherwise, the
+/// most common domain (token name, version "1", `chain_id` and the token address) is assumed. In
+/// both cases, the domain is accepted only if its hash matches the token's `DOMAIN_SEPARATOR()`.
+pub async fn permit_domain<P: Provider + Clone>(
+    user: &ShielderUser<P>,
+    token: Address,
+    chain_id: u64,
+) -> Option<Eip712Domain> {
+    let domain_separator = user.erc20_domain_separator::<DryRun>(token).await.ok()?;
+
+    let domain = match user.erc20_eip712_domain::<DryRun>(token).await {
+        Ok(domain) => domain,
+        Err(_) => {
+            let name = user.erc20_name::<DryRun>(token).await.ok()?;
+            Eip712Domain::new(
+                Some(name.into()),
+                Some("1".into()),
+                Some(U256::from(chain_id)),
+                Some(token),
+                None,
+            )
+        }
+    };
+
+    (domain.separator() == domain_separator).then_some(domain)
+}
+
+/// Sign `permit` for a token with `domain`. `permit.spender` must be the Shielder contract and
+/// `permit.value` the exact amount of the deposit the signature is attached to.
+pub fn sign_permit(
+    signer: &PrivateKeySigner,
+    domain: &Eip712Domain,
+    permit: &Permit,
+) -> Result<PermitSignature, alloy_signer::Error> {
+    let signature = signer.sign_hash_sync(&permit.eip712_signing_hash(domain))?;
+    Ok(PermitSignature {
+        deadline: permit.deadline,
+        v: 27 + signature.v() as u8,
+        r: B256::from(signature.r().to_be_bytes::<32>()),
+        s: B256::from(signature.s().to_be_bytes::<32>()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, PrimitiveSignature, U256};
+    use alloy_signer_local::PrivateKeySigner;
+    use alloy_sol_types::{Eip712Domain, SolStruct};
+
+    use super::{sign_permit, Permit};
+
+    #[test]
+    fn permit_is_signed_by_the_owner() {
+        let signer = PrivateKeySigner::random();
+        let domain = Eip712Domain::new(
+            Some("Token".into()),
+            Some("1".into()),
+            Some(U256::from(1)),
+            Some(Address::repeat_byte(1)),
+            None,
+        );
+        let permit = Permit {
+            owner: signer.address(),
+            spender: Address::repeat_byte(2),
+            value: U256::from(100),
+            nonce: U256::ZERO,
+            deadline: U256::MAX,
+        };
+
+        let permit_signature = sign_permit(&signer, &domain, &permit).unwrap();
+
+        assert_eq!(permit_signature.deadline, permit.deadline);
+        assert!(permit_signature.v == 27 || permit_signature.v == 28);
+        let signature = PrimitiveSignature::new(
+            U256::from_be_bytes(permit_signature.r.0),
+            U256::from_be_bytes(permit_signature.s.0),
+            permit_signature.v == 28,
+        );
+        let recovered = signature
+            .recover_address_from_prehash(&permit.eip712_signing_hash(&domain))
+            .unwrap();
+        assert_eq!(recovered, signer.address());
+    }
+}
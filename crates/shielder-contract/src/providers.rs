@@ -8,24 +8,88 @@ use alloy_provider::{
     },
     Provider, ProviderBuilder, SendableTx,
 };
+use alloy_rpc_client::RpcClient;
 use alloy_signer_local::PrivateKeySigner;
 use alloy_transport::{BoxTransport, Transport, TransportResult};
+pub use failover::{FailoverConfig, FailoverTransport, RetryPolicy};
 
 use crate::{ContractResult, ShielderContractError};
 
+mod failover;
+
+/// Separates the RPC URL list from the quorum setting, e.g. `https://a,https://b|quorum=2`. It
+/// cannot appear (unescaped) in a URL, unlike `?` or `&`.
+const QUORUM_SEPARATOR: char = '|';
+const QUORUM_PARAM: &str = "quorum=";
+
+/// Split `rpc_url` into the endpoint URLs and the failover config. `rpc_url` is a comma-separated
+/// list of URLs, optionally followed by `|quorum=N` (see `FailoverConfig::quorum`). Quorum needs
+/// at least two URLs.
+fn parse_rpc_urls(rpc_url: &str) -> ContractResult<(Vec<String>, FailoverConfig)> {
+    let (rpc_url, quorum) = match rpc_url.split_once(QUORUM_SEPARATOR) {
+        Some((rpc_url, quorum)) => (rpc_url, Some(quorum.trim())),
+        None => (rpc_url, None),
+    };
+    let rpc_urls = rpc_url
+        .split(',')
+        .map(str::trim)
+        .filter(|url| !url.is_empty())
+        .map(String::from)
+        .collect::<Vec<_>>();
+    let mut config = FailoverConfig::default();
+
+    if let Some(quorum) = quorum {
+        if rpc_urls.len() < 2 {
+            return Err("RPC quorum needs several RPC URLs".into());
+        }
+        config.quorum = quorum
+            .strip_prefix(QUORUM_PARAM)
+            .and_then(|quorum| quorum.parse().ok())
+            .ok_or_else(|| {
+                ShielderContractError::Other(format!("Invalid RPC quorum setting: {quorum}"))
+            })?;
+    }
+
+    if config.quorum == 0 || config.quorum > rpc_urls.len().max(1) {
+        return Err(ShielderContractError::Other(format!(
+            "RPC quorum must be between 1 and the number of URLs ({})",
+            rpc_urls.len()
+        )));
+    }
+    Ok((rpc_urls, config))
+}
+
+/// If `rpc_url` is a list of several URLs, creates a client that fails over between them (with
+/// quorum reads, if configured).
+async fn failover_client(rpc_url: &str) -> ContractResult<Option<RpcClient<BoxTransport>>> {
+    let (rpc_urls, config) = parse_rpc_urls(rpc_url)?;
+    if rpc_urls.len() < 2 {
+        return Ok(None);
+    }
+    let transport = FailoverTransport::connect(&rpc_urls, config).await?;
+    Ok(Some(RpcClient::new(BoxTransport::new(transport), false)))
+}
+
 /// Creates a provider for the given RPC URL.
 ///
 /// This is a simple provider, without any fillers or
 /// signer configuration (apart from some devnet-specific defaults). It is suitable for doing
 /// read-only operations.
+///
+/// Like in all the functions below, `rpc_url` may be a comma-separated list of URLs - then the
+/// provider fails over between them (see `FailoverTransport`). A `|quorum=N` suffix makes critical
+/// reads require the same result from `N` of them.
 pub async fn create_simple_provider(
     rpc_url: &str,
 ) -> ContractResult<impl Provider<BoxTransport, AnyNetwork>> {
-    ProviderBuilder::new()
-        .network::<AnyNetwork>()
-        .on_builtin(rpc_url)
-        .await
-        .map_err(ShielderContractError::ProviderError)
+    let builder = ProviderBuilder::new().network::<AnyNetwork>();
+    match failover_client(rpc_url).await? {
+        Some(client) => Ok(builder.on_client(client)),
+        None => builder
+            .on_builtin(rpc_url)
+            .await
+            .map_err(ShielderContractError::ProviderError),
+    }
 }
 
 /// Creates a provider for the given RPC URL, with the given signer. This provider is suitable for
@@ -38,12 +102,16 @@ pub async fn create_provider_with_signer(
     rpc_url: &str,
     signer: PrivateKeySigner,
 ) -> ContractResult<impl Provider + Clone> {
-    ProviderBuilder::new()
+    let builder = ProviderBuilder::new()
         .with_recommended_fillers()
-        .filler(WalletFiller::new(EthereumWallet::from(signer)))
-        .on_builtin(rpc_url)
-        .await
-        .map_err(ShielderContractError::ProviderError)
+        .filler(WalletFiller::new(EthereumWallet::from(signer)));
+    match failover_client(rpc_url).await? {
+        Some(client) => Ok(builder.on_client(client)),
+        None => builder
+            .on_builtin(rpc_url)
+            .await
+            .map_err(ShielderContractError::ProviderError),
+    }
 }
 
 /// Creates a provider for the given RPC URL, with the given signer. This provider is suitable for
@@ -55,7 +123,7 @@ pub async fn create_provider_with_nonce_caching_signer(
     rpc_url: &str,
     signer: PrivateKeySigner,
 ) -> ContractResult<impl Provider + Clone> {
-    ProviderBuilder::new()
+    let builder = ProviderBuilder::new()
         // The four fillers below are the recommended fillers are the same as
         // `with_recommended_fillers` except the `NonceManager` parameter for `NonceFiller`.
         .filler(GasFiller)
@@ -63,11 +131,15 @@ pub async fn create_provider_with_nonce_caching_signer(
         .filler(NonceFiller::<CachedNonceManager>::default())
         .filler(ChainIdFiller::default())
         .filler(WalletFiller::new(EthereumWallet::from(signer)))
-        .filler(LoggingFiller::default())
-        .on_builtin(rpc_url)
-        .await
-        .map_err(ShielderContractError::ProviderError)
-        .map(Arc::new)
+        .filler(LoggingFiller::default());
+    let provider = match failover_client(rpc_url).await? {
+        Some(client) => builder.on_client(client),
+        None => builder
+            .on_builtin(rpc_url)
+            .await
+            .map_err(ShielderContractError::ProviderError)?,
+    };
+    Ok(Arc::new(provider))
 }
 
 /// A noop filler that reports transaction details once it is prepared, just before sending. For
@@ -107,3 +179,34 @@ impl TxFiller for LoggingFiller {
         Ok(tx)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::parse_rpc_urls;
+
+    #[test]
+    fn quorum_is_read_from_url_list() {
+        let (urls, config) = parse_rpc_urls("http://a, http://b|quorum=2").unwrap();
+        assert_eq!(urls, vec!["http://a", "http://b"]);
+        assert_eq!(config.quorum, 2);
+
+        let (urls, config) = parse_rpc_urls("http://a,http://b/?key=secret | quorum=2").unwrap();
+        assert_eq!(urls, vec!["http://a", "http://b/?key=secret"]);
+        assert_eq!(config.quorum, 2);
+
+        // Query parameters belong to the URLs, whatever their names.
+        let (urls, config) = parse_rpc_urls("http://a?quorum=1").unwrap();
+        assert_eq!(urls, vec!["http://a?quorum=1"]);
+        assert_eq!(config.quorum, 1);
+        assert!(parse_rpc_urls("").unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn quorum_must_be_reachable() {
+        assert!(parse_rpc_urls("http://a,http://b|quorum=3").is_err());
+        assert!(parse_rpc_urls("http://a,http://b|quorum=0").is_err());
+        assert!(parse_rpc_urls("http://a,http://b|quorum=many").is_err());
+        assert!(parse_rpc_urls("http://a,http://b|2").is_err());
+        assert!(parse_rpc_urls("http://a|quorum=1").is_err());
+    }
+}
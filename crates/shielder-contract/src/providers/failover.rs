@@ -0,0 +1,477 @@
+use std::{
+    collections::HashMap,
+    future::poll_fn,
+    sync::{Arc, Mutex, OnceLock},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use alloy_json_rpc::{Id, Request, RequestPacket, ResponsePacket, ResponsePayload};
+use alloy_primitives::{hex, U64};
+use alloy_rpc_client::BuiltInConnectionString;
+use alloy_sol_types::SolCall;
+use alloy_transport::{BoxTransport, TransportError, TransportErrorKind, TransportFut};
+use tower::Service;
+use tracing::{debug, warn};
+
+use crate::{
+    ContractResult,
+    ShielderContract::{getMerklePathCall, nullifiersCall},
+    ShielderContractError,
+};
+
+/// How failed requests are retried.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts of a single request (over all the endpoints).
+    pub max_attempts: u32,
+    /// Delay after the first failure. It is doubled with every next consecutive failure.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after `failures` consecutive failures.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff)
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct FailoverConfig {
+    pub retry: RetryPolicy,
+    /// How many endpoints must return the same result of a critical read (`nullifiers` and
+    /// `getMerklePath` calls) for it to be accepted. `1` disables quorum reads.
+    pub quorum: usize,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            retry: RetryPolicy::default(),
+            quorum: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    /// Moving average of the response time.
+    latency: Option<Duration>,
+    /// Until then, the endpoint is used only if all the others are suspended as well.
+    suspended_until: Option<Instant>,
+}
+
+impl Health {
+    fn record_success(&mut self, latency: Duration) {
+        self.consecutive_failures = 0;
+        self.suspended_until = None;
+        self.latency = Some(match self.latency {
+            Some(average) => (average * 4 + latency) / 5,
+            None => latency,
+        });
+    }
+
+    fn record_failure(&mut self, retry: &RetryPolicy, now: Instant) {
+        self.consecutive_failures += 1;
+        self.suspended_until = Some(now + retry.backoff(self.consecutive_failures));
+    }
+
+    fn is_suspended(&self, now: Instant) -> bool {
+        self.suspended_until.is_some_and(|until| until > now)
+    }
+
+    /// The lower, the better.
+    fn rank(&self, now: Instant) -> (bool, u32, Duration) {
+        (
+            self.is_suspended(now),
+            self.consecutive_failures,
+            self.latency.unwrap_or_default(),
+        )
+    }
+}
+
+/// Health of an endpoint. It is shared by all the transports in the process, as providers are
+/// usually created for a single operation.
+fn health_of(url: &str) -> Arc<Mutex<Health>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<String, Arc<Mutex<Health>>>>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap()
+        .entry(url.to_string())
+        .or_default()
+        .clone()
+}
+
+#[derive(Clone)]
+struct Endpoint {
+    url: String,
+    transport: BoxTransport,
+    health: Arc<Mutex<Health>>,
+}
+
+impl Endpoint {
+    async fn send(
+        &self,
+        request: RequestPacket,
+        retry: &RetryPolicy,
+    ) -> Result<ResponsePacket, TransportError> {
+        let started = Instant::now();
+        let mut transport = self.transport.clone();
+        let result = match poll_fn(|cx| transport.poll_ready(cx)).await {
+            Ok(()) => transport.call(request).await,
+            Err(err) => Err(err),
+        };
+
+        let mut health = self.health.lock().unwrap();
+        match &result {
+            Ok(_) => health.record_success(started.elapsed()),
+            Err(err) => {
+                warn!("RPC request to {} failed: {err}", self.url);
+                health.record_failure(retry, Instant::now());
+            }
+        }
+        result
+    }
+}
+
+/// Transport spreading JSON-RPC requests over several endpoints.
+///
+/// Every request is sent to the healthiest endpoint (the one without recent failures and with the
+/// shortest response time). If it fails, the request is retried on the next one, and once all the
+/// endpoints have failed recently, with a backoff. Optionally, critical reads are sent to several
+/// endpoints, until enough of them return the same result.
+///
+/// Only transport failures (connection errors, HTTP errors) are retried - JSON-RPC errors (like a
+/// reverted call) are returned as they are.
+#[derive(Clone)]
+pub struct FailoverTransport {
+    endpoints: Arc<Vec<Endpoint>>,
+    config: FailoverConfig,
+}
+
+impl FailoverTransport {
+    pub async fn connect(rpc_urls: &[String], config: FailoverConfig) -> ContractResult<Self> {
+        if rpc_urls.is_empty() {
+            return Err("No RPC URL provided".into());
+        }
+        let mut endpoints = Vec::with_capacity(rpc_urls.len());
+        for url in rpc_urls {
+            let transport = url
+                .parse::<BuiltInConnectionString>()
+                .map_err(ShielderContractError::ProviderError)?
+                .connect_boxed()
+                .await
+                .map_err(ShielderContractError::ProviderError)?;
+            endpoints.push(Endpoint {
+                url: url.clone(),
+                transport,
+                health: health_of(url),
+            });
+        }
+        Ok(Self {
+            endpoints: Arc::new(endpoints),
+            config,
+        })
+    }
+
+    /// Endpoints from the most to the least preferred (in the configured order if equally good).
+    fn ranked(&self) -> Vec<Endpoint> {
+        let now = Instant::now();
+        let mut endpoints = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.health.lock().unwrap().rank(now), endpoint))
+            .collect::<Vec<_>>();
+        endpoints.sort_by_key(|(rank, _)| *rank);
+        endpoints
+            .into_iter()
+            .map(|(_, endpoint)| endpoint.clone())
+            .collect()
+    }
+
+    async fn request(self, request: RequestPacket) -> Result<ResponsePacket, TransportError> {
+        if self.config.quorum > 1 && is_critical_read(&request) {
+            return self.quorum_request(request).await;
+        }
+
+        let retry = self.config.retry;
+        let mut last_error = None;
+        for attempt in 1..=retry.max_attempts.max(1) {
+            let endpoint = self.ranked().swap_remove(0);
+            let all_suspended = endpoint.health.lock().unwrap().is_suspended(Instant::now());
+            if attempt > 1 && all_suspended {
+                tokio::time::sleep(retry.backoff(attempt - 1)).await;
+            }
+            match endpoint.send(request.clone(), &retry).await {
+                Ok(response) => return Ok(response),
+                Err(err) => last_error = Some(err),
+            }
+        }
+        Err(last_error.expect("at least one attempt is made"))
+    }
+
+    /// Send `request` to the endpoints (best first) until `quorum` of them return the same result.
+    ///
+    /// Endpoints can be at different heights, so a read of the `latest` block could return
+    /// different (but correct) results. Such reads are pinned to a block known to the endpoints.
+    async fn quorum_request(
+        self,
+        request: RequestPacket,
+    ) -> Result<ResponsePacket, TransportError> {
+        let request = match self.common_block().await {
+            Some(block) => pin_block(&request, block).unwrap_or(request),
+            None => request,
+        };
+
+        let mut results = Vec::new();
+        for endpoint in self.ranked() {
+            match endpoint.send(request.clone(), &self.config.retry).await {
+                Ok(response) => match success_payload(&response) {
+                    Some(payload) => results.push((payload, response)),
+                    None => debug!("RPC endpoint {} returned an error", endpoint.url),
+                },
+                Err(_) => continue,
+            }
+            if let Some(response) = agreed(&results, self.config.quorum) {
+                return Ok(response);
+            }
+        }
+        Err(TransportErrorKind::custom_str(&format!(
+            "Fewer than {} RPC endpoints agreed on the result",
+            self.config.quorum
+        )))
+    }
+}
+
+impl FailoverTransport {
+    /// The lowest of the block numbers reported by the first `quorum` endpoints that respond.
+    async fn common_block(&self) -> Option<u64> {
+        let request = Request::new("eth_blockNumber", Id::Number(0), serde_json::json!([]))
+            .serialize()
+            .ok()?;
+        let mut blocks = Vec::new();
+        for endpoint in self.ranked() {
+            let Ok(response) = endpoint
+                .send(RequestPacket::Single(request.clone()), &self.config.retry)
+                .await
+            else {
+                continue;
+            };
+            if let Some(block) = success_payload(&response)
+                .and_then(|payload| serde_json::from_str::<U64>(&payload).ok())
+            {
+                blocks.push(block.to::<u64>());
+            }
+            if blocks.len() >= self.config.quorum {
+                return blocks.into_iter().min();
+            }
+        }
+        None
+    }
+}
+
+impl Service<RequestPacket> for FailoverTransport {
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        Box::pin(self.clone().request(request))
+    }
+}
+
+/// Whether `request` is an `eth_call` of `nullifiers` or `getMerklePath`.
+fn is_critical_read(request: &RequestPacket) -> bool {
+    let RequestPacket::Single(request) = request else {
+        return false;
+    };
+    if request.method() != "eth_call" {
+        return false;
+    }
+    let Ok(request) = serde_json::from_str::<serde_json::Value>(request.serialized().get()) else {
+        return false;
+    };
+    let call = &request["params"][0];
+    let Some(input) = call["input"].as_str().or_else(|| call["data"].as_str()) else {
+        return false;
+    };
+    [nullifiersCall::SELECTOR, getMerklePathCall::SELECTOR]
+        .iter()
+        .any(|selector| input.starts_with(&format!("0x{}", hex::encode(selector))))
+}
+
+/// `request` (an `eth_call`) with the block set to `block`, unless it already reads a specific
+/// block. `None` if the request stays as it is.
+fn pin_block(request: &RequestPacket, block: u64) -> Option<RequestPacket> {
+    let RequestPacket::Single(request) = request else {
+        return None;
+    };
+    let serialized = serde_json::from_str::<serde_json::Value>(request.serialized().get()).ok()?;
+    let mut params = serialized["params"].as_array()?.clone();
+    let block = serde_json::Value::String(format!("{block:#x}"));
+    match params.get(1).map(|tag| tag.as_str()) {
+        None => params.push(block),
+        Some(Some("latest" | "pending")) => params[1] = block,
+        Some(_) => return None,
+    }
+
+    let pinned = Request::new(
+        request.method().to_string(),
+        request.id().clone(),
+        serde_json::Value::Array(params),
+    )
+    .serialize()
+    .ok()?;
+    Some(RequestPacket::Single(pinned))
+}
+
+fn success_payload(response: &ResponsePacket) -> Option<String> {
+    match response {
+        ResponsePacket::Single(response) => match &response.payload {
+            ResponsePayload::Success(payload) => Some(payload.get().to_string()),
+            ResponsePayload::Failure(_) => None,
+        },
+        ResponsePacket::Batch(_) => None,
+    }
+}
+
+/// A result returned by at least `quorum` endpoints, if there is one.
+fn agreed<T: Clone>(results: &[(String, T)], quorum: usize) -> Option<T> {
+    results
+        .iter()
+        .find(|(payload, _)| results.iter().filter(|(other, _)| other == payload).count() >= quorum)
+        .map(|(_, result)| result.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use alloy_json_rpc::{Id, Request, RequestPacket};
+    use alloy_primitives::hex;
+    use alloy_sol_types::SolCall;
+    use serde_json::json;
+
+    use super::{agreed, is_critical_read, pin_block, Health, RetryPolicy};
+    use crate::ShielderContract::{nullifiersCall, protocolWithdrawFeeBpsCall};
+
+    fn retry() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        }
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_limit() {
+        assert_eq!(retry().backoff(1), Duration::from_millis(100));
+        assert_eq!(retry().backoff(3), Duration::from_millis(400));
+        assert_eq!(retry().backoff(100), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn failing_and_slow_endpoints_are_ranked_lower() {
+        let now = Instant::now();
+        let mut fast = Health::default();
+        fast.record_success(Duration::from_millis(10));
+        let mut slow = Health::default();
+        slow.record_success(Duration::from_millis(500));
+        let mut failing = Health::default();
+        failing.record_failure(&retry(), now);
+
+        assert!(fast.rank(now) < slow.rank(now));
+        assert!(slow.rank(now) < failing.rank(now));
+        assert!(failing.is_suspended(now));
+        assert!(!failing.is_suspended(now + Duration::from_secs(1)));
+
+        failing.record_success(Duration::from_millis(10));
+        assert_eq!(failing.rank(now), fast.rank(now));
+    }
+
+    #[test]
+    fn result_needs_quorum() {
+        let results = vec![
+            ("0x1".to_string(), 1),
+            ("0x2".to_string(), 2),
+            ("0x2".to_string(), 3),
+        ];
+
+        assert_eq!(agreed(&results[..2], 2), None);
+        assert_eq!(agreed(&results, 2), Some(2));
+        assert_eq!(agreed(&results, 1), Some(1));
+    }
+
+    #[test]
+    fn only_nullifier_and_merkle_path_calls_are_critical() {
+        let call = |method: &'static str, input: Vec<u8>| {
+            let params = json!([{"to": "0x0000000000000000000000000000000000000001",
+                "input": format!("0x{}", hex::encode(input))}, "latest"]);
+            RequestPacket::Single(
+                Request::new(method, Id::Number(1), params)
+                    .serialize()
+                    .unwrap(),
+            )
+        };
+        let nullifiers = nullifiersCall {
+            nullifierHash: Default::default(),
+        }
+        .abi_encode();
+
+        assert!(is_critical_read(&call("eth_call", nullifiers.clone())));
+        assert!(!is_critical_read(&call("eth_estimateGas", nullifiers)));
+        assert!(!is_critical_read(&call(
+            "eth_call",
+            protocolWithdrawFeeBpsCall {}.abi_encode()
+        )));
+    }
+
+    #[test]
+    fn latest_block_reads_are_pinned() {
+        let call = |params: serde_json::Value| {
+            RequestPacket::Single(
+                Request::new("eth_call", Id::Number(7), params)
+                    .serialize()
+                    .unwrap(),
+            )
+        };
+        let params_of = |request: RequestPacket| {
+            let RequestPacket::Single(request) = request else {
+                panic!("Expected a single request");
+            };
+            assert_eq!(request.method(), "eth_call");
+            assert_eq!(request.id(), &Id::Number(7));
+            serde_json::from_str::<serde_json::Value>(request.serialized().get()).unwrap()["params"]
+                .clone()
+        };
+        let tx = json!({"to": "0x0000000000000000000000000000000000000001", "input": "0x"});
+
+        let pinned = pin_block(&call(json!([tx, "latest"])), 16).unwrap();
+        assert_eq!(params_of(pinned), json!([tx, "0x10"]));
+        let pinned = pin_block(&call(json!([tx])), 16).unwrap();
+        assert_eq!(params_of(pinned), json!([tx, "0x10"]));
+
+        assert!(pin_block(&call(json!([tx, "0x5"])), 16).is_none());
+        assert!(pin_block(&call(json!([tx, "finalized"])), 16).is_none());
+    }
+}
@@ -1,5 +1,5 @@
 use alloy_network::{primitives::BlockTransactionsKind, AnyNetwork, TransactionResponse};
-use alloy_primitives::{BlockHash, BlockNumber, Bytes, TxHash, U256};
+use alloy_primitives::{Address, BlockHash, BlockNumber, Bytes, TxHash, U256};
 use alloy_provider::Provider;
 use alloy_rpc_types::TransactionTrait;
 use alloy_sol_types::SolCall;
@@ -7,7 +7,7 @@ use alloy_transport::BoxTransport;
 
 use crate::{
     call_type::DryRun,
-    events::get_event,
+    events::{get_event, get_shielder_events},
     ContractResult,
     ShielderContract::{
         depositERC20Call, depositNativeCall, newAccountERC20Call, newAccountNativeCall,
@@ -58,6 +58,29 @@ pub async fn get_shielder_action(
     Ok(None)
 }
 
+/// Get all the Shielder events emitted since the block where `nullifier` was spent (inclusive).
+/// If the nullifier has not been spent yet, no events are returned.
+///
+/// Unlike `get_shielder_action`, this doesn't download any block: the events are taken from the
+/// contract logs, in batches. It's up to the caller to pick the events that belong to the account
+/// (e.g. by comparing the notes).
+pub async fn get_shielder_events_since_nullifier(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    shielder_user: &ShielderUser,
+    contract_address: Address,
+    nullifier: U256,
+) -> ContractResult<Vec<(TxHash, ShielderContractEvents)>> {
+    let Some(from_block) = get_block_of_nullifier_spending(shielder_user, nullifier).await? else {
+        return Ok(vec![]);
+    };
+    let to_block = provider
+        .get_block_number()
+        .await
+        .map_err(ShielderContractError::ProviderError)?;
+
+    get_shielder_events(provider, contract_address, from_block, to_block).await
+}
+
 /// Get the block number where the nullifier was spent, if any.
 pub async fn get_block_of_nullifier_spending(
     shielder_user: &ShielderUser,
@@ -0,0 +1,200 @@
+use alloy_contract::Error;
+use alloy_primitives::{Address, Bytes, U256};
+use alloy_sol_types::SolInterface;
+use shielder_setup::version::ContractVersion;
+
+use crate::ShielderContract::{
+    ERC20InsufficientAllowance, ERC20InsufficientBalance, OwnableUnauthorizedAccount,
+    ShielderContractErrors, WrongContractVersion,
+};
+
+/// Reason of a Shielder contract revert, decoded from the revert data.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ShielderRevert {
+    #[error("Deposit proof verification failed")]
+    DepositVerificationFailed,
+    #[error("Nullifier has already been used")]
+    DuplicatedNullifier,
+    #[error("Fee is higher than the amount")]
+    FeeHigherThanAmount,
+    #[error("Merkle root is unknown to the contract")]
+    MerkleRootDoesNotExist,
+    #[error("Native token transfer failed")]
+    NativeTransferFailed,
+    #[error("Withdraw proof verification failed")]
+    WithdrawVerificationFailed,
+    #[error("New account proof verification failed")]
+    NewAccountVerificationFailed,
+    #[error("Amount must not be zero")]
+    ZeroAmount,
+    #[error("Amount is too high")]
+    AmountTooHigh,
+    #[error("Contract balance limit would be exceeded")]
+    ContractBalanceLimitReached,
+    #[error("Contract version is {actual:?}, but the caller expected {expected_by_caller:?}")]
+    WrongContractVersion {
+        actual: ContractVersion,
+        expected_by_caller: ContractVersion,
+    },
+    #[error("Value is not a field element")]
+    NotAFieldElement,
+    #[error("Point is not on the Grumpkin curve")]
+    InvalidGrumpkinPoint,
+    #[error("Account {account} is not authorized to make this call")]
+    OwnableUnauthorizedAccount { account: Address },
+    #[error("Contract is paused")]
+    EnforcedPause,
+    /// Raised by the token contract when the Shielder contract cannot transfer the tokens.
+    #[error("Allowance for {spender} is {allowance}, but {needed} is needed")]
+    ERC20InsufficientAllowance {
+        spender: Address,
+        allowance: U256,
+        needed: U256,
+    },
+    /// Raised by the token contract when the sender doesn't have enough tokens.
+    #[error("Balance of {sender} is {balance}, but {needed} is needed")]
+    ERC20InsufficientBalance {
+        sender: Address,
+        balance: U256,
+        needed: U256,
+    },
+}
+
+impl ShielderRevert {
+    /// Decode revert data returned by the Shielder contract. Returns `None` for data that is not
+    /// one of the known errors (e.g. a plain `revert()` or a custom error of a called token).
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        ShielderContractErrors::abi_decode(data, true)
+            .ok()
+            .map(Self::from)
+    }
+
+    /// Decode the revert reason of a failed call, if there is one.
+    pub fn from_call_error(error: &Error) -> Option<Self> {
+        Self::decode(&revert_data(error)?)
+    }
+}
+
+fn revert_data(error: &Error) -> Option<Bytes> {
+    match error {
+        Error::TransportError(error) => error.as_error_resp()?.as_revert_data(),
+        _ => None,
+    }
+}
+
+impl From<ShielderContractErrors> for ShielderRevert {
+    fn from(error: ShielderContractErrors) -> Self {
+        match error {
+            ShielderContractErrors::DepositVerificationFailed(_) => Self::DepositVerificationFailed,
+            ShielderContractErrors::DuplicatedNullifier(_) => Self::DuplicatedNullifier,
+            ShielderContractErrors::FeeHigherThanAmount(_) => Self::FeeHigherThanAmount,
+            ShielderContractErrors::MerkleRootDoesNotExist(_) => Self::MerkleRootDoesNotExist,
+            ShielderContractErrors::NativeTransferFailed(_) => Self::NativeTransferFailed,
+            ShielderContractErrors::WithdrawVerificationFailed(_) => {
+                Self::WithdrawVerificationFailed
+            }
+            ShielderContractErrors::NewAccountVerificationFailed(_) => {
+                Self::NewAccountVerificationFailed
+            }
+            ShielderContractErrors::ZeroAmount(_) => Self::ZeroAmount,
+            ShielderContractErrors::AmountTooHigh(_) => Self::AmountTooHigh,
+            ShielderContractErrors::ContractBalanceLimitReached(_) => {
+                Self::ContractBalanceLimitReached
+            }
+            ShielderContractErrors::WrongContractVersion(WrongContractVersion {
+                actual,
+                expectedByCaller,
+            }) => Self::WrongContractVersion {
+                actual: ContractVersion::from_bytes(actual),
+                expected_by_caller: ContractVersion::from_bytes(expectedByCaller),
+            },
+            ShielderContractErrors::NotAFieldElement(_) => Self::NotAFieldElement,
+            ShielderContractErrors::InvalidGrumpkinPoint(_) => Self::InvalidGrumpkinPoint,
+            ShielderContractErrors::OwnableUnauthorizedAccount(OwnableUnauthorizedAccount {
+                account,
+            }) => Self::OwnableUnauthorizedAccount { account },
+            ShielderContractErrors::EnforcedPause(_) => Self::EnforcedPause,
+            ShielderContractErrors::ERC20InsufficientAllowance(ERC20InsufficientAllowance {
+                spender,
+                allowance,
+                needed,
+            }) => Self::ERC20InsufficientAllowance {
+                spender,
+                allowance,
+                needed,
+            },
+            ShielderContractErrors::ERC20InsufficientBalance(ERC20InsufficientBalance {
+                sender,
+                balance,
+                needed,
+            }) => Self::ERC20InsufficientBalance {
+                sender,
+                balance,
+                needed,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{Address, FixedBytes, U256};
+    use alloy_sol_types::SolError;
+    use shielder_setup::version::ContractVersion;
+
+    use super::ShielderRevert;
+    use crate::ShielderContract::{
+        DuplicatedNullifier, ERC20InsufficientBalance, EnforcedPause, OwnableUnauthorizedAccount,
+        WrongContractVersion,
+    };
+
+    #[test]
+    fn shielder_errors_are_decoded() {
+        assert_eq!(
+            ShielderRevert::decode(&DuplicatedNullifier {}.abi_encode()),
+            Some(ShielderRevert::DuplicatedNullifier)
+        );
+
+        let account = Address::repeat_byte(7);
+        assert_eq!(
+            ShielderRevert::decode(&OwnableUnauthorizedAccount { account }.abi_encode()),
+            Some(ShielderRevert::OwnableUnauthorizedAccount { account })
+        );
+        assert_eq!(
+            ShielderRevert::decode(&EnforcedPause {}.abi_encode()),
+            Some(ShielderRevert::EnforcedPause)
+        );
+
+        let insufficient_balance = ERC20InsufficientBalance {
+            sender: account,
+            balance: U256::from(1),
+            needed: U256::from(2),
+        };
+        assert_eq!(
+            ShielderRevert::decode(&insufficient_balance.abi_encode()),
+            Some(ShielderRevert::ERC20InsufficientBalance {
+                sender: account,
+                balance: U256::from(1),
+                needed: U256::from(2),
+            })
+        );
+
+        let version = WrongContractVersion {
+            actual: FixedBytes([0, 1, 0]),
+            expectedByCaller: FixedBytes([0, 0, 1]),
+        };
+        assert_eq!(
+            ShielderRevert::decode(&version.abi_encode()),
+            Some(ShielderRevert::WrongContractVersion {
+                actual: ContractVersion::from_bytes(FixedBytes([0, 1, 0])),
+                expected_by_caller: ContractVersion::from_bytes(FixedBytes([0, 0, 1])),
+            })
+        );
+    }
+
+    #[test]
+    fn other_revert_data_is_not_decoded() {
+        assert_eq!(ShielderRevert::decode(&[]), None);
+        assert_eq!(ShielderRevert::decode(&[1, 2, 3, 4]), None);
+    }
+}
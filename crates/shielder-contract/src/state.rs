@@ -0,0 +1,101 @@
+use alloy_network::AnyNetwork;
+use alloy_primitives::{b256, keccak256, Address, B256, U256};
+use alloy_provider::Provider;
+use alloy_sol_types::SolValue;
+use alloy_transport::BoxTransport;
+
+use crate::{ContractResult, ShielderContract, ShielderContractError};
+
+/// ERC-7201 location of `MerkleTreeStorage` (namespace `zkos.storage.MerkleTree`).
+const MERKLE_TREE_LOCATION: B256 =
+    b256!("8dc6e18c8cfce769481c51bf18d0c9f95b9f5dd0b4b4cafa4091d6a06254b700");
+/// Offset of the `merkleRoots` set within `MerkleTreeStorage` (after `notes`, `root`,
+/// `nextFreeLeafId`, `maxLeafId` and `firstLeafId`).
+const MERKLE_ROOTS_OFFSET: u64 = 5;
+
+/// Whether `nullifier_hash` has already been used in the contract at `contract_address`.
+pub async fn is_nullifier_spent(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    contract_address: Address,
+    nullifier_hash: U256,
+) -> ContractResult<bool> {
+    let block_number = ShielderContract::new(contract_address, provider)
+        .nullifiers(nullifier_hash)
+        .call()
+        .await?
+        ._0;
+    Ok(block_number != U256::ZERO)
+}
+
+/// Whether `merkle_root` has ever been the root of the note tree of the contract at
+/// `contract_address`, i.e. whether a proof against it is accepted.
+///
+/// The contract doesn't expose this set, so the flag is read directly from its storage. A zero
+/// read is trusted only if the current root (exposed by the contract) is found at the expected
+/// location, i.e. the storage layout is the one assumed here. Otherwise, `None` is returned.
+pub async fn merkle_root_exists(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    contract_address: Address,
+    merkle_root: U256,
+) -> ContractResult<Option<bool>> {
+    if is_merkle_root_flag_set(provider, contract_address, merkle_root).await? {
+        return Ok(Some(true));
+    }
+
+    let current_root = ShielderContract::new(contract_address, provider)
+        .merkleTree()
+        .call()
+        .await?
+        .root;
+    match is_merkle_root_flag_set(provider, contract_address, current_root).await? {
+        true => Ok(Some(false)),
+        false => Ok(None),
+    }
+}
+
+async fn is_merkle_root_flag_set(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    contract_address: Address,
+    merkle_root: U256,
+) -> ContractResult<bool> {
+    let flag = provider
+        .get_storage_at(contract_address, merkle_root_slot(merkle_root))
+        .await
+        .map_err(ShielderContractError::ProviderError)?;
+    Ok(flag != U256::ZERO)
+}
+
+/// Current protocol fee (in basis points) charged on withdrawals by the contract at
+/// `contract_address`.
+pub async fn protocol_withdraw_fee_bps(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    contract_address: Address,
+) -> ContractResult<U256> {
+    Ok(ShielderContract::new(contract_address, provider)
+        .protocolWithdrawFeeBps()
+        .call()
+        .await?
+        ._0)
+}
+
+/// Storage slot of `merkleRoots.exists[merkle_root]`.
+fn merkle_root_slot(merkle_root: U256) -> U256 {
+    let set_slot = U256::from_be_bytes(MERKLE_TREE_LOCATION.0) + U256::from(MERKLE_ROOTS_OFFSET);
+    keccak256((merkle_root, set_slot).abi_encode()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::{keccak256, U256};
+
+    use super::MERKLE_TREE_LOCATION;
+
+    #[test]
+    fn merkle_tree_location_follows_erc7201() {
+        let namespace = U256::from_be_bytes(keccak256("zkos.storage.MerkleTree").0);
+        let location = keccak256((namespace - U256::from(1)).to_be_bytes::<32>());
+        let location = U256::from_be_bytes(location.0) & !U256::from(0xff);
+
+        assert_eq!(location, U256::from_be_bytes(MERKLE_TREE_LOCATION.0));
+    }
+}
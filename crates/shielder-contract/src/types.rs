@@ -4,7 +4,7 @@ use core::marker::PhantomData;
 use std::fmt::Debug;
 
 use alloy_contract::CallDecoder;
-use alloy_primitives::U256;
+use alloy_primitives::{Address, U256};
 use alloy_sol_types::{sol, SolCall};
 use shielder_setup::{
     shielder_circuits::GrumpkinPointAffine,
@@ -18,6 +18,13 @@ sol! {
     #[sol(rpc, all_derives = true)]
     #[derive(Debug, PartialEq, Eq)]
     contract ShielderContract {
+        struct PermitSignature {
+            uint256 deadline;
+            uint8 v;
+            bytes32 r;
+            bytes32 s;
+        }
+
         event NewAccount(
             bytes3 contractVersion,
             uint256 prenullifier,
@@ -72,6 +79,9 @@ sol! {
 
         error InvalidGrumpkinPoint();
         error OwnableUnauthorizedAccount(address account);
+        error EnforcedPause();
+        error ERC20InsufficientAllowance(address spender, uint256 allowance, uint256 needed);
+        error ERC20InsufficientBalance(address sender, uint256 balance, uint256 needed);
 
         function initialize(
             address initialOwner,
@@ -87,9 +97,19 @@ sol! {
 
         function pause() external;
         function unpause() external;
+        function paused() public view returns (bool);
 
         function setProtocolDepositFeeBps(uint256) external;
         function setProtocolWithdrawFeeBps(uint256) external;
+        function setProtocolFeeReceiver(address newProtocolFeeReceiver) external;
+        function protocolFeeReceiver() public view returns (address);
+
+        function owner() public view returns (address);
+        function pendingOwner() public view returns (address);
+        function transferOwnership(address newOwner) public;
+        function acceptOwnership() public;
+
+        function upgradeToAndCall(address newImplementation, bytes memory data) public payable;
 
         function newAccountNative(
             bytes3 expectedContractVersion,
@@ -119,6 +139,22 @@ sol! {
             bytes calldata proof,
             bytes calldata memo
         ) external whenNotPaused;
+        function newAccountERC20WithPermit(
+            bytes3 expectedContractVersion,
+            address tokenAddress,
+            uint256 amount,
+            uint256 newNote,
+            uint256 prenullifier,
+            uint256 symKeyEncryptionC1X,
+            uint256 symKeyEncryptionC1Y,
+            uint256 symKeyEncryptionC2X,
+            uint256 symKeyEncryptionC2Y,
+            uint256 macSalt,
+            uint256 macCommitment,
+            bytes calldata proof,
+            bytes calldata memo,
+            PermitSignature calldata permit
+        ) external;
         function depositNative(
             bytes3 expectedContractVersion,
             uint256 oldNullifierHash,
@@ -141,6 +177,19 @@ sol! {
             bytes calldata proof,
             bytes calldata memo
         ) external whenNotPaused;
+        function depositERC20WithPermit(
+            bytes3 expectedContractVersion,
+            address tokenAddress,
+            uint256 amount,
+            uint256 oldNullifierHash,
+            uint256 newNote,
+            uint256 merkleRoot,
+            uint256 macSalt,
+            uint256 macCommitment,
+            bytes calldata proof,
+            bytes calldata memo,
+            PermitSignature calldata permit
+        ) external;
         function withdrawNative(
             bytes3 expectedContractVersion,
             uint256 amount,
@@ -175,6 +224,11 @@ sol! {
             uint256 id
         ) external view returns (uint256[] memory);
 
+        function merkleTree()
+            public
+            view
+            returns (uint256 root, uint256 nextFreeLeafId, uint256 maxLeafId, uint256 firstLeafId);
+
         function anonymityRevokerPubkey() public view returns (uint256, uint256);
         function setAnonymityRevokerPubkey(
             uint256 anonymityRevokerPubkeyX,
@@ -195,6 +249,23 @@ impl ShielderContractEvents {
         }
     }
 
+    pub fn note_index(&self) -> U256 {
+        match self {
+            Self::NewAccount(NewAccount {
+                newNoteIndex: index,
+                ..
+            })
+            | Self::Deposit(Deposit {
+                newNoteIndex: index,
+                ..
+            })
+            | Self::Withdraw(Withdraw {
+                newNoteIndex: index,
+                ..
+            }) => *index,
+        }
+    }
+
     pub fn version(&self) -> ContractVersion {
         let version = match self {
             Self::NewAccount(NewAccount {
@@ -259,12 +330,20 @@ impl_unit_call!(unpauseCall);
 
 impl_unit_call!(setProtocolDepositFeeBpsCall);
 impl_unit_call!(setProtocolWithdrawFeeBpsCall);
+impl_unit_call!(setProtocolFeeReceiverCall);
+impl_unit_call!(setAnonymityRevokerPubkeyCall);
+
+impl_unit_call!(transferOwnershipCall);
+impl_unit_call!(acceptOwnershipCall);
+impl_unit_call!(upgradeToAndCallCall);
 
 impl_unit_call!(newAccountNativeCall);
 impl_unit_call!(depositNativeCall);
 impl_unit_call!(withdrawNativeCall);
 impl_unit_call!(newAccountERC20Call);
 impl_unit_call!(depositERC20Call);
+impl_unit_call!(newAccountERC20WithPermitCall);
+impl_unit_call!(depositERC20WithPermitCall);
 impl_unit_call!(withdrawERC20Call);
 
 impl ShielderContractCall for getMerklePathCall {
@@ -304,3 +383,31 @@ impl ShielderContractCall for protocolWithdrawFeeBpsCall {
         fee._0
     }
 }
+
+impl ShielderContractCall for pausedCall {
+    type UnwrappedResult = bool;
+    fn unwrap_result(paused: pausedReturn) -> Self::UnwrappedResult {
+        paused._0
+    }
+}
+
+impl ShielderContractCall for protocolFeeReceiverCall {
+    type UnwrappedResult = Address;
+    fn unwrap_result(receiver: protocolFeeReceiverReturn) -> Self::UnwrappedResult {
+        receiver._0
+    }
+}
+
+impl ShielderContractCall for ownerCall {
+    type UnwrappedResult = Address;
+    fn unwrap_result(owner: ownerReturn) -> Self::UnwrappedResult {
+        owner._0
+    }
+}
+
+impl ShielderContractCall for pendingOwnerCall {
+    type UnwrappedResult = Address;
+    fn unwrap_result(owner: pendingOwnerReturn) -> Self::UnwrappedResult {
+        owner._0
+    }
+}
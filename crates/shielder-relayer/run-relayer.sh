@@ -56,6 +56,9 @@ fi
 if [[ -n "${RELAYER_METRICS_PORT:-}" ]]; then
   ARGS+=(-e RELAYER_METRICS_PORT="${RELAYER_METRICS_PORT}")
 fi
+if [[ -n "${RELAYER_ADMIN_PORT:-}" ]]; then
+  ARGS+=(-e RELAYER_ADMIN_PORT="${RELAYER_ADMIN_PORT}")
+fi
 
 if [[ -n "${RELAY_GAS:-}" ]]; then
   ARGS+=(-e RELAY_GAS="${RELAY_GAS}")
@@ -87,9 +90,59 @@ fi
 if [[ -n "${QUOTE_VALIDITY:-}" ]]; then
   ARGS+=(-e QUOTE_VALIDITY="${QUOTE_VALIDITY}")
 fi
+if [[ -n "${QUOTE_SIGNING_KEY:-}" ]]; then
+  ARGS+=(-e QUOTE_SIGNING_KEY="${QUOTE_SIGNING_KEY}")
+fi
+if [[ -n "${ADMIN_TOKEN:-}" ]]; then
+  ARGS+=(-e ADMIN_TOKEN="${ADMIN_TOKEN}")
+fi
 if [[ -n "${MAX_POCKET_MONEY:-}" ]]; then
   ARGS+=(-e MAX_POCKET_MONEY="${MAX_POCKET_MONEY}")
 fi
+# Relay jobs are stored in the working directory of the container (`/data`) unless `JOB_STORE_PATH` points elsewhere.
+# Set `RELAYER_DATA_DIR` to a host directory (writable by `DOCKER_USER`) to keep them between container runs.
+if [[ -n "${RELAYER_DATA_DIR:-}" ]]; then
+  ARGS+=(-v "${RELAYER_DATA_DIR}:/data")
+fi
+if [[ -n "${JOB_STORE_PATH:-}" ]]; then
+  ARGS+=(-e JOB_STORE_PATH="${JOB_STORE_PATH}")
+fi
+if [[ -n "${TX_CONFIRMATIONS:-}" ]]; then
+  ARGS+=(-e TX_CONFIRMATIONS="${TX_CONFIRMATIONS}")
+fi
+if [[ -n "${RESUBMISSION_TIMEOUT:-}" ]]; then
+  ARGS+=(-e RESUBMISSION_TIMEOUT="${RESUBMISSION_TIMEOUT}")
+fi
+if [[ -n "${MAX_RESUBMISSIONS:-}" ]]; then
+  ARGS+=(-e MAX_RESUBMISSIONS="${MAX_RESUBMISSIONS}")
+fi
+if [[ -n "${PER_IP_RATE_LIMIT:-}" ]]; then
+  ARGS+=(-e PER_IP_RATE_LIMIT="${PER_IP_RATE_LIMIT}")
+fi
+if [[ -n "${GLOBAL_RATE_LIMIT:-}" ]]; then
+  ARGS+=(-e GLOBAL_RATE_LIMIT="${GLOBAL_RATE_LIMIT}")
+fi
+if [[ -n "${RELAY_GAS_ESTIMATION_INTERVAL:-}" ]]; then
+  ARGS+=(-e RELAY_GAS_ESTIMATION_INTERVAL="${RELAY_GAS_ESTIMATION_INTERVAL}")
+fi
+if [[ -n "${ADDITIONAL_CHAINS:-}" ]]; then
+  ARGS+=(-e ADDITIONAL_CHAINS="${ADDITIONAL_CHAINS}")
+fi
+if [[ -n "${RELAY_GAS_PERCENTILE:-}" ]]; then
+  ARGS+=(-e RELAY_GAS_PERCENTILE="${RELAY_GAS_PERCENTILE}")
+fi
+if [[ -n "${PRIORITY_FEE_PERCENTILE:-}" ]]; then
+  ARGS+=(-e PRIORITY_FEE_PERCENTILE="${PRIORITY_FEE_PERCENTILE}")
+fi
+if [[ -n "${BASE_FEE_HEADROOM_PERCENT:-}" ]]; then
+  ARGS+=(-e BASE_FEE_HEADROOM_PERCENT="${BASE_FEE_HEADROOM_PERCENT}")
+fi
+if [[ -n "${WITHDRAW_PARAMS_PATH:-}" ]]; then
+  ARGS+=(-e WITHDRAW_PARAMS_PATH="${WITHDRAW_PARAMS_PATH}")
+fi
+if [[ -n "${WITHDRAW_PK_PATH:-}" ]]; then
+  ARGS+=(-e WITHDRAW_PK_PATH="${WITHDRAW_PK_PATH}")
+fi
 
 DETACHED_FLAG=""
 if [[ "${DETACHED:-}" == "true" ]]; then
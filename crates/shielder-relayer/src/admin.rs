@@ -0,0 +1,156 @@
+use std::{str::FromStr, sync::Arc};
+
+use alloy_primitives::{keccak256, Address};
+use alloy_signer_local::PrivateKeySigner;
+use axum::{
+    extract::{Path, Request, State},
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+use shielder_relayer::server::{bad_request, not_found, success, success_response, unauthorized};
+use tracing::{info, warn};
+
+use crate::{
+    metrics::{ADMIN_SETTING_CHANGE, RELAYING_PAUSED},
+    monitor::balance_monitor::{forget_balance, track_balance},
+    quote_signing::constant_time_eq,
+    runtime_config::SettingsUpdate,
+    AppState,
+};
+
+#[derive(Deserialize)]
+pub struct AddSignerRequest {
+    pub signing_key: String,
+}
+
+/// Middleware rejecting requests that don't carry the admin token (`Authorization: Bearer ...`).
+pub async fn require_admin_token(
+    State(admin_token): State<Arc<str>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let provided = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    match provided {
+        // Hashing first, so that the comparison doesn't reveal the token length.
+        Some(token) if constant_time_eq(&keccak256(token), &keccak256(&*admin_token)) => {
+            next.run(req).await
+        }
+        _ => {
+            warn!(
+                "Rejected unauthorized admin request to {}",
+                req.uri().path()
+            );
+            unauthorized("Missing or invalid admin token")
+        }
+    }
+}
+
+/// List the signers used by the relay workers, together with their nonce state.
+pub async fn list_signers(State(state): State<AppState>) -> impl IntoResponse {
+    success_response(state.signer_pool.statuses())
+}
+
+/// Add a new signer and start a relay worker for it.
+pub async fn add_signer(
+    State(state): State<AppState>,
+    Json(request): Json<AddSignerRequest>,
+) -> impl IntoResponse {
+    let Ok(signer) = PrivateKeySigner::from_str(&request.signing_key) else {
+        return bad_request("Invalid signing key");
+    };
+    let address = signer.address();
+    if !state.signer_pool.add(signer.clone()) {
+        return bad_request(&format!("Signer {address} is already in use"));
+    }
+    track_balance(&state.signer_info.balances, address).await;
+    state.taskmaster.spawn_worker(signer);
+
+    info!("Admin: added signer {address}");
+    success(&format!("Signer {address} added"))
+}
+
+/// Remove a signer. Its worker finishes the current task and stops, transactions that were already
+/// sent are followed until they are final.
+pub async fn remove_signer(
+    State(state): State<AppState>,
+    Path(address): Path<Address>,
+) -> impl IntoResponse {
+    if state.signer_pool.signer_count() == 1 {
+        return bad_request("Cannot remove the last signer");
+    }
+    if !state.signer_pool.remove(address) {
+        return not_found(&format!("Signer {address} is not in use"));
+    }
+    forget_balance(&state.signer_info.balances, address).await;
+
+    info!("Admin: removed signer {address}");
+    success(&format!("Signer {address} removed"))
+}
+
+/// Get the current runtime settings.
+pub async fn get_settings(State(state): State<AppState>) -> impl IntoResponse {
+    success_response(state.runtime_config.settings())
+}
+
+/// Update some of the runtime settings. Returns the settings after the update.
+pub async fn update_settings(
+    State(state): State<AppState>,
+    Json(update): Json<SettingsUpdate>,
+) -> impl IntoResponse {
+    let changed = match state.runtime_config.update(update) {
+        Ok(changed) => changed,
+        Err(err) => return bad_request(&err.to_string()),
+    };
+    let settings = state.runtime_config.settings();
+
+    if changed.contains(&"token_config") {
+        state.prices.reconfigure(&settings.token_config);
+    }
+    for setting in &changed {
+        metrics::counter!(ADMIN_SETTING_CHANGE, "setting" => *setting).increment(1);
+    }
+    if !changed.is_empty() {
+        info!("Admin: changed {changed:?}, current settings: {settings:?}");
+    }
+    success_response(settings)
+}
+
+/// Stop accepting relay requests and stop the workers from taking queued tasks. Transactions that
+/// were already sent are still followed.
+pub async fn pause(State(state): State<AppState>) -> impl IntoResponse {
+    if !state.runtime_config.set_paused(true) {
+        return success("Relaying is already paused");
+    }
+    metrics::gauge!(RELAYING_PAUSED, "chain_id" => state.chain_id.to_string()).set(1.);
+    metrics::counter!(ADMIN_SETTING_CHANGE, "setting" => "paused").increment(1);
+
+    info!("Admin: relaying paused");
+    success("Relaying paused")
+}
+
+/// Resume relaying after `pause`.
+pub async fn resume(State(state): State<AppState>) -> impl IntoResponse {
+    if !state.runtime_config.set_paused(false) {
+        return success("Relaying is not paused");
+    }
+    metrics::gauge!(RELAYING_PAUSED, "chain_id" => state.chain_id.to_string()).set(0.);
+    metrics::counter!(ADMIN_SETTING_CHANGE, "setting" => "paused").increment(1);
+
+    info!("Admin: relaying resumed");
+    success("Relaying resumed")
+}
+
+/// Drop all the relay requests that are waiting for a worker. Their clients get an error.
+pub async fn drain(State(state): State<AppState>) -> impl IntoResponse {
+    let drained = state.taskmaster.drain();
+
+    info!("Admin: dropped {drained} queued relay tasks");
+    success(&format!("Dropped {drained} queued relay tasks"))
+}
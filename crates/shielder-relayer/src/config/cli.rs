@@ -4,9 +4,12 @@ use alloy_primitives::U256;
 use clap::Parser;
 use shielder_relayer::*;
 
-use crate::config::{
-    defaults::*,
-    enums::{DryRunning, LoggingFormat, NoncePolicy},
+use crate::{
+    config::{
+        defaults::*,
+        enums::{DryRunning, LoggingFormat, NoncePolicy},
+    },
+    rate_limit::IpNetwork,
 };
 
 /// Configuration for the Shielder relayer through the command line arguments.
@@ -52,6 +55,16 @@ pub struct CLIConfig {
     )]
     pub metrics_port: Option<u16>,
 
+    #[clap(
+        long,
+        help = "Port where the admin API should be exposed.",
+        long_help = format!("Port where the admin API should be exposed (only if `--admin-token` is \
+            set). If not provided, the value from the environment variable \
+            `{RELAYER_ADMIN_PORT_ENV}` will be used. If that is not set, the default value is \
+            `{DEFAULT_ADMIN_PORT}`.")
+    )]
+    pub admin_port: Option<u16>,
+
     #[clap(
         long,
         help = "Interval (in seconds) for monitoring signers' balances.",
@@ -66,8 +79,10 @@ pub struct CLIConfig {
     #[clap(
         long,
         help = "URL of the Ethereum RPC node.",
-        long_help = format!("URL of the Ethereum RPC node. If not provided, the value from the \
-            environment variable `{NODE_RPC_URL_ENV}` will be used.")
+        long_help = format!("URL of the Ethereum RPC node. Several comma-separated URLs can be \
+            given - requests then go to the healthiest node and fail over to the others. With a \
+            `|quorum=N` suffix (e.g. `https://a,https://b,https://c|quorum=2`), nullifier and \
+            Merkle path reads must return the same result from N nodes. If not provided, the value from the environment variable `{NODE_RPC_URL_ENV}` will be used.")
     )]
     pub node_rpc_url: Option<String>,
 
@@ -96,12 +111,33 @@ pub struct CLIConfig {
     )]
     pub signing_keys: Option<Vec<String>>,
 
+    #[clap(
+        long,
+        help = "Secret used to sign fee quotes.",
+        long_help = format!("Secret used to sign fee quotes. All the relayer instances behind a load \
+            balancer must share it, so that a quote issued by one of them is accepted by the others. \
+            If not provided, the value from the environment variable `{QUOTE_SIGNING_KEY_ENV}` will \
+            be used. If that is not set, a random key is generated on startup.")
+    )]
+    pub quote_signing_key: Option<String>,
+
+    #[clap(
+        long,
+        help = "Token required by the admin API.",
+        long_help = format!("Token required by the admin API (as `Authorization: Bearer <token>`). \
+            If not provided, the value from the environment variable `{ADMIN_TOKEN_ENV}` will be \
+            used. If that is not set, the admin API is disabled.")
+    )]
+    pub admin_token: Option<String>,
+
     #[clap(
         long,
         value_enum,
         help = "Nonce management policy.",
-        long_help = format!("Nonce management policy. If not provided, the value from the \
-            environment variable `{NONCE_POLICY_ENV}` will be used. If that is not set, the \
+        long_help = format!("Nonce management policy. Nonces of relay transactions are always \
+            assigned by the signer pool - this only decides whether a worker keeps its connection \
+            (`Caching`) or connects for every call (`Stateless`). If not provided, the value from \
+            the environment variable `{NONCE_POLICY_ENV}` will be used. If that is not set, the \
             default value is `{DEFAULT_NONCE_POLICY:?}`.")
     )]
     pub nonce_policy: Option<NoncePolicy>,
@@ -159,11 +195,26 @@ pub struct CLIConfig {
             [{{\"kind\":\"Native\", \"price_provider\":{{\"Static\":\"12.3\"}}}}] \
             \
             This example configure a token to use URL price feed for its pricing: \
-            [{{\"kind\":{{\"ERC20\":{{\"address\": \"0x6b175474e89094c44da98b954eedeac495271d0f\", \"decimals\":18}}}},\"price_provider\": {{\"Url\":\"https://price.feed\"}}}}]
+            [{{\"kind\":{{\"ERC20\":{{\"address\": \"0x6b175474e89094c44da98b954eedeac495271d0f\", \"decimals\":18}}}},\"price_provider\": {{\"Url\":\"https://price.feed\"}}}}] \
+            \
+            Other price providers are `CoinGecko`, `Chainlink`, `Relayer` and `Median` (aggregating other providers). \
+            See README for their format.
             ")
     )]
     pub token_config: Option<String>,
 
+    #[clap(
+        long,
+        help = "Other Shielder deployments served by this relayer.",
+        long_help = format!("Other Shielder deployments (on other chains) served by this relayer, \
+            as a JSON list. Every entry needs `node_rpc_url`, `shielder_contract_address` and \
+            `job_store_path`, and can override `relay_gas` and `token_config` (otherwise the main \
+            values are used). The same signing keys are used on every chain. If not provided, the \
+            value from the environment variable `{ADDITIONAL_CHAINS_ENV}` will be used. If that is \
+            not set, only the main deployment is served.")
+    )]
+    pub additional_chains: Option<String>,
+
     #[clap(
         long,
         help = "Price feed refresh interval in seconds.",
@@ -212,6 +263,148 @@ pub struct CLIConfig {
         value_parser = parsing::parse_u256
     )]
     pub max_pocket_money: Option<U256>,
+
+    #[clap(
+        long,
+        help = "Path to the SQLite database with relay jobs.",
+        long_help = format!("Path to the SQLite database with relay jobs. Jobs that were not finished \
+            before a restart are resumed from there. Relative paths are resolved against the working \
+            directory (`/data` in the Docker image). If not provided, the value from the environment \
+            variable `{JOB_STORE_PATH_ENV}` will be used. If that is not set, the default value is \
+            `{DEFAULT_JOB_STORE_PATH}`.")
+    )]
+    pub job_store_path: Option<String>,
+
+    #[clap(
+        long,
+        help = "Number of blocks after which a relayed transaction is considered final.",
+        long_help = format!("Number of blocks (including the one with the transaction) after which a \
+            relayed transaction is considered final. If not provided, the value from the environment \
+            variable `{TX_CONFIRMATIONS_ENV}` will be used. If that is not set, the default value is \
+            `{DEFAULT_TX_CONFIRMATIONS}`.")
+    )]
+    pub tx_confirmations: Option<u64>,
+
+    #[clap(
+        long,
+        help = "Seconds to wait for a relayed transaction before resubmitting it with higher fees.",
+        long_help = format!("How long to wait for a relayed transaction to be mined before resubmitting \
+            it (with the same nonce) with higher fees. In seconds. If not provided, the value from the \
+            environment variable `{RESUBMISSION_TIMEOUT_ENV}` will be used. If that is not set, the \
+            default value is `{}`.", DEFAULT_RESUBMISSION_TIMEOUT.as_secs()),
+        value_parser = parsing::parse_seconds
+    )]
+    pub resubmission_timeout: Option<Duration>,
+
+    #[clap(
+        long,
+        help = "How many times a relayed transaction can be resubmitted.",
+        long_help = format!("How many times a relayed transaction can be resubmitted before the \
+            relayer stops tracking it actively. If not provided, the value from the environment \
+            variable `{MAX_RESUBMISSIONS_ENV}` will be used. If that is not set, the default value is \
+            `{DEFAULT_MAX_RESUBMISSIONS}`.")
+    )]
+    pub max_resubmissions: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Maximum number of requests per minute from a single IP (0 for no limit).",
+        long_help = format!("Maximum number of requests per minute to the quote and relay endpoints \
+            from a single IP address (0 for no limit). Short bursts up to this number are allowed. If \
+            not provided, the value from the environment variable `{PER_IP_RATE_LIMIT_ENV}` will be \
+            used. If that is not set, the default value is `{DEFAULT_PER_IP_RATE_LIMIT}`.")
+    )]
+    pub per_ip_rate_limit: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Maximum number of requests per minute from all clients (0 for no limit).",
+        long_help = format!("Maximum number of requests per minute to the quote and relay endpoints \
+            from all clients together (0 for no limit). Short bursts up to this number are allowed. If \
+            not provided, the value from the environment variable `{GLOBAL_RATE_LIMIT_ENV}` will be \
+            used. If that is not set, the default value is `{DEFAULT_GLOBAL_RATE_LIMIT}`.")
+    )]
+    pub global_rate_limit: Option<u32>,
+
+    #[clap(
+        long = "trusted-proxy",
+        help = "Reverse proxies (IPs or CIDR ranges) whose `X-Forwarded-For` is trusted.",
+        long_help = format!("Reverse proxies (IP addresses or CIDR ranges, e.g. `10.0.0.0/8`) in \
+            front of the relayer. For requests coming from them, the client IP used for rate \
+            limiting is the right-most `X-Forwarded-For` entry that is not a trusted proxy. If not \
+            provided, the comma-separated value from the environment variable \
+            `{TRUSTED_PROXIES_ENV}` will be used. If that is not set, no proxy is trusted and the \
+            header is ignored."),
+        num_args = 1..
+    )]
+    pub trusted_proxies: Option<Vec<IpNetwork>>,
+
+    #[clap(
+        long,
+        help = "How often (at most) withdraw gas is estimated for every fee token (in seconds).",
+        long_help = format!("How often (at most) the gas of a relay is estimated for every fee \
+            token (in seconds). The estimate is done on a recently received relay request. `0` \
+            disables estimation - then only the configured relay gas is used. If not provided, the \
+            value from the environment variable `{RELAY_GAS_ESTIMATION_INTERVAL_ENV}` will be \
+            used. If that is not set, the default value is `{}`.",
+            DEFAULT_RELAY_GAS_ESTIMATION_INTERVAL.as_secs()),
+        value_parser = parsing::parse_seconds
+    )]
+    pub relay_gas_estimation_interval: Option<Duration>,
+
+    #[clap(
+        long,
+        help = "Percentile of the recent gas estimates used for pricing relays.",
+        long_help = format!("Percentile (1-100) of the recent relay gas estimates that is used \
+            for pricing relays. If not provided, the value from the environment variable \
+            `{RELAY_GAS_PERCENTILE_ENV}` will be used. If that is not set, the default value is \
+            `{DEFAULT_RELAY_GAS_PERCENTILE}`."),
+        value_parser = clap::value_parser!(u32).range(1..=100)
+    )]
+    pub relay_gas_percentile: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Percentile of the recent priority fees paid by relays.",
+        long_help = format!("Percentile (1-100) of the priority fees paid in the recent blocks \
+            (as reported by `eth_feeHistory`) that relay transactions pay. If not provided, the \
+            value from the environment variable `{PRIORITY_FEE_PERCENTILE_ENV}` will be used. If \
+            that is not set, the default value is `{DEFAULT_PRIORITY_FEE_PERCENTILE}`."),
+        value_parser = clap::value_parser!(u32).range(1..=100)
+    )]
+    pub priority_fee_percentile: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Headroom for base fee growth in quoted gas prices (in percent).",
+        long_help = format!("By how much (in percent) the base fee may grow between a quote and \
+            the relay. Quoted gas prices include this headroom on top of the next block base fee. \
+            If not provided, the value from the environment variable \
+            `{BASE_FEE_HEADROOM_PERCENT_ENV}` will be used. If that is not set, the default value \
+            is `{DEFAULT_BASE_FEE_HEADROOM_PERCENT}`.")
+    )]
+    pub base_fee_headroom_percent: Option<u32>,
+
+    #[clap(
+        long,
+        help = "Path to the withdraw circuit parameters (`params.bin`).",
+        long_help = format!("Path to the withdraw circuit parameters (`params.bin`). Together with \
+            `--withdraw-pk-path` enables local verification of withdrawal proofs, so that requests \
+            with invalid proofs are rejected before being dry-run. If not provided, the value from \
+            the environment variable `{WITHDRAW_PARAMS_PATH_ENV}` will be used. If that is not \
+            set, proofs are not verified locally.")
+    )]
+    pub withdraw_params_path: Option<String>,
+
+    #[clap(
+        long,
+        help = "Path to the withdraw circuit proving key (`pk.bin`).",
+        long_help = format!("Path to the withdraw circuit proving key (`pk.bin`), from which the \
+            verifying key is taken. See `--withdraw-params-path`. If not provided, the value from \
+            the environment variable `{WITHDRAW_PK_PATH_ENV}` will be used. If that is not set, \
+            proofs are not verified locally.")
+    )]
+    pub withdraw_pk_path: Option<String>,
 }
 
 pub(super) mod parsing {
@@ -19,3 +19,14 @@ pub const DEFAULT_PRICE_FEED_REFRESH_INTERVAL: Duration = Duration::from_secs(60
 pub const DEFAULT_SERVICE_FEE_PERCENT: u32 = 15;
 pub const DEFAULT_QUOTE_VALIDITY: Duration = Duration::from_secs(15);
 pub const DEFAULT_MAX_POCKET_MONEY: &str = "100_000_000_000_000_000"; // 0.1 TZERO
+pub const DEFAULT_JOB_STORE_PATH: &str = "shielder-relayer-jobs.sqlite"; // in the working directory
+pub const DEFAULT_TX_CONFIRMATIONS: u64 = 1;
+pub const DEFAULT_RESUBMISSION_TIMEOUT: Duration = Duration::from_secs(60);
+pub const DEFAULT_MAX_RESUBMISSIONS: u32 = 3;
+pub const DEFAULT_PER_IP_RATE_LIMIT: u32 = 60;
+pub const DEFAULT_GLOBAL_RATE_LIMIT: u32 = 600;
+pub const DEFAULT_ADMIN_PORT: u16 = 9616;
+pub const DEFAULT_RELAY_GAS_ESTIMATION_INTERVAL: Duration = Duration::from_secs(60);
+pub const DEFAULT_RELAY_GAS_PERCENTILE: u32 = 90;
+pub const DEFAULT_PRIORITY_FEE_PERCENTILE: u32 = 50;
+pub const DEFAULT_BASE_FEE_HEADROOM_PERCENT: u32 = 25;
@@ -1,6 +1,8 @@
 use std::str::FromStr;
 
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, ValueEnum)]
 pub enum LoggingFormat {
     #[default]
@@ -39,7 +41,7 @@ impl FromStr for NoncePolicy {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, ValueEnum)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, ValueEnum, Serialize, Deserialize)]
 pub enum DryRunning {
     #[default]
     Always,
@@ -9,10 +9,14 @@ use clap::Parser;
 use cli::CLIConfig;
 use defaults::*;
 pub use enums::{DryRunning, LoggingFormat, NoncePolicy};
+use serde::Deserialize;
 use shielder_contract::alloy_primitives::{Address, U256};
 use shielder_relayer::*;
 
-use crate::config::cli::parsing::{parse_seconds, parse_u256};
+use crate::{
+    config::cli::parsing::{parse_seconds, parse_u256},
+    rate_limit::{parse_ip_networks, IpNetwork},
+};
 
 mod cli;
 mod defaults;
@@ -25,6 +29,7 @@ pub struct NetworkConfig {
     pub host: String,
     pub port: u16,
     pub metrics_port: u16,
+    pub admin_port: u16,
 }
 
 impl NetworkConfig {
@@ -35,6 +40,10 @@ impl NetworkConfig {
     pub fn metrics_address(&self) -> String {
         format!("{}:{}", self.host, self.metrics_port)
     }
+
+    pub fn admin_address(&self) -> String {
+        format!("{}:{}", self.host, self.admin_port)
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
@@ -44,6 +53,21 @@ pub struct ChainConfig {
     pub relay_gas: u64,
 }
 
+/// Another Shielder deployment served by the same relayer process. Values that are not set are
+/// taken from the main configuration.
+#[derive(Clone, Eq, PartialEq, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AdditionalChain {
+    pub node_rpc_url: String,
+    pub shielder_contract_address: Address,
+    /// Every chain needs its own store - job ids are not unique across chains.
+    pub job_store_path: String,
+    #[serde(default)]
+    pub relay_gas: Option<u64>,
+    #[serde(default)]
+    pub token_config: Option<Vec<TokenInfo>>,
+}
+
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct OperationalConfig {
     pub balance_monitor_interval: Duration,
@@ -58,12 +82,27 @@ pub struct OperationalConfig {
     pub service_fee_percent: u32,
     pub quote_validity: Duration,
     pub max_pocket_money: U256,
+    pub job_store_path: String,
+    pub tx_confirmations: u64,
+    pub resubmission_timeout: Duration,
+    pub max_resubmissions: u32,
+    pub per_ip_rate_limit: u32,
+    pub global_rate_limit: u32,
+    pub trusted_proxies: Vec<IpNetwork>,
+    pub relay_gas_estimation_interval: Duration,
+    pub relay_gas_percentile: u32,
+    pub priority_fee_percentile: u32,
+    pub base_fee_headroom_percent: u32,
+    pub withdraw_params_path: Option<String>,
+    pub withdraw_pk_path: Option<String>,
 }
 
 #[derive(Clone, Eq, PartialEq)]
 pub struct KeyConfig {
     pub fee_destination_key: String,
     pub signing_keys: Vec<String>,
+    pub quote_signing_key: Option<String>,
+    pub admin_token: Option<String>,
 }
 
 impl Debug for KeyConfig {
@@ -79,6 +118,14 @@ impl Debug for KeyConfig {
                 "signing_keys",
                 &self.signing_keys.iter().map(fmt_key).collect::<Vec<_>>(),
             )
+            .field(
+                "quote_signing_key",
+                &self.quote_signing_key.as_ref().map(|_| "<secret>"),
+            )
+            .field(
+                "admin_token",
+                &self.admin_token.as_ref().map(|_| "<secret>"),
+            )
             .finish()
     }
 }
@@ -96,6 +143,35 @@ pub struct ServerConfig {
     pub chain: ChainConfig,
     pub operations: OperationalConfig,
     pub keys: KeyConfig,
+    pub additional_chains: Vec<AdditionalChain>,
+}
+
+impl ServerConfig {
+    /// Configuration of every served deployment: the main one first, then the additional ones.
+    /// The returned configurations have no additional chains.
+    pub fn deployments(&self) -> Vec<ServerConfig> {
+        let main = ServerConfig {
+            additional_chains: vec![],
+            ..self.clone()
+        };
+        let additional = self.additional_chains.iter().map(|chain| ServerConfig {
+            chain: ChainConfig {
+                node_rpc_url: chain.node_rpc_url.clone(),
+                shielder_contract_address: chain.shielder_contract_address,
+                relay_gas: chain.relay_gas.unwrap_or(self.chain.relay_gas),
+            },
+            operations: OperationalConfig {
+                token_config: chain
+                    .token_config
+                    .clone()
+                    .unwrap_or_else(|| self.operations.token_config.clone()),
+                job_store_path: chain.job_store_path.clone(),
+                ..self.operations.clone()
+            },
+            ..main.clone()
+        });
+        std::iter::once(main.clone()).chain(additional).collect()
+    }
 }
 
 /// Resolves the configuration for the Shielder relayer using the command line arguments,
@@ -110,22 +186,35 @@ fn resolve_config_from_cli_config(
         host,
         port,
         metrics_port,
+        admin_port,
         balance_monitor_interval,
         node_rpc_url,
         shielder_contract_address,
         fee_destination_key,
         signing_keys,
+        quote_signing_key,
+        admin_token,
         nonce_policy,
         dry_running,
         recharge_threshold,
         recharge_amount,
         relay_gas,
         token_config,
+        additional_chains,
         price_feed_validity,
         price_feed_refresh_interval,
         service_fee_percent,
         quote_validity,
         max_pocket_money,
+        job_store_path,
+        tx_confirmations,
+        resubmission_timeout,
+        max_resubmissions,
+        per_ip_rate_limit,
+        global_rate_limit,
+        trusted_proxies,
+        withdraw_params_path,
+        withdraw_pk_path,
     }: CLIConfig,
 ) -> ServerConfig {
     let to_address = |s: &str| Address::from_str(s).expect("Invalid address");
@@ -141,6 +230,8 @@ fn resolve_config_from_cli_config(
     let key_config = KeyConfig {
         fee_destination_key: resolve_value(fee_destination_key, FEE_DESTINATION_KEY_ENV, None),
         signing_keys,
+        quote_signing_key: quote_signing_key.or_else(|| std::env::var(QUOTE_SIGNING_KEY_ENV).ok()),
+        admin_token: admin_token.or_else(|| std::env::var(ADMIN_TOKEN_ENV).ok()),
     };
 
     let network_config = NetworkConfig {
@@ -151,6 +242,7 @@ fn resolve_config_from_cli_config(
             RELAYER_METRICS_PORT_ENV,
             Some(DEFAULT_METRICS_PORT),
         ),
+        admin_port: resolve_value(admin_port, RELAYER_ADMIN_PORT_ENV, Some(DEFAULT_ADMIN_PORT)),
     };
 
     let chain_config = ChainConfig {
@@ -166,7 +258,17 @@ fn resolve_config_from_cli_config(
     let token_config = token_config
         .or_else(|| std::env::var(TOKEN_CONFIG_ENV).ok())
         .expect("Missing token configuration");
-    let token_config = serde_json::from_str(&token_config).expect("Invalid token configuration");
+    let token_config: Vec<TokenInfo> =
+        serde_json::from_str(&token_config).expect("Invalid token configuration");
+    validate_fee_policies(&token_config);
+
+    let additional_chains: Vec<AdditionalChain> = additional_chains
+        .or_else(|| std::env::var(ADDITIONAL_CHAINS_ENV).ok())
+        .map(|chains| serde_json::from_str(&chains).expect("Invalid additional chains"))
+        .unwrap_or_default();
+    for chain in &additional_chains {
+        validate_fee_policies(chain.token_config.as_deref().unwrap_or_default());
+    }
 
     let operational_config = OperationalConfig {
         balance_monitor_interval: resolve_value_map(
@@ -225,6 +327,67 @@ fn resolve_config_from_cli_config(
             parse_u256,
             Some(parse_u256(DEFAULT_MAX_POCKET_MONEY).unwrap()),
         ),
+        job_store_path: resolve_value(
+            job_store_path,
+            JOB_STORE_PATH_ENV,
+            Some(DEFAULT_JOB_STORE_PATH.to_string()),
+        ),
+        tx_confirmations: resolve_value(
+            tx_confirmations,
+            TX_CONFIRMATIONS_ENV,
+            Some(DEFAULT_TX_CONFIRMATIONS),
+        ),
+        resubmission_timeout: resolve_value_map(
+            resubmission_timeout,
+            RESUBMISSION_TIMEOUT_ENV,
+            parse_seconds,
+            Some(DEFAULT_RESUBMISSION_TIMEOUT),
+        ),
+        max_resubmissions: resolve_value(
+            max_resubmissions,
+            MAX_RESUBMISSIONS_ENV,
+            Some(DEFAULT_MAX_RESUBMISSIONS),
+        ),
+        per_ip_rate_limit: resolve_value(
+            per_ip_rate_limit,
+            PER_IP_RATE_LIMIT_ENV,
+            Some(DEFAULT_PER_IP_RATE_LIMIT),
+        ),
+        global_rate_limit: resolve_value(
+            global_rate_limit,
+            GLOBAL_RATE_LIMIT_ENV,
+            Some(DEFAULT_GLOBAL_RATE_LIMIT),
+        ),
+        trusted_proxies: resolve_value_map(
+            trusted_proxies,
+            TRUSTED_PROXIES_ENV,
+            parse_ip_networks,
+            Some(vec![]),
+        ),
+        relay_gas_estimation_interval: resolve_value_map(
+            relay_gas_estimation_interval,
+            RELAY_GAS_ESTIMATION_INTERVAL_ENV,
+            parse_seconds,
+            Some(DEFAULT_RELAY_GAS_ESTIMATION_INTERVAL),
+        ),
+        relay_gas_percentile: resolve_value(
+            relay_gas_percentile,
+            RELAY_GAS_PERCENTILE_ENV,
+            Some(DEFAULT_RELAY_GAS_PERCENTILE),
+        ),
+        priority_fee_percentile: resolve_value(
+            priority_fee_percentile,
+            PRIORITY_FEE_PERCENTILE_ENV,
+            Some(DEFAULT_PRIORITY_FEE_PERCENTILE),
+        ),
+        base_fee_headroom_percent: resolve_value(
+            base_fee_headroom_percent,
+            BASE_FEE_HEADROOM_PERCENT_ENV,
+            Some(DEFAULT_BASE_FEE_HEADROOM_PERCENT),
+        ),
+        withdraw_params_path: withdraw_params_path
+            .or_else(|| std::env::var(WITHDRAW_PARAMS_PATH_ENV).ok()),
+        withdraw_pk_path: withdraw_pk_path.or_else(|| std::env::var(WITHDRAW_PK_PATH_ENV).ok()),
     };
 
     ServerConfig {
@@ -237,6 +400,15 @@ fn resolve_config_from_cli_config(
         chain: chain_config,
         operations: operational_config,
         keys: key_config,
+        additional_chains,
+    }
+}
+
+fn validate_fee_policies(token_config: &[TokenInfo]) {
+    for token in token_config {
+        if let Err(err) = token.fee_policy.validate() {
+            panic!("Invalid fee policy for {}: {err}", token.kind);
+        }
     }
 }
 
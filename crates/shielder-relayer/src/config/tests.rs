@@ -11,6 +11,135 @@ fn verify_cli() {
     CLIConfig::command().debug_assert()
 }
 
+#[test]
+fn aggregated_price_provider_is_parsed() {
+    let token_config: Vec<TokenInfo> = serde_json::from_str(
+        r#"[{
+            "kind": "Native",
+            "price_provider": {"Median": {
+                "providers": [
+                    {"Url": "https://price.feed"},
+                    {"CoinGecko": {"url": "https://coingecko.feed", "id": "ethereum"}},
+                    {"Chainlink": {
+                        "rpc_url": "http://localhost:8545",
+                        "aggregator": "0x1111111111111111111111111111111111111111"
+                    }},
+                    {"Relayer": "https://relayer"},
+                    {"Static": "1.5"}
+                ],
+                "quorum": 3,
+                "max_deviation_percent": 5
+            }}
+        }]"#,
+    )
+    .unwrap();
+
+    assert!(
+        token_config[0].price_provider
+            == PriceProvider::Median {
+                providers: vec![
+                    PriceProvider::Url("https://price.feed".to_string()),
+                    PriceProvider::CoinGecko {
+                        url: "https://coingecko.feed".to_string(),
+                        id: "ethereum".to_string(),
+                    },
+                    PriceProvider::Chainlink {
+                        rpc_url: "http://localhost:8545".to_string(),
+                        aggregator: address!("1111111111111111111111111111111111111111"),
+                    },
+                    PriceProvider::Relayer("https://relayer".to_string()),
+                    PriceProvider::Static(Decimal::new(15, 1)),
+                ],
+                quorum: 3,
+                max_deviation_percent: 5,
+            }
+    );
+}
+
+#[test]
+fn token_fee_policy_is_parsed() {
+    let token_config: Vec<TokenInfo> = serde_json::from_str(
+        r#"[
+            {"kind": "Native", "price_provider": {"Static": "1"}},
+            {
+                "kind": {"ERC20": {
+                    "address": "0x2222222222222222222222222222222222222222",
+                    "decimals": 6
+                }},
+                "price_provider": {"Static": "1"},
+                "relay_gas": 2500000,
+                "service_fee_percent": 25,
+                "min_fee": "100000",
+                "max_fee": "5000000"
+            }
+        ]"#,
+    )
+    .unwrap();
+
+    assert!(token_config[0].fee_policy == TokenFeePolicy::default());
+    assert!(
+        token_config[1].fee_policy
+            == TokenFeePolicy {
+                relay_gas: Some(2_500_000),
+                service_fee_percent: Some(25),
+                min_fee: Some(U256::from(100_000)),
+                max_fee: Some(U256::from(5_000_000)),
+            }
+    );
+}
+
+#[test]
+fn additional_chains_inherit_main_configuration() {
+    let additional_chains: Vec<AdditionalChain> = serde_json::from_str(
+        r#"[
+            {
+                "node_rpc_url": "http://chain-b:8545",
+                "shielder_contract_address": "0x3333333333333333333333333333333333333333",
+                "job_store_path": "/data/chain-b.sqlite"
+            },
+            {
+                "node_rpc_url": "http://chain-c:8545",
+                "shielder_contract_address": "0x4444444444444444444444444444444444444444",
+                "job_store_path": "/data/chain-c.sqlite",
+                "relay_gas": 3000000,
+                "token_config": []
+            }
+        ]"#,
+    )
+    .unwrap();
+
+    let mut config = resolve_config_from_cli_config(CLIConfig::parse_from([
+        "shielder-relayer",
+        "--node-rpc-url",
+        "http://chain-a:8545",
+        "--shielder-contract-address",
+        "0x2222222222222222222222222222222222222222",
+        "--fee-destination-key",
+        "key0",
+        "--signing-keys",
+        "key1",
+        "--token-config",
+        r#"[{"kind": "Native", "price_provider": {"Static": "1"}}]"#,
+    ]));
+    config.additional_chains = additional_chains;
+
+    let deployments = config.deployments();
+    assert!(deployments.len() == 3);
+    assert!(deployments.iter().all(|d| d.additional_chains.is_empty()));
+    assert!(deployments[0].chain == config.chain);
+    assert!(deployments[0].operations == config.operations);
+
+    assert!(deployments[1].chain.node_rpc_url == "http://chain-b:8545");
+    assert!(deployments[1].chain.relay_gas == config.chain.relay_gas);
+    assert!(deployments[1].operations.token_config == config.operations.token_config);
+    assert!(deployments[1].operations.job_store_path == "/data/chain-b.sqlite");
+    assert!(deployments[1].keys == config.keys);
+
+    assert!(deployments[2].chain.relay_gas == 3_000_000);
+    assert!(deployments[2].operations.token_config.is_empty());
+    assert!(deployments[2].operations.service_fee_percent == config.operations.service_fee_percent);
+}
+
 #[test]
 fn config_resolution() {
     // ---- Target configuration. --------------------------------------------------------------
@@ -18,12 +147,15 @@ fn config_resolution() {
     let host = DEFAULT_HOST.to_string();
     let port = 1234;
     let metrics_port = 5678;
+    let admin_port = 5679;
     let balance_monitor_interval = Duration::from_secs(60);
     let node_rpc_url = "http://localhost:8545".to_string();
     let shielder_contract_address = address!("0000000000000000000000000000000000000000");
     let fee_destination_key = "key0".to_string();
     let key1 = "key1".to_string();
     let key2 = "key2".to_string();
+    let quote_signing_key = Some("quote-key".to_string());
+    let admin_token = Some("admin-token".to_string());
     let nonce_policy = DEFAULT_NONCE_POLICY;
     let dry_running = DryRunning::Always;
     let recharge_threshold = U256::from_str(DEFAULT_RECHARGE_THRESHOLD).unwrap();
@@ -33,6 +165,7 @@ fn config_resolution() {
         TokenInfo {
             kind: TokenKind::Native,
             price_provider: PriceProvider::Url("https://price.feed".to_string()),
+            fee_policy: Default::default(),
         },
         TokenInfo {
             kind: TokenKind::ERC20 {
@@ -40,6 +173,7 @@ fn config_resolution() {
                 decimals: 10,
             },
             price_provider: PriceProvider::Static(Decimal::new(123, 2)),
+            fee_policy: Default::default(),
         },
     ];
     let price_feed_refresh_interval = DEFAULT_PRICE_FEED_REFRESH_INTERVAL;
@@ -48,6 +182,19 @@ fn config_resolution() {
     let service_fee_percent = DEFAULT_SERVICE_FEE_PERCENT;
     let quote_validity = Duration::from_secs(11);
     let max_pocket_money = U256::from(12);
+    let job_store_path = "/data/jobs.sqlite".to_string();
+    let tx_confirmations = 3;
+    let resubmission_timeout = Duration::from_secs(30);
+    let max_resubmissions = DEFAULT_MAX_RESUBMISSIONS;
+    let per_ip_rate_limit = 30;
+    let global_rate_limit = DEFAULT_GLOBAL_RATE_LIMIT;
+    let trusted_proxies = parse_ip_networks("10.0.0.0/8,::1").unwrap();
+    let relay_gas_estimation_interval = Duration::from_secs(30);
+    let relay_gas_percentile = 95;
+    let priority_fee_percentile = 60;
+    let base_fee_headroom_percent = DEFAULT_BASE_FEE_HEADROOM_PERCENT;
+    let withdraw_params_path = Some("/artifacts/params.bin".to_string());
+    let withdraw_pk_path = Some("/artifacts/pk.bin".to_string());
 
     let expected_config = ServerConfig {
         logging_format, // from CLI
@@ -55,6 +202,7 @@ fn config_resolution() {
             host,         // default
             port,         // from env
             metrics_port, // from CLI
+            admin_port,   // from env
         },
         chain: ChainConfig {
             node_rpc_url: node_rpc_url.clone(), // from CLI
@@ -62,23 +210,39 @@ fn config_resolution() {
             relay_gas,                          // from env
         },
         operations: OperationalConfig {
-            balance_monitor_interval,    // from env
-            rpc_health_cache_validity,   // default
-            nonce_policy,                // default
-            dry_running,                 // from CLI
-            recharge_threshold,          // default
-            recharge_amount,             // from CLI
-            token_config,                // from env
-            price_feed_refresh_interval, // default
-            price_feed_validity,         // from CLI
-            service_fee_percent,         // default
-            quote_validity,              // from env
-            max_pocket_money,            // from CLI
+            balance_monitor_interval,      // from env
+            rpc_health_cache_validity,     // default
+            nonce_policy,                  // default
+            dry_running,                   // from CLI
+            recharge_threshold,            // default
+            recharge_amount,               // from CLI
+            token_config,                  // from env
+            price_feed_refresh_interval,   // default
+            price_feed_validity,           // from CLI
+            service_fee_percent,           // default
+            quote_validity,                // from env
+            max_pocket_money,              // from CLI
+            job_store_path,                // from env
+            tx_confirmations,              // from CLI
+            resubmission_timeout,          // from env
+            max_resubmissions,             // default
+            per_ip_rate_limit,             // from CLI
+            global_rate_limit,             // default
+            trusted_proxies,               // from env
+            relay_gas_estimation_interval, // from env
+            relay_gas_percentile,          // from CLI
+            priority_fee_percentile,       // from env
+            base_fee_headroom_percent,     // default
+            withdraw_params_path,          // from CLI
+            withdraw_pk_path,              // from env
         },
         keys: KeyConfig {
             fee_destination_key: fee_destination_key.clone(), // from env
             signing_keys: vec![key1.clone(), key2.clone()],   // from env
+            quote_signing_key,                                // from env
+            admin_token: admin_token.clone(),                 // from CLI
         },
+        additional_chains: vec![], // default
     };
 
     // ---- CLI configuration. -----------------------------------------------------------------
@@ -87,36 +251,59 @@ fn config_resolution() {
         host: None,
         port: None,
         metrics_port: Some(metrics_port),
+        admin_port: None,
         balance_monitor_interval: None,
         node_rpc_url: Some(node_rpc_url),
         shielder_contract_address: Some(shielder_contract_address.to_string()),
         fee_destination_key: None,
         signing_keys: None,
+        quote_signing_key: None,
+        admin_token,
         nonce_policy: None,
         dry_running: Some(dry_running),
         recharge_threshold: None,
         recharge_amount: Some(recharge_amount),
         relay_gas: None,
         token_config: None,
+        additional_chains: None,
         price_feed_refresh_interval: None,
         price_feed_validity: Some(price_feed_validity),
         service_fee_percent: None,
         quote_validity: None,
         max_pocket_money: Some(max_pocket_money),
+        job_store_path: None,
+        tx_confirmations: Some(tx_confirmations),
+        resubmission_timeout: None,
+        max_resubmissions: None,
+        per_ip_rate_limit: Some(per_ip_rate_limit),
+        global_rate_limit: None,
+        trusted_proxies: None,
+        relay_gas_estimation_interval: None,
+        relay_gas_percentile: Some(relay_gas_percentile),
+        priority_fee_percentile: None,
+        base_fee_headroom_percent: None,
+        withdraw_params_path: Some("/artifacts/params.bin".to_string()),
+        withdraw_pk_path: None,
     };
 
     // ---- Environment variables. -----------------------------------------------------------
     unsafe {
         std::env::set_var(RELAYER_PORT_ENV, port.to_string());
+        std::env::set_var(RELAYER_ADMIN_PORT_ENV, admin_port.to_string());
         std::env::set_var(
             BALANCE_MONITOR_INTERVAL_ENV,
             balance_monitor_interval.as_secs().to_string(),
         );
         std::env::set_var(FEE_DESTINATION_KEY_ENV, fee_destination_key);
         std::env::set_var(RELAYER_SIGNING_KEYS_ENV, format!("{key1},{key2}"));
+        std::env::set_var(QUOTE_SIGNING_KEY_ENV, "quote-key");
         std::env::set_var(RELAY_GAS_ENV, relay_gas.to_string());
         std::env::set_var(TOKEN_CONFIG_ENV, "[]");
         std::env::set_var(QUOTE_VALIDITY_ENV, "11");
+        std::env::set_var(JOB_STORE_PATH_ENV, "/data/jobs.sqlite");
+        std::env::set_var(TRUSTED_PROXIES_ENV, "10.0.0.0/8,::1");
+        std::env::set_var(RESUBMISSION_TIMEOUT_ENV, "30");
+        std::env::set_var(WITHDRAW_PK_PATH_ENV, "/artifacts/pk.bin");
         std::env::set_var(
             TOKEN_CONFIG_ENV,
             "[
@@ -135,6 +322,8 @@ fn config_resolution() {
                 }
             ]",
         );
+        std::env::set_var(RELAY_GAS_ESTIMATION_INTERVAL_ENV, "30");
+        std::env::set_var(PRIORITY_FEE_PERCENTILE_ENV, "60");
     }
 
     // ---- Test. ------------------------------------------------------------------------------
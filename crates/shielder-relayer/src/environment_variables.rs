@@ -16,6 +16,23 @@ pub const RELAY_GAS_ENV: &str = "RELAY_GAS";
 pub const PRICE_FEED_VALIDITY_ENV: &str = "PRICE_FEED_VALIDITY";
 pub const PRICE_FEED_REFRESH_INTERVAL_ENV: &str = "PRICE_FEED_REFRESH_INTERVAL";
 pub const TOKEN_CONFIG_ENV: &str = "TOKEN_CONFIG";
+pub const ADDITIONAL_CHAINS_ENV: &str = "ADDITIONAL_CHAINS";
 pub const SERVICE_FEE_PERCENT_ENV: &str = "SERVICE_FEE_PERCENT";
 pub const QUOTE_VALIDITY_ENV: &str = "QUOTE_VALIDITY";
 pub const MAX_POCKET_MONEY_ENV: &str = "MAX_POCKET_MONEY";
+pub const JOB_STORE_PATH_ENV: &str = "JOB_STORE_PATH";
+pub const TX_CONFIRMATIONS_ENV: &str = "TX_CONFIRMATIONS";
+pub const RESUBMISSION_TIMEOUT_ENV: &str = "RESUBMISSION_TIMEOUT";
+pub const MAX_RESUBMISSIONS_ENV: &str = "MAX_RESUBMISSIONS";
+pub const QUOTE_SIGNING_KEY_ENV: &str = "QUOTE_SIGNING_KEY";
+pub const PER_IP_RATE_LIMIT_ENV: &str = "PER_IP_RATE_LIMIT";
+pub const GLOBAL_RATE_LIMIT_ENV: &str = "GLOBAL_RATE_LIMIT";
+pub const TRUSTED_PROXIES_ENV: &str = "TRUSTED_PROXIES";
+pub const WITHDRAW_PARAMS_PATH_ENV: &str = "WITHDRAW_PARAMS_PATH";
+pub const WITHDRAW_PK_PATH_ENV: &str = "WITHDRAW_PK_PATH";
+pub const RELAYER_ADMIN_PORT_ENV: &str = "RELAYER_ADMIN_PORT";
+pub const ADMIN_TOKEN_ENV: &str = "ADMIN_TOKEN";
+pub const RELAY_GAS_ESTIMATION_INTERVAL_ENV: &str = "RELAY_GAS_ESTIMATION_INTERVAL";
+pub const RELAY_GAS_PERCENTILE_ENV: &str = "RELAY_GAS_PERCENTILE";
+pub const PRIORITY_FEE_PERCENTILE_ENV: &str = "PRIORITY_FEE_PERCENTILE";
+pub const BASE_FEE_HEADROOM_PERCENT_ENV: &str = "BASE_FEE_HEADROOM_PERCENT";
@@ -1,4 +1,4 @@
-use alloy_primitives::U256;
+use alloy_primitives::{B256, U256};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use shielder_account::Token;
@@ -44,12 +44,80 @@ pub struct FeeDetails {
     #[schema(value_type = String)]
     pub gas_cost_fee_token: U256,
 
-    /// The commission for the relayer in native token.
+    /// The commission for the relayer in native token. Includes the adjustment to the minimum or
+    /// maximum fee of the fee token.
     #[schema(value_type = String)]
     pub commission_native: U256,
-    /// The commission for the relayer in fee token.
+    /// The commission for the relayer in fee token. Includes the adjustment to the minimum or
+    /// maximum fee of the fee token.
     #[schema(value_type = String)]
     pub commission_fee_token: U256,
+
+    /// Gas amount that the relay is estimated to burn.
+    #[serde(default)]
+    pub relay_gas: u64,
+    /// Commission percentage applied to the relayer cost (before the fee limits).
+    #[serde(default)]
+    pub commission_percent: u32,
+    /// Whether the total fee was raised to the minimum fee of the fee token.
+    #[serde(default)]
+    pub min_fee_applied: bool,
+    /// Whether the total fee was lowered to the maximum fee of the fee token.
+    #[serde(default)]
+    pub max_fee_applied: bool,
+}
+
+/// Fee settings of a single fee token. Missing values fall back to the relayer-wide settings
+/// (`relay_gas` and `service_fee_percent`) or to no limit.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct TokenFeePolicy {
+    /// Gas that the relay burns when withdrawing this token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relay_gas: Option<u64>,
+    /// Commission (percentage of the relayer cost) charged for this token.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_fee_percent: Option<u32>,
+    /// Minimum total fee (in the minimal units of the token).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_fee: Option<U256>,
+    /// Maximum total fee (in the minimal units of the token). Relays that cost the relayer more
+    /// than that are refused.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fee: Option<U256>,
+}
+
+impl TokenFeePolicy {
+    pub fn validate(&self) -> Result<(), &'static str> {
+        match (self.min_fee, self.max_fee) {
+            (Some(min_fee), Some(max_fee)) if min_fee > max_fee => {
+                Err("Minimum fee is higher than maximum fee")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fill the missing values with the relayer-wide defaults.
+    pub fn resolve(&self, default_relay_gas: u64, default_service_fee_percent: u32) -> FeePolicy {
+        FeePolicy {
+            relay_gas: self.relay_gas.unwrap_or(default_relay_gas),
+            commission_percent: self
+                .service_fee_percent
+                .unwrap_or(default_service_fee_percent),
+            min_fee: self.min_fee,
+            max_fee: self.max_fee,
+        }
+    }
+}
+
+/// Fee settings used for computing a single fee.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct FeePolicy {
+    pub relay_gas: u64,
+    pub commission_percent: u32,
+    /// Minimum total fee in fee token.
+    pub min_fee: Option<U256>,
+    /// Maximum total fee in fee token.
+    pub max_fee: Option<U256>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
@@ -67,42 +135,80 @@ pub struct PriceDetails {
     pub fee_token_unit_price: Decimal,
 }
 
+/// Proof that the quote was issued by the relayer. It must be sent back with the relay request.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct QuoteSignature {
+    /// Unix timestamp (in seconds) from which the quote is no longer accepted.
+    pub expires_at: u64,
+    /// Random value, unique for every quote.
+    #[schema(value_type = String)]
+    pub nonce: B256,
+    #[schema(value_type = String)]
+    pub signature: B256,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
 pub struct QuoteFeeResponse {
     pub fee_details: FeeDetails,
     pub price_details: PriceDetails,
+    pub quote_signature: QuoteSignature,
 }
 
 pub fn compute_fee(
     gas_price: U256,
-    required_gas: u64,
     pocket_money: U256,
-    commission: u32,
+    policy: &FeePolicy,
     native_token_unit_price: Decimal,
     fee_token_unit_price: Decimal,
 ) -> Result<FeeDetails, &'static str> {
     // Gas cost in native token.
-    let gas_cost_native = U256::from(required_gas) * gas_price;
+    let gas_cost_native = U256::from(policy.relay_gas) * gas_price;
     // Actual cost of performing the relay.
     let relayer_cost_native = gas_cost_native + pocket_money;
     // Relay commission.
-    let commission_native = relayer_cost_native * U256::from(commission) / U256::from(100);
+    let commission_native =
+        relayer_cost_native * U256::from(policy.commission_percent) / U256::from(100);
     // Total cost for the user.
     let total_cost_native = relayer_cost_native + commission_native;
 
     let native_to_fee_ratio = native_token_unit_price / fee_token_unit_price;
+    let relayer_cost_fee_token = scale_u256(relayer_cost_native, native_to_fee_ratio)?;
+    let total_cost_fee_token = scale_u256(total_cost_native, native_to_fee_ratio)?;
+
+    // Limits are set in fee token, the difference is settled in the commission.
+    let (total_cost_fee_token, min_fee_applied, max_fee_applied) =
+        match (policy.min_fee, policy.max_fee) {
+            (_, Some(max_fee)) if relayer_cost_fee_token > max_fee => {
+                return Err("Relay cost exceeds the maximum fee for the fee token");
+            }
+            (_, Some(max_fee)) if total_cost_fee_token > max_fee => (max_fee, false, true),
+            (Some(min_fee), _) if total_cost_fee_token < min_fee => (min_fee, true, false),
+            _ => (total_cost_fee_token, false, false),
+        };
+    let commission_fee_token = total_cost_fee_token - relayer_cost_fee_token;
+    let (total_cost_native, commission_native) = if min_fee_applied || max_fee_applied {
+        let fee_to_native_ratio = fee_token_unit_price / native_token_unit_price;
+        let commission_native = scale_u256(commission_fee_token, fee_to_native_ratio)?;
+        (relayer_cost_native + commission_native, commission_native)
+    } else {
+        (total_cost_native, commission_native)
+    };
 
     Ok(FeeDetails {
         total_cost_native,
-        total_cost_fee_token: scale_u256(total_cost_native, native_to_fee_ratio)?,
+        total_cost_fee_token,
         relayer_cost_native,
-        relayer_cost_fee_token: scale_u256(relayer_cost_native, native_to_fee_ratio)?,
+        relayer_cost_fee_token,
         pocket_money_native: pocket_money,
         pocket_money_fee_token: scale_u256(pocket_money, native_to_fee_ratio)?,
         gas_cost_native,
         gas_cost_fee_token: scale_u256(gas_cost_native, native_to_fee_ratio)?,
         commission_native,
-        commission_fee_token: scale_u256(commission_native, native_to_fee_ratio)?,
+        commission_fee_token,
+        relay_gas: policy.relay_gas,
+        commission_percent: policy.commission_percent,
+        min_fee_applied,
+        max_fee_applied,
     })
 }
 
@@ -116,3 +222,61 @@ pub fn scale_u256(a: U256, b: Decimal) -> Result<U256, &'static str> {
     let scale = U256::pow(U256::from(10), U256::from(b.scale()));
     Ok(a * mantissa / scale)
 }
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::U256;
+    use rust_decimal::Decimal;
+
+    use crate::{compute_fee, FeeDetails, FeePolicy};
+
+    fn policy(min_fee: Option<u64>, max_fee: Option<u64>) -> FeePolicy {
+        FeePolicy {
+            relay_gas: 100,
+            commission_percent: 10,
+            min_fee: min_fee.map(U256::from),
+            max_fee: max_fee.map(U256::from),
+        }
+    }
+
+    fn fee(policy: &FeePolicy) -> Result<FeeDetails, &'static str> {
+        // 1 unit of the fee token is worth 2 units of the native token.
+        compute_fee(
+            U256::from(10),
+            U256::ZERO,
+            policy,
+            Decimal::ONE,
+            Decimal::TWO,
+        )
+    }
+
+    #[test]
+    fn fee_within_limits_is_not_adjusted() {
+        let details = fee(&policy(Some(100), Some(1_000))).unwrap();
+
+        assert_eq!(details.total_cost_native, U256::from(1_100));
+        assert_eq!(details.total_cost_fee_token, U256::from(550));
+        assert_eq!(details.commission_fee_token, U256::from(50));
+        assert!(!details.min_fee_applied && !details.max_fee_applied);
+    }
+
+    #[test]
+    fn fee_is_raised_to_minimum_and_lowered_to_maximum() {
+        let details = fee(&policy(Some(600), None)).unwrap();
+        assert_eq!(details.total_cost_fee_token, U256::from(600));
+        assert_eq!(details.commission_fee_token, U256::from(100));
+        assert_eq!(details.commission_native, U256::from(200));
+        assert_eq!(details.total_cost_native, U256::from(1_200));
+        assert!(details.min_fee_applied);
+
+        let details = fee(&policy(None, Some(520))).unwrap();
+        assert_eq!(details.total_cost_fee_token, U256::from(520));
+        assert_eq!(details.commission_fee_token, U256::from(20));
+        assert!(details.max_fee_applied);
+    }
+
+    #[test]
+    fn relay_more_expensive_than_maximum_fee_is_refused() {
+        assert!(fee(&policy(None, Some(499))).is_err());
+    }
+}
@@ -0,0 +1,221 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy_signer_local::PrivateKeySigner;
+use parking_lot::Mutex;
+use shielder_account::{call_data::WithdrawCall, Token};
+use shielder_contract::{
+    alloy_primitives::Address, call_type::EstimateGas, ConnectionPolicy, ContractResult,
+    NoProvider, ShielderUser,
+};
+use tracing::{debug, info};
+
+use crate::metrics::RELAY_GAS_ESTIMATE;
+
+/// Number of the most recent estimates kept for every token.
+const SAMPLE_WINDOW: usize = 64;
+
+#[derive(Default)]
+struct TokenSamples {
+    estimates: VecDeque<u64>,
+    last_sampled: Option<Instant>,
+}
+
+/// Keeps track of how much gas relays actually need, by estimating gas of the relay requests that
+/// the relayer receives (at most once per `interval` for every token).
+#[derive(Clone)]
+pub struct RelayGasEstimator {
+    chain_id: u64,
+    interval: Duration,
+    percentile: u32,
+    shielder_user: ShielderUser<NoProvider>,
+    samples: Arc<Mutex<HashMap<Token, TokenSamples>>>,
+}
+
+impl RelayGasEstimator {
+    /// `caller` is only used as the sender of the estimated calls - nothing is ever sent from it.
+    pub fn new(
+        chain_id: u64,
+        interval: Duration,
+        percentile: u32,
+        node_rpc_url: String,
+        shielder_contract_address: Address,
+        caller: PrivateKeySigner,
+    ) -> Self {
+        Self {
+            chain_id,
+            interval,
+            percentile,
+            shielder_user: ShielderUser::new(
+                shielder_contract_address,
+                ConnectionPolicy::OnDemand {
+                    rpc_url: node_rpc_url,
+                    signer: caller,
+                },
+            ),
+            samples: Default::default(),
+        }
+    }
+
+    /// The configured percentile of the recent estimates for `token`, or `None` if there are no
+    /// estimates yet.
+    pub fn estimate(&self, token: Token) -> Option<u64> {
+        let samples = self.samples.lock();
+        percentile(&samples.get(&token)?.estimates, self.percentile)
+    }
+
+    /// Estimate gas of `call` in the background, unless its token has been sampled recently. The
+    /// call must be valid (otherwise the estimation fails and is ignored).
+    pub fn sample(&self, call: &WithdrawCall) {
+        if !self.start_sampling(call.token, Instant::now()) {
+            return;
+        }
+
+        let estimator = self.clone();
+        let call = call.clone();
+        tokio::spawn(async move {
+            let token = call.token;
+            match estimator.estimate_call(call).await {
+                Ok(gas) => {
+                    estimator.record(token, gas);
+                    info!("Estimated relay gas for {token:?}: {gas}");
+                }
+                // E.g. the withdrawal has already been mined. The next request will be sampled.
+                Err(err) => {
+                    debug!("Relay gas estimation failed: {err}");
+                    estimator.cancel_sampling(token);
+                }
+            }
+        });
+    }
+
+    /// Mark `token` as being sampled. Returns `false` if it shouldn't be sampled now.
+    fn start_sampling(&self, token: Token, now: Instant) -> bool {
+        if self.interval.is_zero() {
+            return false;
+        }
+        let mut samples = self.samples.lock();
+        let token_samples = samples.entry(token).or_default();
+        match token_samples.last_sampled {
+            Some(last) if now.saturating_duration_since(last) < self.interval => false,
+            _ => {
+                token_samples.last_sampled = Some(now);
+                true
+            }
+        }
+    }
+
+    fn cancel_sampling(&self, token: Token) {
+        if let Some(token_samples) = self.samples.lock().get_mut(&token) {
+            token_samples.last_sampled = None;
+        }
+    }
+
+    fn record(&self, token: Token, gas: u64) {
+        let mut samples = self.samples.lock();
+        let estimates = &mut samples.entry(token).or_default().estimates;
+        if estimates.len() == SAMPLE_WINDOW {
+            estimates.pop_front();
+        }
+        estimates.push_back(gas);
+
+        if let Some(estimate) = percentile(estimates, self.percentile) {
+            metrics::gauge!(
+                RELAY_GAS_ESTIMATE,
+                "chain_id" => self.chain_id.to_string(),
+                "token" => token.address().to_string()
+            )
+            .set(estimate as f64);
+        }
+    }
+
+    async fn estimate_call(&self, call: WithdrawCall) -> ContractResult<u64> {
+        match call.token {
+            Token::Native => {
+                self.shielder_user
+                    .withdraw_native::<EstimateGas>(call.try_into().unwrap())
+                    .await
+            }
+            Token::ERC20(_) => {
+                let pocket_money = call.pocket_money;
+                self.shielder_user
+                    .withdraw_erc20::<EstimateGas>(call.try_into().unwrap(), pocket_money)
+                    .await
+            }
+        }
+    }
+}
+
+/// Nearest-rank percentile.
+fn percentile(values: &VecDeque<u64>, percentile: u32) -> Option<u64> {
+    let mut sorted = values.iter().copied().collect::<Vec<_>>();
+    sorted.sort_unstable();
+    let rank = (sorted.len() * percentile as usize).div_ceil(100).max(1);
+    sorted.get(rank - 1).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    };
+
+    use alloy_signer_local::PrivateKeySigner;
+    use shielder_account::Token;
+    use shielder_contract::alloy_primitives::Address;
+
+    use crate::gas_estimation::{percentile, RelayGasEstimator, SAMPLE_WINDOW};
+
+    fn estimator(interval: Duration) -> RelayGasEstimator {
+        RelayGasEstimator::new(
+            1,
+            interval,
+            90,
+            "http://localhost:8545".to_string(),
+            Address::ZERO,
+            PrivateKeySigner::random(),
+        )
+    }
+
+    #[test]
+    fn nearest_rank_percentile() {
+        let values = (1..=10).rev().collect::<VecDeque<u64>>();
+
+        assert_eq!(percentile(&values, 90), Some(9));
+        assert_eq!(percentile(&values, 100), Some(10));
+        assert_eq!(percentile(&values, 1), Some(1));
+        assert_eq!(percentile(&VecDeque::new(), 90), None);
+    }
+
+    #[test]
+    fn only_recent_estimates_are_used() {
+        let estimator = estimator(Duration::from_secs(60));
+        for gas in 0..SAMPLE_WINDOW as u64 {
+            estimator.record(Token::Native, 1_000_000 + gas);
+        }
+        estimator.record(Token::Native, 10);
+
+        assert_eq!(estimator.estimate(Token::Native), Some(1_000_057));
+        assert_eq!(estimator.estimate(Token::ERC20(Address::ZERO)), None);
+    }
+
+    #[test]
+    fn token_is_sampled_at_most_once_per_interval() {
+        let estimator = estimator(Duration::from_secs(60));
+        let now = Instant::now();
+
+        assert!(estimator.start_sampling(Token::Native, now));
+        assert!(!estimator.start_sampling(Token::Native, now + Duration::from_secs(59)));
+        assert!(estimator.start_sampling(Token::ERC20(Address::ZERO), now));
+        assert!(estimator.start_sampling(Token::Native, now + Duration::from_secs(60)));
+
+        estimator.cancel_sampling(Token::Native);
+        assert!(estimator.start_sampling(Token::Native, now + Duration::from_secs(61)));
+
+        assert!(!self::estimator(Duration::ZERO).start_sampling(Token::Native, now));
+    }
+}
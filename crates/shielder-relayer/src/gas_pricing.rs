@@ -0,0 +1,269 @@
+use alloy_network::AnyNetwork;
+use alloy_provider::Provider;
+use alloy_rpc_types::{BlockNumberOrTag, FeeHistory};
+use alloy_transport::BoxTransport;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use shielder_contract::TxOverrides;
+use tracing::debug;
+
+/// Number of the recent blocks from which the priority fee is taken.
+const FEE_HISTORY_BLOCKS: u64 = 10;
+
+/// How gas prices are derived from the network fees.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct GasPricingPolicy {
+    /// Percentile of the priority fees paid in the recent blocks that the relayer pays.
+    pub priority_fee_percentile: u32,
+    /// By how much (in percent) the next base fee can grow before the quoted price is exceeded.
+    pub base_fee_headroom_percent: u32,
+}
+
+/// Fees of the network at the moment.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct NetworkFees {
+    /// Base fee of the next block. On chains without EIP-1559, the whole gas price.
+    pub base_fee_per_gas: u128,
+    /// Priority fee needed for a timely inclusion. Always zero on chains without EIP-1559.
+    pub priority_fee_per_gas: u128,
+    /// Whether the chain doesn't support EIP-1559 (relay transactions must be legacy ones).
+    pub legacy: bool,
+}
+
+impl NetworkFees {
+    /// Fees of a chain without EIP-1559, where only the gas price is known.
+    pub fn legacy(gas_price: u128) -> Self {
+        Self {
+            base_fee_per_gas: gas_price,
+            priority_fee_per_gas: 0,
+            legacy: true,
+        }
+    }
+
+    /// Price per gas quoted to users: the base fee with headroom for its growth and the tip.
+    pub fn quoted_gas_price(&self, policy: &GasPricingPolicy) -> u128 {
+        self.base_fee_per_gas * (100 + policy.base_fee_headroom_percent as u128) / 100
+            + self.priority_fee_per_gas
+    }
+
+    /// Price per gas needed to get into the next block.
+    pub fn required_gas_price(&self) -> u128 {
+        self.base_fee_per_gas + self.priority_fee_per_gas
+    }
+
+    /// Fees for a transaction that must not cost more than `quoted_gas_price` per gas.
+    pub fn fee_cap(&self, quoted_gas_price: u128) -> FeeCap {
+        match self.legacy {
+            true => FeeCap::Legacy {
+                gas_price: quoted_gas_price,
+            },
+            false => FeeCap::Eip1559 {
+                max_fee_per_gas: quoted_gas_price,
+                max_priority_fee_per_gas: self.priority_fee_per_gas.min(quoted_gas_price),
+            },
+        }
+    }
+}
+
+/// Fees of a relay transaction, bounded by what the user paid for.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum FeeCap {
+    /// Type-2 transaction.
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+    /// Legacy transaction, for chains without EIP-1559.
+    Legacy { gas_price: u128 },
+}
+
+impl FeeCap {
+    /// The most that can be paid per gas.
+    pub fn max_fee_per_gas(&self) -> u128 {
+        match *self {
+            FeeCap::Eip1559 {
+                max_fee_per_gas, ..
+            } => max_fee_per_gas,
+            FeeCap::Legacy { gas_price } => gas_price,
+        }
+    }
+
+    /// The most that can be paid per gas to the block producer.
+    pub fn max_priority_fee_per_gas(&self) -> u128 {
+        match *self {
+            FeeCap::Eip1559 {
+                max_priority_fee_per_gas,
+                ..
+            } => max_priority_fee_per_gas,
+            FeeCap::Legacy { gas_price } => gas_price,
+        }
+    }
+
+    /// Overrides for a transaction with `nonce` that pays the capped fees.
+    pub fn tx_overrides(&self, nonce: u64) -> TxOverrides {
+        match *self {
+            FeeCap::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => TxOverrides {
+                nonce: Some(nonce),
+                max_fee_per_gas: Some(max_fee_per_gas),
+                max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+                ..Default::default()
+            },
+            FeeCap::Legacy { gas_price } => TxOverrides {
+                nonce: Some(nonce),
+                gas_price: Some(gas_price),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Read the current fees from `eth_feeHistory`. On chains without EIP-1559 (no base fee in the
+/// history), fall back to `eth_gasPrice`.
+pub async fn network_fees(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    policy: &GasPricingPolicy,
+) -> Result<NetworkFees> {
+    let history = provider
+        .get_fee_history(
+            FEE_HISTORY_BLOCKS,
+            BlockNumberOrTag::Latest,
+            &[policy.priority_fee_percentile as f64],
+        )
+        .await;
+    let history = match history {
+        Ok(history) => history,
+        Err(err) => {
+            debug!("Fee history is not available, using the legacy gas price: {err}");
+            FeeHistory::default()
+        }
+    };
+    if let Some(fees) = fees_from_history(&history) {
+        return Ok(fees);
+    }
+
+    let gas_price = provider.get_gas_price().await?;
+    match gas_price {
+        0 => Err(anyhow!("Network reports neither base fee nor gas price")),
+        gas_price => Ok(NetworkFees::legacy(gas_price)),
+    }
+}
+
+/// Next base fee and the median (over blocks) of the priority fees at the requested percentile.
+/// `None` if the history has no base fee (the chain doesn't support EIP-1559).
+fn fees_from_history(history: &FeeHistory) -> Option<NetworkFees> {
+    let base_fee_per_gas = history.next_block_base_fee().filter(|fee| *fee > 0)?;
+
+    let mut rewards = history
+        .reward
+        .iter()
+        .flatten()
+        .filter_map(|block_rewards| block_rewards.first().copied())
+        .collect::<Vec<_>>();
+    rewards.sort_unstable();
+    let priority_fee_per_gas = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+
+    Some(NetworkFees {
+        base_fee_per_gas,
+        priority_fee_per_gas,
+        legacy: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_rpc_types::FeeHistory;
+
+    use crate::gas_pricing::{fees_from_history, FeeCap, GasPricingPolicy, NetworkFees};
+
+    #[test]
+    fn fees_are_read_from_history() {
+        let history = FeeHistory {
+            base_fee_per_gas: vec![100, 110, 120],
+            reward: Some(vec![vec![5], vec![1], vec![3]]),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            fees_from_history(&history),
+            Some(NetworkFees {
+                base_fee_per_gas: 120,
+                priority_fee_per_gas: 3,
+                legacy: false,
+            })
+        );
+        assert_eq!(fees_from_history(&FeeHistory::default()), None);
+    }
+
+    #[test]
+    fn history_without_base_fee_means_legacy_chain() {
+        let history = FeeHistory {
+            base_fee_per_gas: vec![],
+            reward: Some(vec![vec![5], vec![1], vec![3]]),
+            ..Default::default()
+        };
+        assert_eq!(fees_from_history(&history), None);
+
+        // Nodes of some chains report zeros instead.
+        let history = FeeHistory {
+            base_fee_per_gas: vec![0, 0, 0],
+            ..Default::default()
+        };
+        assert_eq!(fees_from_history(&history), None);
+
+        let policy = GasPricingPolicy {
+            priority_fee_percentile: 50,
+            base_fee_headroom_percent: 25,
+        };
+        let fees = NetworkFees::legacy(1000);
+        assert_eq!(fees.required_gas_price(), 1000);
+        assert_eq!(fees.quoted_gas_price(&policy), 1250);
+
+        let fee_cap = fees.fee_cap(1250);
+        assert_eq!(fee_cap, FeeCap::Legacy { gas_price: 1250 });
+        let overrides = fee_cap.tx_overrides(3);
+        assert_eq!(overrides.gas_price, Some(1250));
+        assert_eq!(overrides.max_fee_per_gas, None);
+        assert_eq!(overrides.max_priority_fee_per_gas, None);
+    }
+
+    #[test]
+    fn fee_caps_stored_before_legacy_support_are_read() {
+        let fee_cap: FeeCap =
+            serde_json::from_str(r#"{"max_fee_per_gas":1000,"max_priority_fee_per_gas":10}"#)
+                .unwrap();
+        assert_eq!(
+            fee_cap,
+            FeeCap::Eip1559 {
+                max_fee_per_gas: 1000,
+                max_priority_fee_per_gas: 10,
+            }
+        );
+        let legacy = serde_json::to_string(&FeeCap::Legacy { gas_price: 7 }).unwrap();
+        assert_eq!(
+            serde_json::from_str::<FeeCap>(&legacy).unwrap(),
+            FeeCap::Legacy { gas_price: 7 }
+        );
+    }
+
+    #[test]
+    fn quoted_price_has_headroom_for_base_fee_growth() {
+        let fees = NetworkFees {
+            base_fee_per_gas: 1000,
+            priority_fee_per_gas: 10,
+            legacy: false,
+        };
+        let policy = GasPricingPolicy {
+            priority_fee_percentile: 50,
+            base_fee_headroom_percent: 25,
+        };
+
+        assert_eq!(fees.quoted_gas_price(&policy), 1260);
+        assert_eq!(fees.required_gas_price(), 1010);
+        assert_eq!(fees.fee_cap(1260).max_priority_fee_per_gas(), 10);
+        assert_eq!(fees.fee_cap(5).max_priority_fee_per_gas(), 5);
+    }
+}
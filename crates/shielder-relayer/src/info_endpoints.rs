@@ -22,7 +22,8 @@ pub async fn fee_address(state: State<AppState>) -> impl IntoResponse {
 pub async fn supported_tokens(state: State<AppState>) -> impl IntoResponse {
     Json(
         state
-            .token_config
+            .runtime_config
+            .token_config()
             .iter()
             .map(|t| t.kind)
             .collect::<Vec<_>>(),
@@ -32,5 +33,5 @@ pub async fn supported_tokens(state: State<AppState>) -> impl IntoResponse {
 /// Get upper limit for pocket money.
 #[utoipa::path(get, path = "/max_pocket_money", responses((status = 200, body = String)))]
 pub async fn max_pocket_money(state: State<AppState>) -> impl IntoResponse {
-    Json(state.max_pocket_money.to_string())
+    Json(state.runtime_config.max_pocket_money().to_string())
 }
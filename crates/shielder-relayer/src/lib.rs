@@ -23,6 +23,46 @@ pub struct SimpleServiceResponse {
 pub struct RelayResponse {
     #[schema(value_type = String)]
     pub tx_hash: TxHash,
+    /// Identifier of the relay job. Can be used to query `/relay/{id}`.
+    #[serde(default)]
+    pub job_id: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct RelayJobResponse {
+    /// Identifier of the relay job. Can be used to query `/relay/{id}`.
+    pub job_id: u64,
+}
+
+/// Lifecycle of a relay job.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize, ToSchema)]
+pub enum RelayJobStatus {
+    /// Accepted and waiting for a free worker.
+    Queued,
+    /// Being dry-run by a worker.
+    DryRun,
+    /// Transaction has been sent to the network.
+    Submitted,
+    /// Transaction has been included in a block.
+    Mined,
+    /// Job failed. See `failure_reason` for details.
+    Failed,
+}
+
+impl RelayJobStatus {
+    /// Whether the job will not change its status anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Mined | Self::Failed)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, ToSchema)]
+pub struct RelayJobInfo {
+    pub job_id: u64,
+    pub status: RelayJobStatus,
+    #[schema(value_type = Option<String>)]
+    pub tx_hash: Option<TxHash>,
+    pub failure_reason: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
@@ -61,6 +101,15 @@ pub struct RelayQuote {
     pub gas_price: U256,
     pub native_token_unit_price: Decimal,
     pub fee_token_unit_price: Decimal,
+    /// Gas amount that the quote was computed for (`fee_details.relay_gas`).
+    #[serde(default)]
+    pub relay_gas: u64,
+    /// Total fee that the quote was computed for (`fee_details.total_cost_fee_token`). This is the
+    /// relayer fee of the relay.
+    #[schema(value_type = String)]
+    #[serde(default)]
+    pub total_fee: U256,
+    pub signature: QuoteSignature,
 }
 
 impl From<QuoteFeeResponse> for RelayQuote {
@@ -69,6 +118,9 @@ impl From<QuoteFeeResponse> for RelayQuote {
             gas_price: response.price_details.gas_price,
             native_token_unit_price: response.price_details.native_token_unit_price,
             fee_token_unit_price: response.price_details.fee_token_unit_price,
+            relay_gas: response.fee_details.relay_gas,
+            total_fee: response.fee_details.total_cost_fee_token,
+            signature: response.quote_signature,
         }
     }
 }
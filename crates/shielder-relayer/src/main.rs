@@ -1,62 +1,80 @@
-use std::{env, io, str::FromStr, sync::Arc};
+use std::{env, io, net::SocketAddr, str::FromStr, sync::Arc};
 
 use alloy_provider::Provider;
 use alloy_signer_local::PrivateKeySigner;
 use anyhow::{anyhow, Result};
-use axum::{middleware, routing::get, Router};
+use axum::{
+    middleware,
+    routing::{delete, get, post},
+    Router,
+};
 use price_feed::{start_price_feed, Prices};
 use shielder_contract::{
-    alloy_primitives::{Address, U256},
-    providers::{create_provider_with_nonce_caching_signer, create_provider_with_signer},
-    ConnectionPolicy, ShielderUser,
+    alloy_primitives::Address,
+    providers::{create_provider_with_signer, create_simple_provider},
 };
-use shielder_relayer::TokenInfo;
+use tokio::{sync::RwLock, task::JoinSet};
 use tower_http::cors::CorsLayer;
-use tracing::info;
+use tracing::{info, warn};
 use tracing_subscriber::EnvFilter;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
 use crate::{
-    config::{
-        resolve_config, ChainConfig, KeyConfig, LoggingFormat, NoncePolicy, OperationalConfig,
-        ServerConfig,
-    },
-    metrics::{prometheus_endpoint, setup_metrics_handle},
+    config::{resolve_config, KeyConfig, LoggingFormat, OperationalConfig, ServerConfig},
+    gas_estimation::RelayGasEstimator,
+    gas_pricing::GasPricingPolicy,
+    metrics::{prometheus_endpoint, setup_metrics_handle, ChainMetrics},
     monitor::{
         balance_monitor::{balance_monitor, set_balance},
         rpc_monitor::RpcMonitor,
         Balances,
     },
-    quote_cache::{garbage_collector_worker, QuoteCache},
+    proof_verification::WithdrawVerifier,
+    quote_signing::QuoteSigner,
+    rate_limit::{garbage_collector_worker, RateLimiter},
     recharge::{start_recharging_worker, try_recharging_relayer},
-    relay::Taskmaster,
+    relay::{resume_unfinished_jobs, ConfirmationPolicy, JobStore, Taskmaster, WorkerConnection},
+    runtime_config::RuntimeConfig,
+    signer_pool::{signer_pool_worker, SignerPool},
 };
 
+mod admin;
 mod config;
+mod gas_estimation;
+mod gas_pricing;
 mod health_endpoint;
 mod info_endpoints;
 mod metrics;
 mod monitor;
 mod price_feed;
+mod proof_verification;
 mod quote;
-mod quote_cache;
+mod quote_signing;
+mod rate_limit;
 mod recharge;
 mod relay;
+mod runtime_config;
+mod signer_pool;
 
 #[derive(Clone)]
 pub struct AppState {
+    pub chain_id: u64,
     pub node_rpc_url: String,
+    pub shielder_contract_address: Address,
     pub relay_gas: u64,
     pub taskmaster: Taskmaster,
+    pub signer_pool: SignerPool,
+    pub job_store: JobStore,
     pub signer_info: SignerInfo,
     pub rpc_monitor: RpcMonitor,
     pub prices: Prices,
-    pub token_config: Vec<TokenInfo>,
-    pub quote_cache: QuoteCache,
-    pub max_pocket_money: U256,
-    pub service_fee_percent: u32,
+    pub runtime_config: RuntimeConfig,
+    pub quote_signer: QuoteSigner,
+    pub withdraw_verifier: Option<Arc<WithdrawVerifier>>,
+    pub relay_gas_estimator: RelayGasEstimator,
+    pub gas_pricing: GasPricingPolicy,
 }
 
 #[derive(Clone)]
@@ -79,37 +97,91 @@ async fn main() -> Result<()> {
 
     info!("Starting Shielder relayer.");
     info!("Server configuration:\n{server_config:#?}",);
-    let rpc_monitor = RpcMonitor::new(
-        server_config.operations.rpc_health_cache_validity,
-        server_config.chain.node_rpc_url.clone(),
-    )
-    .await;
-    let signer_info = get_signer_info(&server_config.keys)?;
-    let prices = Prices::new(
-        &server_config.operations.token_config,
-        server_config.operations.price_feed_validity,
-        server_config.operations.price_feed_refresh_interval,
-    );
+
+    let mut chains = Vec::new();
+    for config in server_config.deployments() {
+        let chain = Chain::new(config).await?;
+        if chains
+            .iter()
+            .any(|other: &Chain| other.chain_id == chain.chain_id)
+        {
+            return Err(anyhow!(
+                "Chain {} is configured more than once",
+                chain.chain_id
+            ));
+        }
+        info!("Serving chain {}", chain.chain_id);
+        chains.push(chain);
+    }
+
+    let mut background = JoinSet::new();
+    for chain in &chains {
+        let node_rpc_url = chain.config.chain.node_rpc_url.clone();
+        let interval = chain.config.operations.balance_monitor_interval;
+        let balances = chain.signer_info.balances.clone();
+        background.spawn(async move { balance_monitor(&node_rpc_url, interval, balances).await });
+        background.spawn(start_price_feed(chain.prices.clone()));
+    }
 
     tokio::try_join!(
-        balance_monitor(
-            &server_config.chain.node_rpc_url,
-            server_config.operations.balance_monitor_interval,
-            signer_info.balances.clone(),
-        ),
-        start_metrics_server(
-            &server_config,
-            signer_info.clone(),
-            rpc_monitor.clone(),
-            prices.clone()
-        ),
-        start_main_server(&server_config, signer_info, rpc_monitor, prices.clone()),
-        start_price_feed(prices)
+        start_metrics_server(&server_config, chains.iter().map(Chain::metrics).collect()),
+        start_main_server(&server_config, chains),
+        async {
+            while let Some(result) = background.join_next().await {
+                result??;
+            }
+            Ok(())
+        }
     )?;
 
     Ok(())
 }
 
+/// A single Shielder deployment served by the relayer.
+struct Chain {
+    chain_id: u64,
+    config: ServerConfig,
+    signer_info: SignerInfo,
+    rpc_monitor: RpcMonitor,
+    prices: Prices,
+}
+
+impl Chain {
+    async fn new(config: ServerConfig) -> Result<Self> {
+        let chain_id = create_simple_provider(&config.chain.node_rpc_url)
+            .await?
+            .get_chain_id()
+            .await?;
+        let rpc_monitor = RpcMonitor::new(
+            config.operations.rpc_health_cache_validity,
+            config.chain.node_rpc_url.clone(),
+        )
+        .await;
+        let signer_info = get_signer_info(&config.keys)?;
+        let prices = Prices::new(
+            &config.operations.token_config,
+            config.operations.price_feed_validity,
+            config.operations.price_feed_refresh_interval,
+        );
+        Ok(Self {
+            chain_id,
+            config,
+            signer_info,
+            rpc_monitor,
+            prices,
+        })
+    }
+
+    fn metrics(&self) -> ChainMetrics {
+        ChainMetrics {
+            chain_id: self.chain_id,
+            signer_info: self.signer_info.clone(),
+            rpc_monitor: self.rpc_monitor.clone(),
+            prices: self.prices.clone(),
+        }
+    }
+}
+
 fn get_signer_info(config: &KeyConfig) -> Result<SignerInfo> {
     let fee_destination_key = signer(&config.fee_destination_key)?;
     let fee_destination_address = fee_destination_key.address();
@@ -119,12 +191,12 @@ fn get_signer_info(config: &KeyConfig) -> Result<SignerInfo> {
         .chain(std::iter::once(&fee_destination_address))
         .cloned()
         .collect();
-    let balances = Arc::new(
+    let balances = Arc::new(RwLock::new(
         all_addresses
             .iter()
             .map(|address| (*address, Default::default()))
             .collect(),
-    );
+    ));
     Ok(SignerInfo {
         signer_keys,
         signer_addresses,
@@ -134,12 +206,7 @@ fn get_signer_info(config: &KeyConfig) -> Result<SignerInfo> {
     })
 }
 
-async fn start_metrics_server(
-    config: &ServerConfig,
-    signer_info: SignerInfo,
-    rpc_monitor: RpcMonitor,
-    prices: Prices,
-) -> Result<()> {
+async fn start_metrics_server(config: &ServerConfig, chains: Vec<ChainMetrics>) -> Result<()> {
     let address = config.network.metrics_address();
     let listener = tokio::net::TcpListener::bind(address.clone()).await?;
     info!("Exposing metrics on {address}");
@@ -149,18 +216,64 @@ async fn start_metrics_server(
     let app = Router::new()
         .route(
             "/metrics",
-            get(move || prometheus_endpoint(metrics_handle, signer_info, rpc_monitor, prices)),
+            get(move || prometheus_endpoint(metrics_handle, chains)),
         )
         .layer(CorsLayer::permissive());
     Ok(axum::serve(listener, app).await?)
 }
 
-async fn start_main_server(
-    config: &ServerConfig,
-    signer_info: SignerInfo,
-    rpc_monitor: RpcMonitor,
-    prices: Prices,
-) -> Result<()> {
+/// Serve all the chains: every one under `/chains/{chain_id}`, and the main one also without the
+/// prefix.
+async fn start_main_server(config: &ServerConfig, chains: Vec<Chain>) -> Result<()> {
+    let mut states = Vec::new();
+    for chain in chains {
+        states.push(start_chain(chain).await?);
+    }
+
+    let rate_limiter = RateLimiter::new(
+        config.operations.per_ip_rate_limit,
+        config.operations.global_rate_limit,
+        config.operations.trusted_proxies.clone(),
+    );
+    tokio::spawn(garbage_collector_worker(rate_limiter.clone()));
+
+    let (mut router, api) = chain_router(states[0].clone(), rate_limiter.clone());
+    for state in &states {
+        let (nested, _) = chain_router(state.clone(), rate_limiter.clone());
+        router = router.nest(&format!("/chains/{}", state.chain_id), nested);
+    }
+
+    let app = router
+        .merge(SwaggerUi::new("/api").url("/api/openapi.json", api.clone()))
+        .layer(CorsLayer::permissive());
+
+    let address = config.network.main_address();
+    let listener = tokio::net::TcpListener::bind(address.clone()).await?;
+    info!("Server is ready. Listening on {address}");
+
+    tokio::try_join!(
+        async {
+            Ok(axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await?)
+        },
+        start_admin_server(config, &states),
+    )?;
+    Ok(())
+}
+
+/// Start the signer pool, workers and recharging of `chain` and resume its unfinished jobs.
+async fn start_chain(chain: Chain) -> Result<AppState> {
+    let Chain {
+        chain_id,
+        config,
+        signer_info,
+        rpc_monitor,
+        prices,
+    } = chain;
+    let config = &config;
     let fee_destination = signer_info.fee_destination_key.clone();
 
     ensure_signers_have_funds(
@@ -179,53 +292,159 @@ async fn start_main_server(
         config.operations.recharge_amount,
     );
 
-    let quote_cache = QuoteCache::new(config.operations.quote_validity);
-    tokio::spawn(garbage_collector_worker(quote_cache.clone()));
+    let quote_signer = match &config.keys.quote_signing_key {
+        Some(key) => QuoteSigner::new(key, config.operations.quote_validity, chain_id),
+        None => {
+            warn!("No quote signing key configured - quotes will be valid only for this instance");
+            QuoteSigner::random(config.operations.quote_validity, chain_id)
+        }
+    };
+
+    let withdraw_verifier = load_withdraw_verifier(config, chain_id)?;
+
+    let job_store = JobStore::open(&config.operations.job_store_path, chain_id)?;
+
+    let runtime_config = RuntimeConfig::new((&config.operations).into());
+
+    let signer_pool = SignerPool::new(
+        signer_info.signer_keys.clone(),
+        config.chain.node_rpc_url.clone(),
+        chain_id,
+        config.operations.recharge_threshold,
+        config.operations.resubmission_timeout,
+    );
+    tokio::spawn(signer_pool_worker(
+        signer_pool.clone(),
+        report_for_recharge.clone(),
+    ));
 
     let state = AppState {
+        chain_id,
         node_rpc_url: config.chain.node_rpc_url.clone(),
+        shielder_contract_address: config.chain.shielder_contract_address,
         relay_gas: config.chain.relay_gas,
         signer_info: signer_info.clone(),
         rpc_monitor,
         taskmaster: Taskmaster::new(
-            build_shielder_users(
-                signer_info.signer_keys,
-                &config.chain,
-                config.operations.nonce_policy,
-            )
-            .await?,
-            config.operations.dry_running,
+            signer_pool.clone(),
+            signer_info.signer_keys,
+            WorkerConnection {
+                chain: config.chain.clone(),
+                nonce_policy: config.operations.nonce_policy,
+            },
+            runtime_config.clone(),
             report_for_recharge,
+            job_store.clone(),
+            ConfirmationPolicy {
+                confirmations: config.operations.tx_confirmations,
+                resubmission_timeout: config.operations.resubmission_timeout,
+                max_resubmissions: config.operations.max_resubmissions,
+            },
         ),
-        token_config: config.operations.token_config.clone(),
+        signer_pool,
+        job_store,
         prices,
-        quote_cache,
-        max_pocket_money: config.operations.max_pocket_money,
-        service_fee_percent: config.operations.service_fee_percent,
+        runtime_config,
+        quote_signer,
+        withdraw_verifier,
+        relay_gas_estimator: RelayGasEstimator::new(
+            chain_id,
+            config.operations.relay_gas_estimation_interval,
+            config.operations.relay_gas_percentile,
+            config.chain.node_rpc_url.clone(),
+            config.chain.shielder_contract_address,
+            signer_info.fee_destination_key.clone(),
+        ),
+        gas_pricing: GasPricingPolicy {
+            priority_fee_percentile: config.operations.priority_fee_percentile,
+            base_fee_headroom_percent: config.operations.base_fee_headroom_percent,
+        },
     };
 
-    let (router, api) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+    resume_unfinished_jobs(&state).await?;
+    Ok(state)
+}
+
+/// Public API of a single chain, together with its OpenAPI specification.
+fn chain_router(state: AppState, rate_limiter: RateLimiter) -> (Router, utoipa::openapi::OpenApi) {
+    // Endpoints that are expensive for the relayer (external calls, dry-runs, transactions).
+    let limited_routes = OpenApiRouter::new()
+        .routes(routes!(quote::quote_fees))
+        .routes(routes!(relay::relay))
+        .routes(routes!(relay::relay_async))
+        .route_layer(middleware::from_fn_with_state(
+            rate_limiter,
+            rate_limit::rate_limit,
+        ));
+
+    OpenApiRouter::with_openapi(ApiDoc::openapi())
         .routes(routes!(health_endpoint::health))
         .routes(routes!(info_endpoints::fee_address))
         .routes(routes!(info_endpoints::supported_tokens))
         .routes(routes!(info_endpoints::max_pocket_money))
-        .routes(routes!(quote::quote_fees))
-        .routes(routes!(relay::relay))
-        .with_state(state.clone())
+        .merge(limited_routes)
+        .routes(routes!(relay::relay_status))
+        .with_state(state)
         .route_layer(middleware::from_fn(metrics::request_metrics))
-        .split_for_parts();
+        .split_for_parts()
+}
 
-    let app = router
-        .merge(SwaggerUi::new("/api").url("/api/openapi.json", api.clone()))
-        .layer(CorsLayer::permissive());
+/// Admin API of all the chains, laid out like the public one.
+async fn start_admin_server(config: &ServerConfig, states: &[AppState]) -> Result<()> {
+    let Some(admin_token) = config.keys.admin_token.as_deref() else {
+        info!("No admin token configured - admin API is disabled");
+        return Ok(());
+    };
 
-    let address = config.network.main_address();
-    let listener = tokio::net::TcpListener::bind(address.clone()).await?;
-    info!("Server is ready. Listening on {address}");
+    let mut app = admin_router(states[0].clone());
+    for state in states {
+        app = app.nest(
+            &format!("/chains/{}", state.chain_id),
+            admin_router(state.clone()),
+        );
+    }
+    let app = app.layer(middleware::from_fn_with_state(
+        Arc::<str>::from(admin_token),
+        admin::require_admin_token,
+    ));
 
+    let address = config.network.admin_address();
+    let listener = tokio::net::TcpListener::bind(address.clone()).await?;
+    info!("Exposing admin API on {address}");
     Ok(axum::serve(listener, app).await?)
 }
 
+fn admin_router(state: AppState) -> Router {
+    Router::new()
+        .route("/signers", get(admin::list_signers).post(admin::add_signer))
+        .route("/signers/{address}", delete(admin::remove_signer))
+        .route(
+            "/config",
+            get(admin::get_settings).patch(admin::update_settings),
+        )
+        .route("/pause", post(admin::pause))
+        .route("/resume", post(admin::resume))
+        .route("/drain", post(admin::drain))
+        .with_state(state)
+}
+
+fn load_withdraw_verifier(
+    config: &ServerConfig,
+    chain_id: u64,
+) -> Result<Option<Arc<WithdrawVerifier>>> {
+    let (Some(params_path), Some(pk_path)) = (
+        &config.operations.withdraw_params_path,
+        &config.operations.withdraw_pk_path,
+    ) else {
+        warn!("Withdraw circuit artifacts not configured - proofs will not be verified locally");
+        return Ok(None);
+    };
+
+    let verifier = WithdrawVerifier::load(params_path, pk_path, chain_id)?;
+    info!("Loaded withdraw verifying key - proofs will be verified locally");
+    Ok(Some(Arc::new(verifier)))
+}
+
 async fn ensure_signers_have_funds(
     node_rpc_urk: &str,
     cornucopia: PrivateKeySigner,
@@ -283,26 +502,3 @@ fn signer(signing_key: &str) -> Result<PrivateKeySigner> {
     PrivateKeySigner::from_str(signing_key)
         .map_err(|err| anyhow!("Failed to create signer - invalid signing key: {err:?}"))
 }
-
-async fn build_shielder_users(
-    signers: Vec<PrivateKeySigner>,
-    config: &ChainConfig,
-    nonce_policy: NoncePolicy,
-) -> Result<Vec<ShielderUser<impl Provider + Clone>>> {
-    let mut shielder_users = vec![];
-    for signer in signers {
-        let policy = match nonce_policy {
-            NoncePolicy::Caching => ConnectionPolicy::Keep {
-                caller_address: signer.address(),
-                provider: create_provider_with_nonce_caching_signer(&config.node_rpc_url, signer)
-                    .await?,
-            },
-            NoncePolicy::Stateless => ConnectionPolicy::OnDemand {
-                signer,
-                rpc_url: config.node_rpc_url.clone(),
-            },
-        };
-        shielder_users.push(ShielderUser::new(config.shielder_contract_address, policy));
-    }
-    Ok(shielder_users)
-}
@@ -17,22 +17,44 @@ pub const REQUEST_DURATION_METRIC: &str = "http_requests_duration_seconds";
 pub const WITHDRAW_DRY_RUN_FAILURE: &str = "withdraw_dry_run_failure";
 pub const WITHDRAW_FAILURE: &str = "withdraw_failure";
 pub const WITHDRAW_SUCCESS: &str = "withdraw_success";
+pub const WITHDRAW_REVERTED: &str = "withdraw_reverted";
+pub const WITHDRAW_NOT_MINED: &str = "withdraw_not_mined";
+pub const WITHDRAW_RESUBMISSION: &str = "withdraw_resubmission";
+pub const WITHDRAW_PRECHECK_FAILURE: &str = "withdraw_precheck_failure";
+pub const RATE_LIMITED: &str = "rate_limited";
+pub const SIGNER_SIDELINED: &str = "signer_sidelined";
+pub const NONCE_GAP_FILLED: &str = "nonce_gap_filled";
+pub const ADMIN_SETTING_CHANGE: &str = "admin_setting_change";
+pub const RELAYING_PAUSED: &str = "relaying_paused";
+pub const RELAY_TASKS_DRAINED: &str = "relay_tasks_drained";
+pub const RELAY_GAS_ESTIMATE: &str = "relay_gas_estimate";
 pub const HEALTH: &str = "health";
 pub const SIGNER_BALANCES: &str = "signer_balances";
 pub const FEE_DESTINATION_BALANCE: &str = "fee_destination_balance";
 pub const EXPIRED_PRICE: &str = "expired_price";
 pub const PRICE_AGE: &str = "price_age";
 
+/// State of a single served chain, rendered by the metrics endpoint (labeled with `chain_id`).
+#[derive(Clone)]
+pub struct ChainMetrics {
+    pub chain_id: u64,
+    pub signer_info: SignerInfo,
+    pub rpc_monitor: RpcMonitor,
+    pub prices: Prices,
+}
+
 pub async fn prometheus_endpoint(
     metrics_handle: PrometheusHandle,
-    signer_info: SignerInfo,
-    rpc_monitor: RpcMonitor,
-    prices: Prices,
+    chains: Vec<ChainMetrics>,
 ) -> impl IntoResponse {
-    metrics::gauge!(HEALTH).set(rpc_monitor.is_healthy().await.is_ok() as u8 as f64);
-    render_signer_balances(&signer_info).await;
-    render_fee_destination_balance(&signer_info).await;
-    render_price_validity(&prices);
+    for chain in &chains {
+        let chain_id = chain.chain_id.to_string();
+        metrics::gauge!(HEALTH, "chain_id" => chain_id.clone())
+            .set(chain.rpc_monitor.is_healthy().await.is_ok() as u8 as f64);
+        render_signer_balances(&chain_id, &chain.signer_info).await;
+        render_fee_destination_balance(&chain_id, &chain.signer_info).await;
+        render_price_validity(&chain_id, &chain.prices);
+    }
 
     metrics_handle.render()
 }
@@ -46,24 +68,30 @@ fn u256_to_f64(value: U256) -> f64 {
         .fold(0_f64, |acc, &limb| acc * pow2_64 + limb as f64)
 }
 
-async fn render_signer_balances(signer_info: &SignerInfo) {
-    let balances = signer_info.balances.clone();
-    for signer in &signer_info.signer_addresses {
-        if let Some(balance) = balances.get(signer) {
-            let unit_balance = balance.read().await.unwrap_or_default();
-            metrics::gauge!(SIGNER_BALANCES, "address" => signer.to_string())
-                .set(u256_to_f64(unit_balance) / 10f64.powi(NATIVE_TOKEN_DECIMALS as i32));
+async fn render_signer_balances(chain_id: &str, signer_info: &SignerInfo) {
+    // Signers can be added and removed at runtime, so all the tracked addresses are rendered.
+    for (signer, balance) in signer_info.balances.read().await.iter() {
+        if *signer != signer_info.fee_destination_address {
+            let unit_balance = balance.unwrap_or_default();
+            metrics::gauge!(
+                SIGNER_BALANCES,
+                "chain_id" => chain_id.to_string(),
+                "address" => signer.to_string()
+            )
+            .set(u256_to_f64(unit_balance) / 10f64.powi(NATIVE_TOKEN_DECIMALS as i32));
         }
     }
 }
 
-async fn render_fee_destination_balance(signer_info: &SignerInfo) {
+async fn render_fee_destination_balance(chain_id: &str, signer_info: &SignerInfo) {
     if let Some(balance) = signer_info
         .balances
+        .read()
+        .await
         .get(&signer_info.fee_destination_address)
     {
-        let unit_balance = balance.read().await.unwrap_or_default();
-        metrics::gauge!(FEE_DESTINATION_BALANCE)
+        let unit_balance = balance.unwrap_or_default();
+        metrics::gauge!(FEE_DESTINATION_BALANCE, "chain_id" => chain_id.to_string())
             .set(u256_to_f64(unit_balance) / 10f64.powi(NATIVE_TOKEN_DECIMALS as i32));
     }
 }
@@ -113,29 +141,39 @@ fn get_request_path(req: &Request) -> String {
     }
 }
 
-fn render_price_validity(prices: &Prices) {
-    render_expired_prices(prices);
-    render_price_ages(prices);
+fn render_price_validity(chain_id: &str, prices: &Prices) {
+    render_expired_prices(chain_id, prices);
+    render_price_ages(chain_id, prices);
 }
 
-fn render_expired_prices(prices: &Prices) {
+fn render_expired_prices(chain_id: &str, prices: &Prices) {
     let current_prices = prices.current_prices();
     for (token, price) in current_prices.iter() {
         let expired = match price {
             Some(_) => 0.0,
             None => 1.0,
         };
-        metrics::gauge!(EXPIRED_PRICE, "token" => token.to_string()).set(expired);
+        metrics::gauge!(
+            EXPIRED_PRICE,
+            "chain_id" => chain_id.to_string(),
+            "token" => token.to_string()
+        )
+        .set(expired);
     }
 }
 
-fn render_price_ages(prices: &Prices) {
+fn render_price_ages(chain_id: &str, prices: &Prices) {
     let ages = prices.price_ages();
     for (token, age) in ages.iter() {
         let age = match age {
             Some(age) => age.as_seconds_f64(),
             None => f64::MAX,
         };
-        metrics::gauge!(PRICE_AGE, "token" => token.to_string()).set(age);
+        metrics::gauge!(
+            PRICE_AGE,
+            "chain_id" => chain_id.to_string(),
+            "token" => token.to_string()
+        )
+        .set(age);
     }
 }
@@ -20,24 +20,34 @@ pub async fn balance_monitor(
 
     loop {
         interval.tick().await;
-        for signer in balances.keys() {
-            match provider.get_balance(*signer).await {
+        let signers = balances.read().await.keys().copied().collect::<Vec<_>>();
+        for signer in signers {
+            match provider.get_balance(signer).await {
                 Ok(balance) => {
-                    set_balance(&balances, *signer, Some(balance)).await;
+                    set_balance(&balances, signer, Some(balance)).await;
                 }
                 Err(err) => {
                     error!("Cannot reach RPC node: {err:?}. Cannot check balance for {signer}");
-                    set_balance(&balances, *signer, None).await;
+                    set_balance(&balances, signer, None).await;
                 }
             }
         }
     }
 }
 
+/// Set the balance of `address`. Does nothing if the address is not tracked (anymore).
 pub async fn set_balance(balances: &Balances, address: Address, balance: Option<U256>) {
-    *balances
-        .get(&address)
-        .expect("Map should be already intialized")
-        .write()
-        .await = balance;
+    if let Some(entry) = balances.write().await.get_mut(&address) {
+        *entry = balance;
+    }
+}
+
+/// Start tracking the balance of `address`.
+pub async fn track_balance(balances: &Balances, address: Address) {
+    balances.write().await.entry(address).or_default();
+}
+
+/// Stop tracking the balance of `address`.
+pub async fn forget_balance(balances: &Balances, address: Address) {
+    balances.write().await.remove(&address);
 }
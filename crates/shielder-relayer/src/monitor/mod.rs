@@ -6,4 +6,4 @@ use tokio::sync::RwLock;
 pub mod balance_monitor;
 pub mod rpc_monitor;
 
-pub type Balances = Arc<HashMap<Address, RwLock<Option<U256>>>>;
+pub type Balances = Arc<RwLock<HashMap<Address, Option<U256>>>>;
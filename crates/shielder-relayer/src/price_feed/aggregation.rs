@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use tracing::warn;
+
+use crate::price_feed::{
+    fetching::{PriceFetchError, PriceInfoFromProvider},
+    sources::{PriceFuture, PriceSource},
+};
+
+/// How long a single source may take to respond before it is counted as failed.
+const SOURCE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Aggregates several sources, so that a single misbehaving one cannot misprice the token.
+///
+/// The sources are queried concurrently, each with a timeout, so a slow one cannot stall the
+/// others. Quotes that deviate from the median of all the received quotes by more than
+/// `max_deviation_percent` are discarded. The price is the median of the remaining quotes, as long
+/// as there are at least `quorum` of them. The oldest of the remaining quotes determines the time
+/// of the price.
+pub struct MedianSource {
+    sources: Vec<Box<dyn PriceSource>>,
+    quorum: usize,
+    max_deviation_percent: u32,
+    source_timeout: Duration,
+}
+
+impl MedianSource {
+    pub fn new(
+        sources: Vec<Box<dyn PriceSource>>,
+        quorum: usize,
+        max_deviation_percent: u32,
+    ) -> Self {
+        Self {
+            sources,
+            quorum: quorum.max(1),
+            max_deviation_percent,
+            source_timeout: SOURCE_TIMEOUT,
+        }
+    }
+}
+
+impl PriceSource for MedianSource {
+    fn name(&self) -> String {
+        let names = self
+            .sources
+            .iter()
+            .map(|source| source.name())
+            .collect::<Vec<_>>();
+        format!("median of [{}]", names.join(", "))
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        Box::pin(async move {
+            let fetches = self.sources.iter().map(|source| async move {
+                match tokio::time::timeout(self.source_timeout, source.fetch()).await {
+                    Ok(Ok(quote)) => Some(quote),
+                    Ok(Err(err)) => {
+                        warn!("Failed to fetch price from {}: {err}", source.name());
+                        None
+                    }
+                    Err(_) => {
+                        warn!("Timed out fetching price from {}", source.name());
+                        None
+                    }
+                }
+            });
+            let quotes = join_all(fetches).await.into_iter().flatten().collect();
+            aggregate(quotes, self.quorum, self.max_deviation_percent)
+        })
+    }
+}
+
+fn aggregate(
+    quotes: Vec<PriceInfoFromProvider>,
+    quorum: usize,
+    max_deviation_percent: u32,
+) -> Result<PriceInfoFromProvider, PriceFetchError> {
+    let no_quorum = |got| PriceFetchError::NoQuorum { got, quorum };
+    if quotes.len() < quorum {
+        return Err(no_quorum(quotes.len()));
+    }
+
+    let reference = median(&quotes);
+    let max_deviation = reference * Decimal::from(max_deviation_percent) / Decimal::ONE_HUNDRED;
+    let (accepted, rejected): (Vec<_>, Vec<_>) = quotes
+        .into_iter()
+        .partition(|quote| (quote.token_price - reference).abs() <= max_deviation);
+    for quote in rejected {
+        warn!(
+            "Discarding price {} - deviates too much from the median {reference}",
+            quote.token_price
+        );
+    }
+    if accepted.len() < quorum {
+        return Err(no_quorum(accepted.len()));
+    }
+
+    Ok(PriceInfoFromProvider {
+        token_price: median(&accepted),
+        time: accepted
+            .iter()
+            .map(|quote| quote.time)
+            .min()
+            .expect("Quorum is positive"),
+    })
+}
+
+fn median(quotes: &[PriceInfoFromProvider]) -> Decimal {
+    let mut prices = quotes
+        .iter()
+        .map(|quote| quote.token_price)
+        .collect::<Vec<_>>();
+    prices.sort();
+    let middle = prices.len() / 2;
+    match prices.len() % 2 {
+        0 => (prices[middle - 1] + prices[middle]) / Decimal::TWO,
+        _ => prices[middle],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use rust_decimal::Decimal;
+    use time::{Duration, OffsetDateTime};
+
+    use super::{aggregate, MedianSource};
+    use crate::price_feed::{
+        fetching::{PriceFetchError, PriceInfoFromProvider},
+        sources::{PriceFuture, PriceSource},
+    };
+
+    fn quotes(prices: &[i64]) -> Vec<PriceInfoFromProvider> {
+        let now = OffsetDateTime::now_utc();
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, price)| PriceInfoFromProvider {
+                token_price: Decimal::from(*price),
+                time: now - Duration::seconds(i as i64),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn outlier_does_not_move_the_price() {
+        let quotes = quotes(&[100, 102, 10_000, 98]);
+        let oldest_accepted = quotes[3].time;
+
+        let price = aggregate(quotes, 3, 5).unwrap();
+
+        assert_eq!(price.token_price, Decimal::from(100));
+        assert_eq!(price.time, oldest_accepted);
+    }
+
+    #[test]
+    fn quorum_is_required() {
+        assert!(matches!(
+            aggregate(quotes(&[100, 101]), 3, 5),
+            Err(PriceFetchError::NoQuorum { got: 2, quorum: 3 })
+        ));
+        // Only two quotes are close enough to the median.
+        assert!(matches!(
+            aggregate(quotes(&[100, 110, 120, 200]), 3, 5),
+            Err(PriceFetchError::NoQuorum { got: 2, quorum: 3 })
+        ));
+    }
+
+    /// Source that never responds.
+    struct HangingSource;
+
+    impl PriceSource for HangingSource {
+        fn name(&self) -> String {
+            "hanging".into()
+        }
+
+        fn fetch(&self) -> PriceFuture<'_> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    /// Source that responds with `price` after `delay`.
+    struct DelayedSource {
+        price: i64,
+        delay: std::time::Duration,
+    }
+
+    impl PriceSource for DelayedSource {
+        fn name(&self) -> String {
+            format!("delayed {}", self.price)
+        }
+
+        fn fetch(&self) -> PriceFuture<'_> {
+            Box::pin(async move {
+                tokio::time::sleep(self.delay).await;
+                Ok(PriceInfoFromProvider {
+                    token_price: Decimal::from(self.price),
+                    time: OffsetDateTime::now_utc(),
+                })
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn sources_are_queried_concurrently_with_timeout() {
+        let delay = std::time::Duration::from_millis(200);
+        let source = MedianSource {
+            sources: vec![
+                Box::new(DelayedSource { price: 100, delay }),
+                Box::new(HangingSource),
+                Box::new(DelayedSource { price: 102, delay }),
+            ],
+            quorum: 2,
+            max_deviation_percent: 5,
+            source_timeout: delay * 2,
+        };
+
+        let start = Instant::now();
+        let price = source.fetch().await.unwrap();
+
+        assert_eq!(price.token_price, Decimal::from(101));
+        assert!(start.elapsed() < delay * 3);
+    }
+}
@@ -18,6 +18,12 @@ pub struct PriceInfoFromProvider {
 pub enum PriceFetchError {
     #[error("Reqwest error: {0}")]
     Reqwest(#[from] reqwest::Error),
+    #[error("RPC error: {0}")]
+    Rpc(String),
+    #[error("Invalid price data: {0}")]
+    InvalidData(String),
+    #[error("Only {got} out of required {quorum} sources agreed on the price")]
+    NoQuorum { got: usize, quorum: usize },
 }
 
 pub async fn fetch_price(url: &str) -> Result<PriceInfoFromProvider, PriceFetchError> {
@@ -1,7 +1,6 @@
 use std::{collections::HashMap, sync::Arc};
 
-use fetching::fetch_price;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 pub use price::Price;
 #[cfg(test)]
 use rust_decimal::Decimal;
@@ -10,21 +9,51 @@ use time::OffsetDateTime;
 use tokio::time::Duration;
 use tracing::warn;
 
-use crate::price_feed::price::Expiration;
+use crate::price_feed::{
+    price::Expiration,
+    sources::{build_source, PriceSource},
+};
 
+mod aggregation;
 mod fetching;
 mod price;
+mod sources;
 
 /// A collection of prices for various coins.
 ///
 /// The underlying structure is behind a mutex, and a process to update it
-/// asynchronously can be started with `start_price_feed`.
+/// asynchronously can be started with `start_price_feed`. The set of tokens can be changed at
+/// runtime with `reconfigure`.
 #[derive(Clone)]
 pub struct Prices {
     validity: time::Duration,
     refresh_interval: Duration,
-    tokens: HashMap<TokenKind, TokenInfo>,
-    inner: HashMap<TokenKind, Arc<Mutex<Option<Price>>>>,
+    feeds: Arc<RwLock<HashMap<TokenKind, Feed>>>,
+}
+
+/// Price of a single token together with the source it is fetched from (`None` for static
+/// prices).
+#[derive(Clone)]
+struct Feed {
+    token: TokenInfo,
+    source: Option<Arc<dyn PriceSource>>,
+    price: Arc<Mutex<Option<Price>>>,
+}
+
+impl Feed {
+    fn new(token: &TokenInfo) -> Self {
+        let (source, price) = match &token.price_provider {
+            PriceProvider::Static(price) => {
+                (None, Some(Price::static_price(*price, token.decimals())))
+            }
+            provider => (Some(Arc::from(build_source(token, provider))), None),
+        };
+        Self {
+            token: token.clone(),
+            source,
+            price: Arc::new(Mutex::new(price)),
+        }
+    }
 }
 
 impl Prices {
@@ -36,38 +65,45 @@ impl Prices {
     pub fn new(tokens: &[TokenInfo], validity: Duration, refresh_interval: Duration) -> Self {
         let validity =
             time::Duration::new(validity.as_secs() as i64, validity.subsec_nanos() as i32);
-
-        let mut token_map = HashMap::new();
-        let mut inner = HashMap::new();
-
-        for token in tokens {
-            token_map.insert(token.kind, token.clone());
-            let price = match &token.price_provider {
-                PriceProvider::Url(_) => None,
-                PriceProvider::Static(price) => Some(Price::static_price(*price, token.decimals())),
-            };
-            inner.insert(token.kind, Arc::new(Mutex::new(price)));
-        }
+        let feeds = tokens
+            .iter()
+            .map(|token| (token.kind, Feed::new(token)))
+            .collect();
 
         Self {
             validity,
             refresh_interval,
-            tokens: token_map,
-            inner,
+            feeds: Arc::new(RwLock::new(feeds)),
         }
     }
 
+    /// Replace the set of tokens. Tokens whose configuration didn't change keep their current
+    /// price, the others are fetched in the next update.
+    pub fn reconfigure(&self, tokens: &[TokenInfo]) {
+        let mut feeds = self.feeds.write();
+        let reconfigured = tokens
+            .iter()
+            .map(|token| match feeds.get(&token.kind) {
+                Some(feed) if feed.token == *token => (token.kind, feed.clone()),
+                _ => (token.kind, Feed::new(token)),
+            })
+            .collect();
+        *feeds = reconfigured;
+    }
+
     /// Gather current price for all the tokens.
     pub fn current_prices(&self) -> HashMap<TokenKind, Option<Price>> {
-        self.tokens.keys().map(|k| (*k, self.price(*k))).collect()
+        let tokens = self.feeds.read().keys().copied().collect::<Vec<_>>();
+        tokens.into_iter().map(|k| (k, self.price(k))).collect()
     }
 
     pub fn price_ages(&self) -> HashMap<TokenKind, Option<time::Duration>> {
         let now = OffsetDateTime::now_utc();
-        self.inner
+        self.feeds
+            .read()
             .iter()
-            .map(|(token, price)| {
-                let price = price.lock();
+            .map(|(token, feed)| {
+                let price = feed.price.lock();
                 if price.is_none() {
                     // if the price is None, it means it was never fetched
                     return (*token, None);
@@ -83,30 +119,35 @@ impl Prices {
 
     /// Get the price of a token or `None` if the price is not available or outdated.
     pub fn price(&self, token: TokenKind) -> Option<Price> {
-        self.inner
+        self.feeds
+            .read()
             .get(&token)?
+            .price
             .lock()
             .clone()?
             .validate(&OffsetDateTime::now_utc())
     }
 
     async fn update(&self) {
-        for token in self.tokens.values() {
-            let PriceProvider::Url(url) = &token.price_provider else {
+        // Fetching takes a while, so it is done on a snapshot of the feeds.
+        let feeds = self.feeds.read().clone();
+        for (token_kind, feed) in feeds {
+            let Some(source) = feed.source else {
                 continue;
             };
+            let price_info = match source.fetch().await {
+                Ok(price_info) => price_info,
+                Err(err) => {
+                    warn!(
+                        "Failed to update {token_kind} price from {}: {err}",
+                        source.name()
+                    );
+                    continue;
+                }
+            };
 
-            let price_info = fetch_price(url).await;
-
-            if let Err(err) = price_info {
-                warn!("Failed to update prices: {err}");
-                continue;
-            }
-
-            let price =
-                Price::from_price_info(price_info.unwrap(), token.decimals(), self.validity);
-
-            self.inner.get(&token.kind).unwrap().lock().replace(price);
+            let price = Price::from_price_info(price_info, feed.token.decimals(), self.validity);
+            feed.price.lock().replace(price);
         }
     }
 }
@@ -129,6 +170,7 @@ mod tests {
         TokenInfo {
             kind: TokenKind::Native,
             price_provider: PriceProvider::Static(Decimal::ONE),
+            fee_policy: Default::default(),
         }
     }
 
@@ -138,6 +180,7 @@ mod tests {
             price_provider: PriceProvider::Url(
                 "https://api.diadata.org/v1/assetQuotation/Ethereum/0x0000000000000000000000000000000000000000".to_string(),
             ),
+            fee_policy: Default::default(),
         }
     }
 
@@ -189,6 +232,31 @@ mod tests {
         assert!(prices.price(TokenKind::Native).is_none());
     }
 
+    #[tokio::test]
+    async fn reconfiguration_keeps_prices_of_unchanged_tokens() {
+        let prices = Prices::new(
+            &[token_with_static_price()],
+            Duration::from_secs(1_000_000),
+            Default::default(),
+        );
+        let erc20 = TokenInfo {
+            kind: TokenKind::ERC20 {
+                address: Default::default(),
+                decimals: 6,
+            },
+            price_provider: PriceProvider::Url("http://localhost:0".to_string()),
+            fee_policy: Default::default(),
+        };
+
+        prices.reconfigure(&[token_with_static_price(), erc20.clone()]);
+        assert!(prices.price(TokenKind::Native).is_some());
+        assert!(prices.price(erc20.kind).is_none());
+
+        prices.reconfigure(&[erc20.clone()]);
+        assert!(prices.price(TokenKind::Native).is_none());
+        assert_eq!(prices.current_prices().len(), 1);
+    }
+
     #[tokio::test]
     async fn start_price_feed_works() {
         let prices = Prices::new(
@@ -0,0 +1,231 @@
+use std::{collections::HashMap, future::Future, pin::Pin};
+
+use alloy_provider::{network::TransactionBuilder, Provider, ProviderBuilder};
+use alloy_rpc_types::TransactionRequest;
+use alloy_sol_types::{sol, SolCall};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use shielder_account::Token;
+use shielder_contract::alloy_primitives::{Address, U256};
+use shielder_relayer::{PriceProvider, QuoteFeeQuery, QuoteFeeResponse, TokenInfo};
+use time::OffsetDateTime;
+
+use crate::price_feed::{
+    aggregation::MedianSource,
+    fetching::{fetch_price, PriceFetchError, PriceInfoFromProvider},
+};
+
+pub type PriceFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<PriceInfoFromProvider, PriceFetchError>> + Send + 'a>>;
+
+/// Something that can tell the current price of a token (in USD).
+pub trait PriceSource: Send + Sync {
+    /// Human-readable description, used in logs.
+    fn name(&self) -> String;
+
+    /// Fetch the current price of the main unit of the token.
+    fn fetch(&self) -> PriceFuture<'_>;
+}
+
+/// Build a source that fetches the price of `token` according to `provider`.
+pub fn build_source(token: &TokenInfo, provider: &PriceProvider) -> Box<dyn PriceSource> {
+    match provider {
+        PriceProvider::Static(price) => Box::new(StaticSource(*price)),
+        PriceProvider::Url(url) => Box::new(DiaSource { url: url.clone() }),
+        PriceProvider::CoinGecko { url, id } => Box::new(CoinGeckoSource {
+            url: url.clone(),
+            id: id.clone(),
+        }),
+        PriceProvider::Chainlink {
+            rpc_url,
+            aggregator,
+        } => Box::new(ChainlinkSource {
+            rpc_url: rpc_url.clone(),
+            aggregator: *aggregator,
+        }),
+        PriceProvider::Relayer(url) => Box::new(RelayerSource {
+            url: url.clone(),
+            token: token.kind.into(),
+        }),
+        PriceProvider::Median {
+            providers,
+            quorum,
+            max_deviation_percent,
+        } => Box::new(MedianSource::new(
+            providers
+                .iter()
+                .map(|provider| build_source(token, provider))
+                .collect(),
+            *quorum,
+            *max_deviation_percent,
+        )),
+    }
+}
+
+/// Fixed price. As a standalone provider it is handled without fetching (see `Prices::new`), but
+/// it can also be a member of an aggregated source (e.g. as a sanity anchor).
+struct StaticSource(Decimal);
+
+impl PriceSource for StaticSource {
+    fn name(&self) -> String {
+        format!("static price {}", self.0)
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        let price = PriceInfoFromProvider {
+            token_price: self.0,
+            time: OffsetDateTime::now_utc(),
+        };
+        Box::pin(async move { Ok(price) })
+    }
+}
+
+/// DIA-compatible endpoint.
+struct DiaSource {
+    url: String,
+}
+
+impl PriceSource for DiaSource {
+    fn name(&self) -> String {
+        format!("DIA ({})", self.url)
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        Box::pin(fetch_price(&self.url))
+    }
+}
+
+/// Single entry of the CoinGecko `simple/price` response.
+#[derive(Deserialize)]
+struct CoinGeckoQuote {
+    usd: Decimal,
+    last_updated_at: Option<i64>,
+}
+
+/// CoinGecko-compatible `simple/price` endpoint.
+struct CoinGeckoSource {
+    url: String,
+    id: String,
+}
+
+impl PriceSource for CoinGeckoSource {
+    fn name(&self) -> String {
+        format!("CoinGecko ({}, {})", self.url, self.id)
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        Box::pin(async move {
+            let mut quotes = reqwest::get(&self.url)
+                .await?
+                .json::<HashMap<String, CoinGeckoQuote>>()
+                .await?;
+            let quote = quotes.remove(&self.id).ok_or_else(|| {
+                PriceFetchError::InvalidData(format!("No price for `{}`", self.id))
+            })?;
+            let time = match quote.last_updated_at {
+                Some(timestamp) => OffsetDateTime::from_unix_timestamp(timestamp)
+                    .map_err(|err| PriceFetchError::InvalidData(err.to_string()))?,
+                None => OffsetDateTime::now_utc(),
+            };
+            Ok(PriceInfoFromProvider {
+                token_price: quote.usd,
+                time,
+            })
+        })
+    }
+}
+
+sol! {
+    function latestRoundData() external view returns (
+        uint80 roundId,
+        int256 answer,
+        uint256 startedAt,
+        uint256 updatedAt,
+        uint80 answeredInRound
+    );
+    function decimals() external view returns (uint8);
+}
+
+/// Chainlink aggregator contract.
+struct ChainlinkSource {
+    rpc_url: String,
+    aggregator: Address,
+}
+
+impl ChainlinkSource {
+    async fn call<C: SolCall + Send>(&self, call: C) -> Result<C::Return, PriceFetchError> {
+        let rpc_error = |err: &dyn std::fmt::Display| PriceFetchError::Rpc(err.to_string());
+        let provider = ProviderBuilder::new()
+            .on_builtin(&self.rpc_url)
+            .await
+            .map_err(|err| rpc_error(&err))?;
+        let tx = TransactionRequest::default()
+            .with_to(self.aggregator)
+            .with_input(call.abi_encode());
+        let result = provider.call(&tx).await.map_err(|err| rpc_error(&err))?;
+        C::abi_decode_returns(&result, true).map_err(|err| rpc_error(&err))
+    }
+}
+
+impl PriceSource for ChainlinkSource {
+    fn name(&self) -> String {
+        format!("Chainlink ({})", self.aggregator)
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        Box::pin(async move {
+            let decimals = self.call(decimalsCall {}).await?._0;
+            let round = self.call(latestRoundDataCall {}).await?;
+
+            let answer = i128::try_from(round.answer)
+                .ok()
+                .filter(|answer| *answer > 0)
+                .ok_or_else(|| {
+                    PriceFetchError::InvalidData(format!("Invalid answer: {}", round.answer))
+                })?;
+            let updated_at =
+                i64::try_from(round.updatedAt.min(U256::from(i64::MAX))).expect("Value is capped");
+            let time = OffsetDateTime::from_unix_timestamp(updated_at)
+                .map_err(|err| PriceFetchError::InvalidData(err.to_string()))?;
+
+            Ok(PriceInfoFromProvider {
+                token_price: Decimal::try_from_i128_with_scale(answer, decimals as u32)
+                    .map_err(|err| PriceFetchError::InvalidData(err.to_string()))?,
+                time,
+            })
+        })
+    }
+}
+
+/// Another Shielder relayer. Its quote for a relay paid in `token` contains the token price.
+struct RelayerSource {
+    url: String,
+    token: Token,
+}
+
+impl PriceSource for RelayerSource {
+    fn name(&self) -> String {
+        format!("relayer ({})", self.url)
+    }
+
+    fn fetch(&self) -> PriceFuture<'_> {
+        Box::pin(async move {
+            let query = QuoteFeeQuery {
+                fee_token: self.token,
+                pocket_money: U256::ZERO,
+            };
+            let response = reqwest::Client::new()
+                .post(format!("{}/quote_fees", self.url.trim_end_matches('/')))
+                .json(&query)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<QuoteFeeResponse>()
+                .await?;
+            Ok(PriceInfoFromProvider {
+                token_price: response.price_details.fee_token_price,
+                time: OffsetDateTime::now_utc(),
+            })
+        })
+    }
+}
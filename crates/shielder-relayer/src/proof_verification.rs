@@ -0,0 +1,135 @@
+use std::fs;
+
+use anyhow::{anyhow, Result};
+use shielder_circuits::{
+    circuits::{Params, VerifyingKey},
+    marshall::{unmarshall_params, unmarshall_pk},
+    verify,
+    withdraw::{WithdrawInstance, WithdrawProverKnowledge},
+    Fr, ProverKnowledge, PublicInputProvider,
+};
+use shielder_contract::{
+    alloy_primitives::{Address, U256},
+    WithdrawCommitment,
+};
+use shielder_relayer::RelayCalldata;
+use shielder_setup::{protocol_fee::compute_protocol_fee_from_gross, version::contract_version};
+use type_conversions::{address_to_field, u256_to_field};
+
+type WithdrawCircuit = <WithdrawProverKnowledge<Fr> as ProverKnowledge>::Circuit;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum ProofVerificationError {
+    #[error("Amount does not cover the protocol fee and the relayer fee")]
+    FeeHigherThanAmount,
+    #[error("Proof is not valid for the requested withdrawal")]
+    InvalidProof,
+}
+
+/// Verifies withdrawal proofs off-chain, exactly like the Shielder contract would, so that invalid
+/// requests can be rejected without spending a dry-run on them.
+pub struct WithdrawVerifier {
+    params: Params,
+    vk: VerifyingKey,
+    chain_id: U256,
+}
+
+impl WithdrawVerifier {
+    /// Load the withdraw circuit artifacts (the same ones that the clients use for proving).
+    pub fn load(params_path: &str, pk_path: &str, chain_id: u64) -> Result<Self> {
+        let params = unmarshall_params(&fs::read(params_path)?)
+            .map_err(|err| anyhow!("Failed to read withdraw params from {params_path}: {err:?}"))?;
+        let (_, pk) = unmarshall_pk::<WithdrawCircuit>(&fs::read(pk_path)?)
+            .map_err(|err| anyhow!("Failed to read withdraw pk from {pk_path}: {err:?}"))?;
+        Ok(Self {
+            params,
+            vk: pk.get_vk().clone(),
+            chain_id: U256::from(chain_id),
+        })
+    }
+
+    /// Verify the proof from `calldata` against the public inputs that the contract would compute
+    /// for the withdrawal relayed by `relayer_address` for `relayer_fee`.
+    pub fn verify(
+        &self,
+        calldata: &RelayCalldata,
+        relayer_address: Address,
+        relayer_fee: U256,
+        protocol_fee_bps: U256,
+    ) -> Result<(), ProofVerificationError> {
+        let protocol_fee = check_fees(calldata.amount, relayer_fee, protocol_fee_bps)?;
+
+        let commitment = WithdrawCommitment {
+            contract_version: contract_version(),
+            withdraw_address: calldata.withdraw_address,
+            relayer_address,
+            relayer_fee,
+            chain_id: self.chain_id,
+            pocket_money: calldata.pocket_money,
+            protocol_fee,
+            memo: calldata.memo.clone(),
+        }
+        .commitment_hash();
+
+        // Same values as in the contract's `_withdraw`.
+        let public_input = |input: WithdrawInstance| -> Fr {
+            match input {
+                WithdrawInstance::MerkleRoot => u256_to_field(calldata.merkle_root),
+                WithdrawInstance::HashedOldNullifier => u256_to_field(calldata.nullifier_hash),
+                WithdrawInstance::HashedNewNote => u256_to_field(calldata.new_note),
+                WithdrawInstance::WithdrawalValue => u256_to_field(calldata.amount),
+                WithdrawInstance::TokenAddress => address_to_field(calldata.fee_token.address()),
+                WithdrawInstance::Commitment => u256_to_field(commitment),
+                WithdrawInstance::MacSalt => u256_to_field(calldata.mac_salt),
+                WithdrawInstance::MacCommitment => u256_to_field(calldata.mac_commitment),
+            }
+        };
+
+        verify(
+            &self.params,
+            &self.vk,
+            &calldata.proof.to_vec(),
+            &public_input.serialize_public_input(),
+        )
+        .map_err(|_| ProofVerificationError::InvalidProof)
+    }
+}
+
+/// Compute the protocol fee that the contract would charge and check that `amount` covers it
+/// together with `relayer_fee`.
+fn check_fees(
+    amount: U256,
+    relayer_fee: U256,
+    protocol_fee_bps: U256,
+) -> Result<U256, ProofVerificationError> {
+    let protocol_fee = compute_protocol_fee_from_gross(amount, protocol_fee_bps);
+    match amount.checked_sub(protocol_fee) {
+        Some(net_amount) if net_amount > relayer_fee => Ok(protocol_fee),
+        _ => Err(ProofVerificationError::FeeHigherThanAmount),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shielder_contract::alloy_primitives::U256;
+
+    use crate::proof_verification::{check_fees, ProofVerificationError};
+
+    #[test]
+    fn protocol_fee_is_rounded_up_and_must_leave_room_for_relayer_fee() {
+        let bps = U256::from(25);
+
+        assert_eq!(
+            check_fees(U256::from(10_001), U256::from(100), bps),
+            Ok(U256::from(26))
+        );
+        assert_eq!(
+            check_fees(U256::from(10_000), U256::from(9_975), bps),
+            Err(ProofVerificationError::FeeHigherThanAmount)
+        );
+        assert_eq!(
+            check_fees(U256::from(10_000), U256::from(9_974), bps),
+            Ok(U256::from(25))
+        );
+    }
+}
@@ -1,6 +1,4 @@
-use alloy_provider::Provider;
 use axum::{extract::State, response::IntoResponse, Json};
-use shielder_account::Token;
 use shielder_contract::{alloy_primitives::U256, providers::create_simple_provider};
 use shielder_relayer::{
     compute_fee,
@@ -10,7 +8,7 @@ use shielder_relayer::{
 use time::OffsetDateTime;
 use tracing::error;
 
-use crate::{price_feed::Price, quote_cache::CachedQuote, AppState};
+use crate::{gas_pricing::network_fees, price_feed::Price, quote_signing::Quote, AppState};
 
 /// Get a quote for the fees associated with a relay.
 #[utoipa::path(
@@ -41,23 +39,38 @@ async fn _quote_fees(
 ) -> Result<QuoteFeeResponse, String> {
     let gas_price = U256::from(get_gas_price(&app_state).await?);
 
+    let token_info = app_state
+        .runtime_config
+        .token_info(query.fee_token)
+        .ok_or_else(|| {
+            format!(
+                "Requested token fee is not supported: {:?}",
+                query.fee_token
+            )
+        })?;
+    // Recent estimate if there is any, otherwise the configured value.
+    let relay_gas = app_state
+        .relay_gas_estimator
+        .estimate(query.fee_token)
+        .unwrap_or(app_state.relay_gas);
+    let fee_policy = app_state.runtime_config.fee_policy(&token_info, relay_gas);
+
     // Token conversion.
-    let prices = match query.fee_token {
-        Token::Native => {
+    let prices = match token_info.kind {
+        TokenKind::Native => {
             let price = get_native_token_price(&app_state)?;
             Prices {
                 fee_token_price: price.clone(),
                 native_token_price: price,
             }
         }
-        erc20 @ Token::ERC20 { .. } => get_token_price(&app_state, erc20)?,
+        erc20 @ TokenKind::ERC20 { .. } => get_token_price(&app_state, erc20)?,
     };
 
     let fee_details = compute_fee(
         gas_price,
-        app_state.relay_gas,
         query.pocket_money,
-        app_state.service_fee_percent,
+        &fee_policy,
         prices.native_token_price.unit_price,
         prices.fee_token_price.unit_price,
     )?;
@@ -70,32 +83,36 @@ async fn _quote_fees(
         fee_token_unit_price: prices.fee_token_price.unit_price,
     };
 
-    let cached_quote = CachedQuote {
+    let quote = Quote {
         fee_token: query.fee_token,
         gas_price,
         native_token_unit_price: prices.native_token_price.unit_price,
         fee_token_unit_price: prices.fee_token_price.unit_price,
+        relay_gas: fee_details.relay_gas,
+        pocket_money: query.pocket_money,
+        total_fee: fee_details.total_cost_fee_token,
     };
-    app_state
-        .quote_cache
-        .store_quote_response(cached_quote, OffsetDateTime::now_utc())
-        .await;
+    let quote_signature = app_state
+        .quote_signer
+        .sign(&quote, OffsetDateTime::now_utc());
 
     Ok(QuoteFeeResponse {
         fee_details,
         price_details,
+        quote_signature,
     })
 }
 
+/// Price per gas that covers the relay even if the base fee grows by the configured headroom.
 async fn get_gas_price(app_state: &AppState) -> Result<u128, String> {
     let provider = create_simple_provider(&app_state.node_rpc_url)
         .await
         .map_err(|err| format!("Failed to create provider: {err}"))?;
 
-    provider
-        .get_gas_price()
+    let fees = network_fees(&provider, &app_state.gas_pricing)
         .await
-        .map_err(|err| format!("Failed to get gas price: {err}"))
+        .map_err(|err| format!("Failed to get network fees: {err}"))?;
+    Ok(fees.quoted_gas_price(&app_state.gas_pricing))
 }
 
 struct Prices {
@@ -110,16 +127,9 @@ fn get_native_token_price(app_state: &AppState) -> Result<Price, String> {
         .ok_or("Native token price not available")?)
 }
 
-fn get_token_price(app_state: &AppState, token: Token) -> Result<Prices, String> {
+fn get_token_price(app_state: &AppState, token_kind: TokenKind) -> Result<Prices, String> {
     let native_token_price = get_native_token_price(app_state)?;
 
-    let token_kind = app_state
-        .token_config
-        .iter()
-        .find(|info| Token::from(info.kind) == token)
-        .map(|info| info.kind)
-        .ok_or_else(|| format!("Requested token fee is not supported: {token:?}"))?;
-
     let fee_token_price = app_state
         .prices
         .price(token_kind)
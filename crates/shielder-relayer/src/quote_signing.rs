@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use alloy_primitives::{keccak256, B256, U256};
+use rust_decimal::Decimal;
+use shielder_account::Token;
+use shielder_relayer::QuoteSignature;
+use time::OffsetDateTime;
+
+/// Domain separator, so that a quote signature cannot be mistaken for any other keyed hash.
+const QUOTE_DOMAIN: &[u8] = b"shielder-relayer-quote-v4";
+
+/// Quote data that was presented to a user and should be referenced to during relay request.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Quote {
+    /// Requested fee token.
+    pub fee_token: Token,
+    /// Gas price (in native token) at the quotation moment.
+    pub gas_price: U256,
+    /// Price of the minimal unit of the native token (like 1 wei or 1 satoshi) at the quotation moment.
+    pub native_token_unit_price: Decimal,
+    /// Price of the minimal unit of the fee token (like 1 wei or 1 satoshi) at the quotation moment.
+    pub fee_token_unit_price: Decimal,
+    /// Gas amount that the relay was priced for.
+    pub relay_gas: u64,
+    /// Pocket money that the relay was priced for.
+    pub pocket_money: U256,
+    /// Total fee (in fee token) that the relayer charges for the relay.
+    pub total_fee: U256,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum QuoteError {
+    #[error("Quote has expired")]
+    Expired,
+    #[error("Quote signature is invalid")]
+    InvalidSignature,
+}
+
+/// Service issuing quotations with a certain validity and checking them later, without keeping any
+/// state. Every relayer replica configured with the same key accepts quotes issued by the others
+/// (but only for the same chain).
+#[derive(Clone)]
+pub struct QuoteSigner {
+    key: B256,
+    validity: Duration,
+    chain_id: u64,
+}
+
+impl QuoteSigner {
+    /// Creates a new quote signer, with the key derived from `secret`.
+    pub fn new(secret: &str, quote_validity: Duration, chain_id: u64) -> Self {
+        Self {
+            key: keccak256(secret),
+            validity: quote_validity,
+            chain_id,
+        }
+    }
+
+    /// Creates a new quote signer with a random key. Its quotes are accepted only by itself and
+    /// only until restart.
+    pub fn random(quote_validity: Duration, chain_id: u64) -> Self {
+        Self {
+            key: B256::from(rand::random::<[u8; 32]>()),
+            validity: quote_validity,
+            chain_id,
+        }
+    }
+
+    /// Sign `quote`. Its validity starts at `at` and lasts for `self.validity`.
+    pub fn sign(&self, quote: &Quote, at: OffsetDateTime) -> QuoteSignature {
+        let expires_at = (at + self.validity).unix_timestamp().max(0) as u64;
+        let nonce = B256::from(rand::random::<[u8; 32]>());
+        QuoteSignature {
+            expires_at,
+            nonce,
+            signature: self.mac(quote, expires_at, nonce),
+        }
+    }
+
+    /// Check whether `signature` was issued for `quote` and is still valid at `at`.
+    pub fn verify(
+        &self,
+        quote: &Quote,
+        signature: &QuoteSignature,
+        at: OffsetDateTime,
+    ) -> Result<(), QuoteError> {
+        let expected = self.mac(quote, signature.expires_at, signature.nonce);
+        if !constant_time_eq(&expected, &signature.signature) {
+            return Err(QuoteError::InvalidSignature);
+        }
+        if at.unix_timestamp() >= signature.expires_at as i64 {
+            return Err(QuoteError::Expired);
+        }
+        Ok(())
+    }
+
+    /// `keccak256(key || domain || chain_id || quote || expires_at || nonce)`. Keccak is not prone
+    /// to length extension, so prefixing the message with the key is enough to get a secure MAC.
+    fn mac(&self, quote: &Quote, expires_at: u64, nonce: B256) -> B256 {
+        let mut message = Vec::with_capacity(256);
+        message.extend_from_slice(self.key.as_slice());
+        message.extend_from_slice(QUOTE_DOMAIN);
+        message.extend_from_slice(&self.chain_id.to_be_bytes());
+        message.extend_from_slice(quote.fee_token.address().as_slice());
+        message.extend_from_slice(&quote.gas_price.to_be_bytes::<32>());
+        message.extend_from_slice(&quote.relay_gas.to_be_bytes());
+        message.extend_from_slice(&quote.pocket_money.to_be_bytes::<32>());
+        message.extend_from_slice(&quote.total_fee.to_be_bytes::<32>());
+        for price in [quote.native_token_unit_price, quote.fee_token_unit_price] {
+            // Normalized, so that the same price sent back with a different scale is still valid.
+            let price = price.normalize().to_string();
+            message.extend_from_slice(&(price.len() as u32).to_be_bytes());
+            message.extend_from_slice(price.as_bytes());
+        }
+        message.extend_from_slice(&expires_at.to_be_bytes());
+        message.extend_from_slice(nonce.as_slice());
+        keccak256(message)
+    }
+}
+
+pub fn constant_time_eq(a: &B256, b: &B256) -> bool {
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy_primitives::{B256, U256};
+    use rust_decimal::Decimal;
+    use shielder_account::Token;
+    use time::OffsetDateTime;
+
+    use crate::quote_signing::{Quote, QuoteError, QuoteSigner};
+
+    const VALIDITY: Duration = Duration::from_secs(10);
+    const CHAIN_ID: u64 = 1;
+
+    fn quote_signer() -> QuoteSigner {
+        QuoteSigner::new("secret", VALIDITY, CHAIN_ID)
+    }
+
+    fn quote() -> Quote {
+        Quote {
+            fee_token: Token::Native,
+            gas_price: U256::from(1),
+            native_token_unit_price: Decimal::new(15, 1),
+            fee_token_unit_price: Decimal::ONE,
+            relay_gas: 2_000_000,
+            pocket_money: U256::ZERO,
+            total_fee: U256::from(3_000_000),
+        }
+    }
+
+    #[test]
+    fn quote_is_valid_right_after_being_signed() {
+        let now = OffsetDateTime::now_utc();
+        let signature = quote_signer().sign(&quote(), now);
+
+        assert_eq!(quote_signer().verify(&quote(), &signature, now), Ok(()));
+    }
+
+    #[test]
+    fn quote_is_invalid_after_validity_period() {
+        let now = OffsetDateTime::now_utc();
+        let signature = quote_signer().sign(&quote(), now);
+
+        assert_eq!(
+            quote_signer().verify(&quote(), &signature, now + VALIDITY * 2),
+            Err(QuoteError::Expired)
+        );
+    }
+
+    #[test]
+    fn tampered_quote_is_rejected() {
+        let now = OffsetDateTime::now_utc();
+        let signer = quote_signer();
+        let signature = signer.sign(&quote(), now);
+
+        let cheaper = Quote {
+            gas_price: U256::ZERO,
+            ..quote()
+        };
+        assert_eq!(
+            signer.verify(&cheaper, &signature, now),
+            Err(QuoteError::InvalidSignature)
+        );
+
+        for tampered in [
+            Quote {
+                total_fee: U256::from(1),
+                ..quote()
+            },
+            Quote {
+                pocket_money: U256::from(1),
+                ..quote()
+            },
+        ] {
+            assert_eq!(
+                signer.verify(&tampered, &signature, now),
+                Err(QuoteError::InvalidSignature)
+            );
+        }
+
+        let mut extended = signature.clone();
+        extended.expires_at += 3600;
+        assert_eq!(
+            signer.verify(&quote(), &extended, now),
+            Err(QuoteError::InvalidSignature)
+        );
+
+        let mut renonced = signature;
+        renonced.nonce = B256::ZERO;
+        assert_eq!(
+            signer.verify(&quote(), &renonced, now),
+            Err(QuoteError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn quotes_are_portable_between_signers_with_the_same_key() {
+        let now = OffsetDateTime::now_utc();
+        let signature = quote_signer().sign(&quote(), now);
+
+        assert_eq!(quote_signer().verify(&quote(), &signature, now), Ok(()));
+        assert_eq!(
+            QuoteSigner::new("other secret", VALIDITY, CHAIN_ID).verify(&quote(), &signature, now),
+            Err(QuoteError::InvalidSignature)
+        );
+        assert_eq!(
+            QuoteSigner::new("secret", VALIDITY, CHAIN_ID + 1).verify(&quote(), &signature, now),
+            Err(QuoteError::InvalidSignature)
+        );
+        assert_eq!(
+            QuoteSigner::random(VALIDITY, CHAIN_ID).verify(&quote(), &signature, now),
+            Err(QuoteError::InvalidSignature)
+        );
+    }
+
+    #[test]
+    fn price_scale_does_not_matter() {
+        let now = OffsetDateTime::now_utc();
+        let signer = quote_signer();
+        let signature = signer.sign(&quote(), now);
+
+        let rescaled = Quote {
+            native_token_unit_price: Decimal::new(1500, 3),
+            ..quote()
+        };
+        assert_eq!(signer.verify(&rescaled, &signature, now), Ok(()));
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::HeaderMap,
+    middleware::Next,
+    response::Response,
+};
+use parking_lot::Mutex;
+use shielder_relayer::server::too_many_requests;
+use tokio::time::{interval, Instant};
+use tracing::debug;
+
+use crate::metrics::RATE_LIMITED;
+
+/// How often the buckets of clients that have not been seen recently are dropped.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Header in which reverse proxies pass the addresses of the clients they forward.
+const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Range of IP addresses in the CIDR notation (e.g. `10.0.0.0/8`). A bare address stands for a
+/// single host.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    fn contains(&self, ip: IpAddr) -> bool {
+        let (network, ip, bits) = match (self.address, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                (u32::from(network) as u128, u32::from(ip) as u128, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => (u128::from(network), u128::from(ip), 128),
+            _ => return false,
+        };
+        self.prefix_len == 0 || (network ^ ip) >> (bits - self.prefix_len) == 0
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match s.trim().split_once('/') {
+            Some((address, prefix_len)) => (address.parse::<IpAddr>()?, Some(prefix_len.parse()?)),
+            None => (s.trim().parse::<IpAddr>()?, None),
+        };
+        let max_prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        let prefix_len = prefix_len.unwrap_or(max_prefix_len);
+        if prefix_len > max_prefix_len {
+            bail!("Invalid prefix length in `{s}`");
+        }
+        Ok(Self {
+            address,
+            prefix_len,
+        })
+    }
+}
+
+/// Parses a comma-separated list of networks.
+pub fn parse_ip_networks(s: &str) -> anyhow::Result<Vec<IpNetwork>> {
+    s.split(',')
+        .filter(|network| !network.trim().is_empty())
+        .map(|network| network.parse().map_err(|e| anyhow!("`{network}`: {e}")))
+        .collect()
+}
+
+/// Token bucket allowing `capacity` requests at once and refilling at `capacity` requests per
+/// minute.
+#[derive(Copy, Clone, Debug)]
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(requests_per_minute: u32, now: Instant) -> Self {
+        Self {
+            capacity: requests_per_minute as f64,
+            tokens: requests_per_minute as f64,
+            last_refill: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / 60.).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take a single token. If there is none, returns how long it takes until one is available.
+    fn try_take(&mut self, now: Instant) -> Result<(), Duration> {
+        self.refill(now);
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1. - self.tokens) * 60. / self.capacity,
+            ))
+        }
+    }
+
+    fn is_full(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        self.tokens >= self.capacity
+    }
+}
+
+/// Request limits applied to the relayer endpoints. A limit of `0` means no limit.
+#[derive(Clone)]
+pub struct RateLimiter {
+    per_ip_limit: u32,
+    global: Option<Arc<Mutex<TokenBucket>>>,
+    per_ip: Arc<Mutex<HashMap<IpAddr, TokenBucket>>>,
+    trusted_proxies: Arc<Vec<IpNetwork>>,
+}
+
+impl RateLimiter {
+    /// Creates a new rate limiter. Limits are in requests per minute. Requests coming from
+    /// `trusted_proxies` are attributed to the client they were forwarded for.
+    pub fn new(per_ip_limit: u32, global_limit: u32, trusted_proxies: Vec<IpNetwork>) -> Self {
+        Self {
+            per_ip_limit,
+            global: (global_limit > 0)
+                .then(|| Arc::new(Mutex::new(TokenBucket::new(global_limit, Instant::now())))),
+            per_ip: Default::default(),
+            trusted_proxies: Arc::new(trusted_proxies),
+        }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies
+            .iter()
+            .any(|network| network.contains(ip))
+    }
+
+    /// The address of the client that sent the request, as seen by the last proxy we trust.
+    ///
+    /// Without trusted proxies (or when `peer` is not one of them) this is just `peer`. Otherwise,
+    /// `X-Forwarded-For` is read from the right (every proxy appends the address it got the
+    /// request from) and the first untrusted entry is taken. Anything to the left of it could be
+    /// forged by the client.
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let forwarded_for = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect::<Vec<_>>();
+
+        let mut client = peer;
+        for entry in forwarded_for.into_iter().rev() {
+            match entry.trim().parse::<IpAddr>() {
+                Ok(ip) if self.is_trusted(ip) => client = ip,
+                Ok(ip) => return ip,
+                // Garbage from an untrusted hop: the last trusted address is the best we know.
+                Err(_) => break,
+            }
+        }
+        client
+    }
+
+    /// Register a request from `ip`. If it exceeds any limit, returns the limit name and how long
+    /// the client should wait before retrying.
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), (&'static str, Duration)> {
+        if self.per_ip_limit > 0 {
+            self.per_ip
+                .lock()
+                .entry(ip)
+                .or_insert_with(|| TokenBucket::new(self.per_ip_limit, now))
+                .try_take(now)
+                .map_err(|retry_after| ("ip", retry_after))?;
+        }
+        if let Some(global) = &self.global {
+            global
+                .lock()
+                .try_take(now)
+                .map_err(|retry_after| ("global", retry_after))?;
+        }
+        Ok(())
+    }
+
+    /// Single sweep over the per-IP buckets. Full buckets are equivalent to missing ones.
+    fn collect_garbage(&self) {
+        let now = Instant::now();
+        self.per_ip.lock().retain(|_, bucket| !bucket.is_full(now));
+    }
+}
+
+/// Spawns a background garbage collector worker, responsible for removing idle clients.
+pub async fn garbage_collector_worker(rate_limiter: RateLimiter) {
+    let mut interval = interval(CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        rate_limiter.collect_garbage();
+    }
+}
+
+/// Middleware rejecting requests that exceed the limits with `429 Too Many Requests`.
+pub async fn rate_limit(
+    State(rate_limiter): State<RateLimiter>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let client = rate_limiter.client_ip(peer.ip(), req.headers());
+    match rate_limiter.check(client, Instant::now()) {
+        Ok(()) => next.run(req).await,
+        Err((scope, retry_after)) => {
+            debug!("Rate limit ({scope}) exceeded by {client}");
+            metrics::counter!(RATE_LIMITED, "scope" => scope).increment(1);
+            too_many_requests(retry_after)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{IpAddr, Ipv4Addr},
+        time::Duration,
+    };
+
+    use axum::http::{HeaderMap, HeaderValue};
+    use tokio::time::Instant;
+
+    use crate::rate_limit::{parse_ip_networks, RateLimiter};
+
+    const ALICE: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    const BOB: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    #[test]
+    fn per_ip_limit_is_separate_for_every_client() {
+        let limiter = RateLimiter::new(2, 0, vec![]);
+        let now = Instant::now();
+
+        assert!(limiter.check(ALICE, now).is_ok());
+        assert!(limiter.check(ALICE, now).is_ok());
+        let (scope, retry_after) = limiter.check(ALICE, now).unwrap_err();
+        assert_eq!(scope, "ip");
+        assert_eq!(retry_after, Duration::from_secs(30));
+
+        assert!(limiter.check(BOB, now).is_ok());
+        assert!(limiter.check(ALICE, now + Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn global_limit_applies_to_all_clients() {
+        let limiter = RateLimiter::new(0, 2, vec![]);
+        let now = Instant::now();
+
+        assert!(limiter.check(ALICE, now).is_ok());
+        assert!(limiter.check(BOB, now).is_ok());
+        assert_eq!(limiter.check(BOB, now).unwrap_err().0, "global");
+    }
+
+    #[test]
+    fn idle_clients_are_forgotten() {
+        let limiter = RateLimiter::new(60, 0, vec![]);
+        limiter.check(ALICE, Instant::now()).unwrap();
+        limiter.collect_garbage();
+        assert_eq!(limiter.per_ip.lock().len(), 1);
+
+        limiter.per_ip.lock().get_mut(&ALICE).unwrap().last_refill -= Duration::from_secs(2);
+        limiter.collect_garbage();
+        assert!(limiter.per_ip.lock().is_empty());
+    }
+
+    fn forwarded_for(entries: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for entry in entries {
+            headers.append("x-forwarded-for", HeaderValue::from_str(entry).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn without_trusted_proxies_forwarded_for_is_ignored() {
+        let limiter = RateLimiter::new(1, 0, vec![]);
+        let headers = forwarded_for(&["10.0.0.2"]);
+
+        assert_eq!(limiter.client_ip(ALICE, &headers), ALICE);
+    }
+
+    #[test]
+    fn behind_trusted_proxy_rightmost_untrusted_entry_is_the_client() {
+        let limiter = RateLimiter::new(
+            1,
+            0,
+            parse_ip_networks("192.168.0.0/16, 172.16.0.1").unwrap(),
+        );
+        let proxy: IpAddr = "192.168.1.1".parse().unwrap();
+
+        // Alice forges an entry; the proxies append Bob's real address and their own.
+        let headers = forwarded_for(&["1.2.3.4, 10.0.0.2", "172.16.0.1"]);
+        assert_eq!(limiter.client_ip(proxy, &headers), BOB);
+
+        // An untrusted peer cannot pretend to be someone else.
+        assert_eq!(limiter.client_ip(ALICE, &headers), ALICE);
+
+        // Without the header, the proxy itself is the client.
+        assert_eq!(limiter.client_ip(proxy, &HeaderMap::new()), proxy);
+
+        let headers = forwarded_for(&["not-an-ip, 172.16.0.1"]);
+        assert_eq!(
+            limiter.client_ip(proxy, &headers),
+            "172.16.0.1".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn networks_are_parsed_and_matched() {
+        let networks = parse_ip_networks("10.0.0.0/8,::1,2001:db8::/32").unwrap();
+        assert!(networks[0].contains("10.255.0.1".parse().unwrap()));
+        assert!(!networks[0].contains("11.0.0.1".parse().unwrap()));
+        assert!(networks[0].contains("::ffff:10.0.0.1".parse().unwrap()));
+        assert!(networks[1].contains("::1".parse().unwrap()));
+        assert!(!networks[1].contains("::2".parse().unwrap()));
+        assert!(networks[2].contains("2001:db8:1::1".parse().unwrap()));
+
+        assert!(parse_ip_networks("10.0.0.0/33").is_err());
+        assert!(parse_ip_networks("localhost").is_err());
+    }
+}
@@ -0,0 +1,400 @@
+use std::{future::Future, time::Duration};
+
+use alloy_consensus::Transaction as _;
+use alloy_network::AnyNetwork;
+use alloy_provider::Provider;
+use alloy_transport::BoxTransport;
+use anyhow::Result;
+use shielder_contract::{
+    alloy_primitives::{Address, TxHash},
+    providers::create_simple_provider,
+    ContractResult, TxOverrides,
+};
+use tokio::time::{sleep, Instant};
+use tracing::{info, warn};
+
+use crate::gas_pricing::FeeCap;
+
+/// How often we check the status of a submitted transaction.
+const POLLING_INTERVAL: Duration = Duration::from_secs(2);
+/// How often we check whether an unresolved transaction has been settled by the node.
+const SETTLEMENT_POLLING_INTERVAL: Duration = Duration::from_secs(30);
+/// By how much (in percent) the fees are raised when a transaction is resubmitted. Nodes usually
+/// require at least 10% to accept a replacement.
+const GAS_BUMP_PERCENT: u128 = 20;
+
+/// How the relay workers follow transactions after they are submitted.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConfirmationPolicy {
+    /// Number of blocks (including the one with the transaction) after which a transaction is
+    /// considered final.
+    pub confirmations: u64,
+    /// How long to wait for a transaction to be mined before resubmitting it with higher fees.
+    pub resubmission_timeout: Duration,
+    /// How many times a transaction can be resubmitted before tracking gives up. Once the fee cap
+    /// is reached, every further `resubmission_timeout` counts as a resubmission as well.
+    pub max_resubmissions: u32,
+}
+
+/// Final outcome of a submitted transaction.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TxOutcome {
+    /// The transaction (or one of its replacements) succeeded and has enough confirmations.
+    Confirmed(TxHash),
+    /// The transaction (or one of its replacements) was mined, but reverted.
+    Reverted(TxHash),
+    /// None of the sent versions has been (or will be) mined, so the nonce is free again. Contains
+    /// the reason.
+    NotMined(String),
+}
+
+/// Tracking gave up, but some of the sent versions are still known to the node and can be mined
+/// anytime - the nonce cannot be reused until they are settled (see [`await_settlement`]).
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unresolved {
+    pub reason: String,
+    /// All the sent versions of the transaction.
+    pub sent: Vec<TxHash>,
+}
+
+/// Follow the transaction `tx_hash` until it is final.
+///
+/// If the transaction is not mined within `policy.resubmission_timeout`, `resubmit` is called with
+/// the same nonce and bumped fees, and from then on all the sent versions are followed (any of them
+/// can be mined). `on_resubmitted` is called with the hash of every successful resubmission.
+///
+/// The bumped fees never exceed `fee_cap`. Once it is reached, the transaction is not resubmitted
+/// anymore and the already sent versions are awaited.
+///
+/// After `policy.max_resubmissions` rounds tracking gives up. If the node still knows some of the
+/// sent versions, `Unresolved` is returned instead of an outcome.
+pub async fn track_transaction<Resubmission: Future<Output = ContractResult<TxHash>>>(
+    node_rpc_url: &str,
+    policy: ConfirmationPolicy,
+    tx_hash: TxHash,
+    fee_cap: Option<FeeCap>,
+    mut resubmit: impl FnMut(TxOverrides) -> Resubmission,
+    mut on_resubmitted: impl FnMut(TxHash),
+) -> Result<TxOutcome, Unresolved> {
+    let provider = match create_simple_provider(node_rpc_url).await {
+        Ok(provider) => provider,
+        Err(err) => {
+            return Err(Unresolved {
+                reason: format!("Couldn't connect to the node: {err}"),
+                sent: vec![tx_hash],
+            })
+        }
+    };
+
+    let mut sent = vec![tx_hash];
+    let mut sent_at = Instant::now();
+    let mut resubmissions = 0;
+    // Sender and the nonce and fees of the last sent version, known once the node reports the
+    // original transaction.
+    let mut last_sent = None;
+    let mut cap_reached = false;
+
+    loop {
+        match status(&provider, &sent, policy.confirmations).await {
+            Ok(Status::Final(outcome)) => return Ok(outcome),
+            Ok(Status::Pending) => {}
+            Ok(Status::Mined) => {
+                sleep(POLLING_INTERVAL).await;
+                continue;
+            }
+            Err(err) => warn!("Failed to check the status of {tx_hash}: {err}"),
+        }
+
+        if last_sent.is_none() {
+            last_sent = sent_transaction(&provider, tx_hash)
+                .await
+                .unwrap_or_else(|err| {
+                    warn!("Failed to fetch transaction {tx_hash}: {err}");
+                    None
+                });
+        }
+
+        if sent_at.elapsed() >= policy.resubmission_timeout {
+            let Some((sender, previous)) = last_sent else {
+                return Ok(TxOutcome::NotMined(
+                    "Transaction is unknown to the node".into(),
+                ));
+            };
+            if nonce_used_elsewhere(&provider, sender, previous.nonce, &sent).await {
+                return Ok(TxOutcome::NotMined(
+                    "Nonce has been used by another transaction".into(),
+                ));
+            }
+            if resubmissions >= policy.max_resubmissions {
+                let reason = format!("Not mined after {resubmissions} resubmissions");
+                return match any_known(&provider, &sent).await {
+                    true => Err(Unresolved { reason, sent }),
+                    false => Ok(TxOutcome::NotMined(format!(
+                        "{reason}, dropped by the node"
+                    ))),
+                };
+            }
+            if cap_reached {
+                // Waiting at the cap counts as a resubmission, so that tracking ends eventually.
+                resubmissions += 1;
+                sent_at = Instant::now();
+                sleep(POLLING_INTERVAL).await;
+                continue;
+            }
+
+            match bump(previous, current_gas_price(&provider).await, fee_cap) {
+                Some(overrides) => {
+                    resubmissions += 1;
+                    last_sent = Some((sender, overrides));
+                    match resubmit(overrides).await {
+                        Ok(new_tx_hash) => {
+                            info!("Resubmitted {tx_hash} as {new_tx_hash} with {overrides:?}");
+                            sent.push(new_tx_hash);
+                            on_resubmitted(new_tx_hash);
+                        }
+                        // Most probably one of the previous versions has just been mined - it will
+                        // be noticed in the next iteration.
+                        Err(err) => warn!("Failed to resubmit {tx_hash}: {err}"),
+                    }
+                }
+                None => {
+                    info!("Fees of {tx_hash} reached the cap - waiting for the sent versions");
+                    cap_reached = true;
+                }
+            }
+            sent_at = Instant::now();
+        }
+
+        sleep(POLLING_INTERVAL).await;
+    }
+}
+
+/// Wait (without resubmitting) until the `sent` versions of an unresolved transaction are settled:
+/// one of them is final, `nonce` of `sender` is used by another transaction or the node drops all
+/// of them.
+pub async fn await_settlement(
+    node_rpc_url: &str,
+    confirmations: u64,
+    sender: Address,
+    nonce: u64,
+    sent: Vec<TxHash>,
+) -> TxOutcome {
+    loop {
+        match settlement(node_rpc_url, confirmations, sender, nonce, &sent).await {
+            Ok(Some(outcome)) => return outcome,
+            Ok(None) => {}
+            Err(err) => warn!("Failed to check the settlement of {sent:?}: {err}"),
+        }
+        sleep(SETTLEMENT_POLLING_INTERVAL).await;
+    }
+}
+
+async fn settlement(
+    node_rpc_url: &str,
+    confirmations: u64,
+    sender: Address,
+    nonce: u64,
+    sent: &[TxHash],
+) -> Result<Option<TxOutcome>> {
+    let provider = create_simple_provider(node_rpc_url).await?;
+    match status(&provider, sent, confirmations).await? {
+        Status::Final(outcome) => Ok(Some(outcome)),
+        Status::Mined => Ok(None),
+        Status::Pending => {
+            if nonce_used_elsewhere(&provider, sender, Some(nonce), sent).await {
+                Ok(Some(TxOutcome::NotMined(
+                    "Nonce has been used by another transaction".into(),
+                )))
+            } else if !any_known(&provider, sent).await {
+                Ok(Some(TxOutcome::NotMined("Dropped by the node".into())))
+            } else {
+                Ok(None)
+            }
+        }
+    }
+}
+
+enum Status {
+    /// None of the sent transactions is mined.
+    Pending,
+    /// One of the sent transactions is mined, but doesn't have enough confirmations yet.
+    Mined,
+    Final(TxOutcome),
+}
+
+async fn status(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    sent: &[TxHash],
+    confirmations: u64,
+) -> Result<Status> {
+    for tx_hash in sent {
+        let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? else {
+            continue;
+        };
+        if !receipt.status() {
+            return Ok(Status::Final(TxOutcome::Reverted(*tx_hash)));
+        }
+        let mined_in = receipt.block_number.unwrap_or_default();
+        let head = provider.get_block_number().await?;
+        return match head + 1 >= mined_in + confirmations {
+            true => Ok(Status::Final(TxOutcome::Confirmed(*tx_hash))),
+            false => Ok(Status::Mined),
+        };
+    }
+    Ok(Status::Pending)
+}
+
+/// Sender of the transaction and the fields needed to replace it.
+async fn sent_transaction(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    tx_hash: TxHash,
+) -> Result<Option<(Address, TxOverrides)>> {
+    let Some(tx) = provider.get_transaction_by_hash(tx_hash).await? else {
+        return Ok(None);
+    };
+    let overrides = match tx.max_priority_fee_per_gas() {
+        Some(max_priority_fee_per_gas) => TxOverrides {
+            nonce: Some(tx.nonce()),
+            max_fee_per_gas: Some(tx.max_fee_per_gas()),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            ..Default::default()
+        },
+        None => TxOverrides {
+            nonce: Some(tx.nonce()),
+            gas_price: tx.gas_price(),
+            ..Default::default()
+        },
+    };
+    Ok(Some((tx.from, overrides)))
+}
+
+/// Whether the sender has already used the nonce, but none of the `sent` transactions is mined
+/// (i.e. the nonce was taken by some other transaction and ours will never be included).
+async fn nonce_used_elsewhere(
+    provider: &impl Provider<BoxTransport, AnyNetwork>,
+    sender: Address,
+    nonce: Option<u64>,
+    sent: &[TxHash],
+) -> bool {
+    let (Some(nonce), Ok(mined_nonce)) = (nonce, provider.get_transaction_count(sender).await)
+    else {
+        return false;
+    };
+    if mined_nonce <= nonce {
+        return false;
+    }
+    // The nonce is used - make sure it is not by one of our transactions mined in the meantime.
+    matches!(status(provider, sent, 1).await, Ok(Status::Pending))
+}
+
+/// Whether the node still knows any of the `sent` transactions. In doubt, it is assumed that it
+/// does.
+async fn any_known(provider: &impl Provider<BoxTransport, AnyNetwork>, sent: &[TxHash]) -> bool {
+    for tx_hash in sent {
+        if !matches!(provider.get_transaction_by_hash(*tx_hash).await, Ok(None)) {
+            return true;
+        }
+    }
+    false
+}
+
+async fn current_gas_price(provider: &impl Provider<BoxTransport, AnyNetwork>) -> u128 {
+    provider.get_gas_price().await.unwrap_or_default()
+}
+
+/// Raise the fees of the previously sent version by `GAS_BUMP_PERCENT`, but never below the
+/// current network gas price nor above `fee_cap`. Returns `None` if the cap leaves no room for
+/// a raise (a replacement with the same fees would be rejected by the nodes anyway).
+fn bump(previous: TxOverrides, gas_price: u128, fee_cap: Option<FeeCap>) -> Option<TxOverrides> {
+    let raise = |fee: u128| fee + fee * GAS_BUMP_PERCENT / 100 + 1;
+    let max_fee = fee_cap.map_or(u128::MAX, |cap| cap.max_fee_per_gas());
+    let max_priority_fee = fee_cap.map_or(u128::MAX, |cap| cap.max_priority_fee_per_gas());
+
+    let bumped = TxOverrides {
+        nonce: previous.nonce,
+        gas_price: previous
+            .gas_price
+            .map(|fee| raise(fee).max(gas_price).min(max_fee)),
+        max_fee_per_gas: previous
+            .max_fee_per_gas
+            .map(|fee| raise(fee).max(gas_price).min(max_fee)),
+        max_priority_fee_per_gas: previous
+            .max_priority_fee_per_gas
+            .map(|fee| raise(fee).min(max_priority_fee).min(max_fee)),
+    };
+
+    let all_raised = [
+        (bumped.gas_price, previous.gas_price),
+        (bumped.max_fee_per_gas, previous.max_fee_per_gas),
+        (
+            bumped.max_priority_fee_per_gas,
+            previous.max_priority_fee_per_gas,
+        ),
+    ]
+    .into_iter()
+    .filter(|(_, previous)| previous.is_some())
+    .all(|(bumped, previous)| bumped > previous);
+    all_raised.then_some(bumped)
+}
+
+#[cfg(test)]
+mod tests {
+    use shielder_contract::TxOverrides;
+
+    use super::bump;
+    use crate::gas_pricing::FeeCap;
+
+    #[test]
+    fn legacy_fees_are_bumped_above_network_price() {
+        let original = TxOverrides {
+            nonce: Some(7),
+            gas_price: Some(100),
+            ..Default::default()
+        };
+
+        let first = bump(original, 0, None).unwrap();
+        assert_eq!(first.nonce, Some(7));
+        assert_eq!(first.gas_price, Some(121));
+        assert_eq!(bump(first, 0, None).unwrap().gas_price, Some(146));
+        assert_eq!(bump(original, 500, None).unwrap().gas_price, Some(500));
+    }
+
+    #[test]
+    fn eip1559_fees_are_bumped() {
+        let original = TxOverrides {
+            nonce: Some(1),
+            max_fee_per_gas: Some(1000),
+            max_priority_fee_per_gas: Some(10),
+            ..Default::default()
+        };
+
+        let bumped = bump(original, 0, None).unwrap();
+        assert_eq!(bumped.max_fee_per_gas, Some(1201));
+        assert_eq!(bumped.max_priority_fee_per_gas, Some(13));
+        assert_eq!(bumped.gas_price, None);
+    }
+
+    #[test]
+    fn bumps_stop_at_fee_cap() {
+        let fee_cap = Some(FeeCap::Eip1559 {
+            max_fee_per_gas: 1300,
+            max_priority_fee_per_gas: 15,
+        });
+        let original = TxOverrides {
+            nonce: Some(1),
+            max_fee_per_gas: Some(1000),
+            max_priority_fee_per_gas: Some(10),
+            ..Default::default()
+        };
+
+        let first = bump(original, 0, fee_cap).unwrap();
+        assert_eq!(first.max_fee_per_gas, Some(1201));
+        assert_eq!(first.max_priority_fee_per_gas, Some(13));
+
+        let second = bump(first, 2000, fee_cap).unwrap();
+        assert_eq!(second.max_fee_per_gas, Some(1300));
+        assert_eq!(second.max_priority_fee_per_gas, Some(15));
+
+        assert_eq!(bump(second, 0, fee_cap), None);
+    }
+}
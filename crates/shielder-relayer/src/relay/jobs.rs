@@ -0,0 +1,386 @@
+use std::{
+    str::FromStr,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{anyhow, Result};
+use parking_lot::Mutex;
+use rusqlite::{Connection, OptionalExtension, Row};
+use shielder_contract::alloy_primitives::{Address, TxHash, U256};
+use shielder_relayer::{RelayCalldata, RelayJobInfo, RelayJobStatus};
+
+use crate::gas_pricing::FeeCap;
+
+pub type JobId = u64;
+
+/// A job that was not finished before the relayer stopped.
+pub struct UnfinishedJob {
+    pub id: JobId,
+    pub status: RelayJobStatus,
+    pub calldata: RelayCalldata,
+    pub relayer_fee: U256,
+    pub fee_cap: Option<FeeCap>,
+    pub tx_hash: Option<TxHash>,
+    /// Signer and nonce of the submitted transaction. Needed for resubmitting it.
+    pub submitted_by: Option<(Address, u64)>,
+}
+
+/// Persistent storage of relay jobs of a single chain, backed by SQLite.
+///
+/// Several chains may share the same database - every job is tagged with its chain id, and only
+/// the jobs of `chain_id` are visible.
+#[derive(Clone)]
+pub struct JobStore {
+    connection: Arc<Mutex<Connection>>,
+    chain_id: u64,
+}
+
+impl JobStore {
+    pub fn open(path: &str, chain_id: u64) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS relay_jobs (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                chain_id INTEGER,
+                status TEXT NOT NULL,
+                calldata TEXT NOT NULL,
+                relayer_fee TEXT NOT NULL,
+                fee_cap TEXT,
+                tx_hash TEXT,
+                signer TEXT,
+                nonce INTEGER,
+                failure_reason TEXT,
+                created_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            )",
+            (),
+        )?;
+        // Stores created before fee caps, resubmissions and multiple chains were introduced.
+        for (column, column_type) in [
+            ("fee_cap", "TEXT"),
+            ("signer", "TEXT"),
+            ("nonce", "INTEGER"),
+            ("chain_id", "INTEGER"),
+        ] {
+            let has_column: bool = connection.query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('relay_jobs') WHERE name = ?1",
+                [column],
+                |row| row.get(0),
+            )?;
+            if !has_column {
+                connection.execute(
+                    &format!("ALTER TABLE relay_jobs ADD COLUMN {column} {column_type}"),
+                    (),
+                )?;
+            }
+        }
+        // Jobs from before chain ids were recorded come from a single-chain relayer - they belong
+        // to the (main) chain that opens the store first.
+        connection.execute(
+            "UPDATE relay_jobs SET chain_id = ?1 WHERE chain_id IS NULL",
+            [chain_id],
+        )?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            chain_id,
+        })
+    }
+
+    /// Persist a new job (in the `Queued` state) and return its id.
+    pub fn create(
+        &self,
+        calldata: &RelayCalldata,
+        relayer_fee: U256,
+        fee_cap: Option<FeeCap>,
+    ) -> Result<JobId> {
+        let connection = self.connection.lock();
+        connection.execute(
+            "INSERT INTO relay_jobs
+                 (chain_id, status, calldata, relayer_fee, fee_cap, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6)",
+            (
+                self.chain_id,
+                status_to_str(RelayJobStatus::Queued),
+                serde_json::to_string(calldata)?,
+                relayer_fee.to_string(),
+                fee_cap
+                    .map(|fee_cap| serde_json::to_string(&fee_cap))
+                    .transpose()?,
+                now(),
+            ),
+        )?;
+        Ok(connection.last_insert_rowid() as JobId)
+    }
+
+    pub fn set_status(&self, id: JobId, status: RelayJobStatus) -> Result<()> {
+        self.update(
+            id,
+            "UPDATE relay_jobs SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            (id, status_to_str(status), now()),
+        )
+    }
+
+    /// Record that the job has been submitted in `tx_hash` by `signer` with `nonce` (the same for
+    /// all the replacements of the transaction).
+    pub fn set_submitted(
+        &self,
+        id: JobId,
+        tx_hash: TxHash,
+        signer: Address,
+        nonce: u64,
+    ) -> Result<()> {
+        self.update(
+            id,
+            "UPDATE relay_jobs SET status = ?2, tx_hash = ?3, signer = ?4, nonce = ?5, updated_at = ?6
+             WHERE id = ?1",
+            (
+                id,
+                status_to_str(RelayJobStatus::Submitted),
+                tx_hash.to_string(),
+                signer.to_string(),
+                nonce,
+                now(),
+            ),
+        )
+    }
+
+    pub fn set_mined(&self, id: JobId, tx_hash: TxHash) -> Result<()> {
+        self.update(
+            id,
+            "UPDATE relay_jobs SET status = ?2, tx_hash = ?3, updated_at = ?4 WHERE id = ?1",
+            (
+                id,
+                status_to_str(RelayJobStatus::Mined),
+                tx_hash.to_string(),
+                now(),
+            ),
+        )
+    }
+
+    pub fn set_failed(&self, id: JobId, reason: &str) -> Result<()> {
+        self.update(
+            id,
+            "UPDATE relay_jobs SET status = ?2, failure_reason = ?3, updated_at = ?4 WHERE id = ?1",
+            (id, status_to_str(RelayJobStatus::Failed), reason, now()),
+        )
+    }
+
+    pub fn get(&self, id: JobId) -> Result<Option<RelayJobInfo>> {
+        self.connection
+            .lock()
+            .query_row(
+                "SELECT id, status, tx_hash, failure_reason FROM relay_jobs
+                 WHERE id = ?1 AND chain_id = ?2",
+                [id, self.chain_id],
+                |row| {
+                    Ok(RelayJobInfo {
+                        job_id: row.get(0)?,
+                        status: status_from_row(row, 1)?,
+                        tx_hash: tx_hash_from_row(row, 2)?,
+                        failure_reason: row.get(3)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(Into::into)
+    }
+
+    /// All the jobs that are neither mined nor failed, oldest first.
+    pub fn unfinished(&self) -> Result<Vec<UnfinishedJob>> {
+        let connection = self.connection.lock();
+        let mut statement = connection.prepare(
+            "SELECT id, status, calldata, relayer_fee, fee_cap, tx_hash, signer, nonce
+             FROM relay_jobs WHERE chain_id = ?1 AND status NOT IN (?2, ?3) ORDER BY id",
+        )?;
+        let rows = statement.query_map(
+            (
+                self.chain_id,
+                status_to_str(RelayJobStatus::Mined),
+                status_to_str(RelayJobStatus::Failed),
+            ),
+            |row| {
+                Ok((
+                    row.get::<_, JobId>(0)?,
+                    status_from_row(row, 1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    tx_hash_from_row(row, 5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    row.get::<_, Option<u64>>(7)?,
+                ))
+            },
+        )?;
+
+        rows.map(|row| {
+            let (id, status, calldata, relayer_fee, fee_cap, tx_hash, signer, nonce) = row?;
+            let submitted_by = match (signer, nonce) {
+                (Some(signer), Some(nonce)) => Some((Address::from_str(&signer)?, nonce)),
+                _ => None,
+            };
+            Ok(UnfinishedJob {
+                id,
+                status,
+                calldata: serde_json::from_str(&calldata)?,
+                relayer_fee: U256::from_str(&relayer_fee)?,
+                fee_cap: fee_cap
+                    .map(|fee_cap| serde_json::from_str(&fee_cap))
+                    .transpose()?,
+                tx_hash,
+                submitted_by,
+            })
+        })
+        .collect()
+    }
+
+    fn update(&self, id: JobId, sql: &str, params: impl rusqlite::Params) -> Result<()> {
+        let connection = self.connection.lock();
+        let exists: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM relay_jobs WHERE id = ?1 AND chain_id = ?2",
+            [id, self.chain_id],
+            |row| row.get(0),
+        )?;
+        if !exists {
+            return Err(anyhow!("Relay job {id} does not exist"));
+        }
+        connection.execute(sql, params)?;
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+fn status_to_str(status: RelayJobStatus) -> &'static str {
+    match status {
+        RelayJobStatus::Queued => "queued",
+        RelayJobStatus::DryRun => "dry_run",
+        RelayJobStatus::Submitted => "submitted",
+        RelayJobStatus::Mined => "mined",
+        RelayJobStatus::Failed => "failed",
+    }
+}
+
+fn status_from_row(row: &Row, index: usize) -> rusqlite::Result<RelayJobStatus> {
+    match row.get::<_, String>(index)?.as_str() {
+        "queued" => Ok(RelayJobStatus::Queued),
+        "dry_run" => Ok(RelayJobStatus::DryRun),
+        "submitted" => Ok(RelayJobStatus::Submitted),
+        "mined" => Ok(RelayJobStatus::Mined),
+        "failed" => Ok(RelayJobStatus::Failed),
+        other => Err(rusqlite::Error::InvalidColumnType(
+            index,
+            format!("unknown status `{other}`"),
+            rusqlite::types::Type::Text,
+        )),
+    }
+}
+
+fn tx_hash_from_row(row: &Row, index: usize) -> rusqlite::Result<Option<TxHash>> {
+    row.get::<_, Option<String>>(index)?
+        .map(|tx_hash| {
+            TxHash::from_str(&tx_hash).map_err(|err| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    index,
+                    rusqlite::types::Type::Text,
+                    Box::new(err),
+                )
+            })
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use shielder_contract::alloy_primitives::{Address, TxHash, U256};
+    use shielder_relayer::{RelayCalldata, RelayJobStatus};
+
+    use super::JobStore;
+    use crate::gas_pricing::FeeCap;
+
+    const CHAIN_ID: u64 = 1;
+
+    #[test]
+    fn job_lifecycle_is_persisted() {
+        let store = JobStore::open(":memory:", CHAIN_ID).unwrap();
+        let calldata = RelayCalldata {
+            amount: U256::from(41),
+            ..Default::default()
+        };
+
+        let fee_cap = FeeCap::Eip1559 {
+            max_fee_per_gas: 1_000,
+            max_priority_fee_per_gas: 10,
+        };
+
+        let first = store
+            .create(&calldata, U256::from(7), Some(fee_cap))
+            .unwrap();
+        let second = store.create(&calldata, U256::from(8), None).unwrap();
+        let third = store.create(&calldata, U256::from(9), None).unwrap();
+
+        store.set_status(first, RelayJobStatus::DryRun).unwrap();
+        store
+            .set_submitted(second, TxHash::repeat_byte(1), Address::repeat_byte(2), 5)
+            .unwrap();
+        store.set_failed(third, "Dry run failed").unwrap();
+
+        let info = store.get(second).unwrap().unwrap();
+        assert_eq!(info.status, RelayJobStatus::Submitted);
+        assert_eq!(info.tx_hash, Some(TxHash::repeat_byte(1)));
+        let info = store.get(third).unwrap().unwrap();
+        assert_eq!(info.status, RelayJobStatus::Failed);
+        assert_eq!(info.failure_reason.as_deref(), Some("Dry run failed"));
+        assert!(store.get(third + 1).unwrap().is_none());
+
+        let unfinished = store.unfinished().unwrap();
+        assert_eq!(
+            unfinished.iter().map(|job| job.id).collect::<Vec<_>>(),
+            vec![first, second]
+        );
+        assert_eq!(unfinished[0].status, RelayJobStatus::DryRun);
+        assert_eq!(unfinished[0].calldata.amount, U256::from(41));
+        assert_eq!(unfinished[0].fee_cap, Some(fee_cap));
+        assert_eq!(unfinished[0].submitted_by, None);
+        assert_eq!(unfinished[1].relayer_fee, U256::from(8));
+        assert_eq!(unfinished[1].fee_cap, None);
+        assert_eq!(unfinished[1].tx_hash, Some(TxHash::repeat_byte(1)));
+        assert_eq!(
+            unfinished[1].submitted_by,
+            Some((Address::repeat_byte(2), 5))
+        );
+    }
+
+    #[test]
+    fn chains_sharing_a_store_see_only_their_jobs() {
+        // Shared in-memory database, so that both stores use the same one.
+        let path = "file:shared_job_store?mode=memory&cache=shared";
+        let first_chain = JobStore::open(path, 1).unwrap();
+        let second_chain = JobStore::open(path, 2).unwrap();
+        let calldata = RelayCalldata::default();
+
+        let first_job = first_chain.create(&calldata, U256::from(1), None).unwrap();
+        let second_job = second_chain.create(&calldata, U256::from(2), None).unwrap();
+
+        assert!(first_chain.get(first_job).unwrap().is_some());
+        assert!(first_chain.get(second_job).unwrap().is_none());
+        assert!(second_chain.get(first_job).unwrap().is_none());
+        assert!(second_chain.set_failed(first_job, "Wrong chain").is_err());
+
+        let unfinished = |store: &JobStore| {
+            store
+                .unfinished()
+                .unwrap()
+                .into_iter()
+                .map(|job| job.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(unfinished(&first_chain), vec![first_job]);
+        assert_eq!(unfinished(&second_chain), vec![second_job]);
+    }
+}
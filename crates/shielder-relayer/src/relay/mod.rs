@@ -1,30 +1,51 @@
+use anyhow::anyhow;
 use axum::{
-    extract::State,
+    extract::{Path, State},
     response::{IntoResponse, Response},
     Json,
 };
 use shielder_account::{call_data::WithdrawCall, Token};
-use shielder_contract::alloy_primitives::{Address, U256};
+use shielder_contract::{
+    alloy_primitives::{Address, TxHash, U256},
+    providers::create_simple_provider,
+    state::{is_nullifier_spent, merkle_root_exists, protocol_withdraw_fee_bps},
+    ShielderContractError,
+};
 use shielder_relayer::{
-    compute_fee,
-    server::{bad_request, server_error, success_response},
-    RelayCalldata, RelayQuery, RelayResponse, SimpleServiceResponse,
+    server::{bad_request, not_found, server_error, success_response, temporary_failure},
+    RelayCalldata, RelayJobInfo, RelayJobResponse, RelayJobStatus, RelayQuery, RelayResponse,
+    SimpleServiceResponse,
 };
 use shielder_setup::version::{contract_version, ContractVersion};
-use tracing::{debug, error};
+use time::OffsetDateTime;
+use tokio::sync::oneshot::Receiver as OneshotReceiver;
+use tracing::{debug, error, info, warn};
 
-pub use crate::relay::taskmaster::Taskmaster;
+pub use crate::relay::{
+    confirmation::ConfirmationPolicy,
+    jobs::JobStore,
+    taskmaster::{Taskmaster, WorkerConnection},
+};
 use crate::{
+    gas_pricing::{network_fees, FeeCap},
     metrics::WITHDRAW_FAILURE,
-    quote_cache::CachedQuote,
-    relay::{request_trace::RequestTrace, taskmaster::TaskResult},
+    quote_signing::Quote,
+    relay::{
+        jobs::{JobId, UnfinishedJob},
+        request_trace::RequestTrace,
+        taskmaster::TaskResult,
+    },
     AppState,
 };
 
+mod confirmation;
+mod jobs;
 mod monitoring;
 mod request_trace;
 mod taskmaster;
 
+type TaskReport = OneshotReceiver<(RequestTrace, TaskResult)>;
+
 const TASK_QUEUE_SIZE: usize = 1024;
 const OPTIMISTIC_DRY_RUN_THRESHOLD: u32 = 32;
 
@@ -54,47 +75,267 @@ pub async fn relay(
     }
 }
 
+/// Same as `/relay`, but returns as soon as the request is accepted. The outcome can be then
+/// followed with `/relay/{id}`.
+#[utoipa::path(
+    post,
+    path = "/relay/async",
+    request_body(content = RelayQuery, description = "The relay request"),
+    responses(
+        (status = 200, description = "Relay job accepted", body = RelayJobResponse),
+        (status = BAD_REQUEST, description = "Failed to relay withdrawal. Ensure your query, including proof, is correct.", body = SimpleServiceResponse),
+        (status = SERVICE_UNAVAILABLE, description = "Failed to obtain current chain and price info. Try again later.", body = SimpleServiceResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Server encountered unexpected error. Try again later.", body = SimpleServiceResponse),
+    )
+)]
+pub async fn relay_async(
+    State(app_state): State<AppState>,
+    Json(query): Json<RelayQuery>,
+) -> impl IntoResponse {
+    debug!("Async relay request received: {query:?}");
+    match start_job(&app_state, query).await {
+        Ok((job_id, report)) => {
+            tokio::spawn(finish_job(report));
+            success_response(RelayJobResponse { job_id })
+        }
+        Err(err) => {
+            error!("Relay request failed: {err:?}");
+            err
+        }
+    }
+}
+
+/// Get the current status of a relay job.
+#[utoipa::path(
+    get,
+    path = "/relay/{id}",
+    params(("id" = u64, Path, description = "Relay job identifier")),
+    responses(
+        (status = 200, description = "Relay job found", body = RelayJobInfo),
+        (status = NOT_FOUND, description = "There is no relay job with such identifier", body = SimpleServiceResponse),
+        (status = INTERNAL_SERVER_ERROR, description = "Server encountered unexpected error. Try again later.", body = SimpleServiceResponse),
+    )
+)]
+pub async fn relay_status(
+    State(app_state): State<AppState>,
+    Path(job_id): Path<JobId>,
+) -> impl IntoResponse {
+    match app_state.job_store.get(job_id) {
+        Ok(Some(job_info)) => success_response(job_info),
+        Ok(None) => not_found(&format!("Relay job {job_id} not found")),
+        Err(err) => {
+            error!("Failed to read relay job {job_id}: {err:?}");
+            server_error("Failed to read relay job")
+        }
+    }
+}
+
+/// Bring back the jobs that were not finished before the last shutdown. Jobs that have not been
+/// submitted yet are passed to the workers again (with the fee that was agreed on originally) and
+/// submitted transactions are followed (and resubmitted if needed) until they are final.
+pub async fn resume_unfinished_jobs(app_state: &AppState) -> anyhow::Result<()> {
+    for job in app_state.job_store.unfinished()? {
+        match (job.status, job.tx_hash, job.submitted_by) {
+            (RelayJobStatus::Submitted, Some(tx_hash), Some(submitted_by)) => {
+                info!("Following relay job {} submitted in {tx_hash}", job.id);
+                resume_submission(app_state, job, tx_hash, submitted_by).await;
+            }
+            (RelayJobStatus::Submitted, _, _) => {
+                warn!("Relay job {} has no submission details to resume", job.id);
+                app_state
+                    .job_store
+                    .set_failed(job.id, "Submission details lost during restart")?;
+            }
+            _ => {
+                info!("Resuming relay job {}", job.id);
+                let request_trace = RequestTrace::new(&job.calldata);
+                let report = enqueue_job(
+                    app_state,
+                    job.id,
+                    job.calldata,
+                    job.relayer_fee,
+                    job.fee_cap,
+                    request_trace,
+                )
+                .await
+                .map_err(|_| anyhow!("Failed to resume relay job {}", job.id))?;
+                tokio::spawn(finish_job(report));
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn resume_submission(
+    app_state: &AppState,
+    job: UnfinishedJob,
+    tx_hash: TxHash,
+    submitted_by: (Address, u64),
+) {
+    let request_trace = RequestTrace::new(&job.calldata);
+    let withdraw_call = create_call(
+        job.calldata,
+        app_state.signer_info.fee_destination_address,
+        job.relayer_fee,
+    );
+    let resumed = app_state
+        .taskmaster
+        .resume_submission(
+            job.id,
+            withdraw_call,
+            job.fee_cap,
+            tx_hash,
+            submitted_by,
+            request_trace,
+        )
+        .await;
+    match resumed {
+        Ok(report) => {
+            tokio::spawn(finish_job(report));
+        }
+        Err(err) => {
+            warn!("Couldn't resume relay job {}: {err}", job.id);
+            if let Err(err) = app_state
+                .job_store
+                .set_failed(job.id, &format!("Couldn't resume: {err}"))
+            {
+                error!("Failed to update relay job {}: {err:?}", job.id);
+            }
+        }
+    }
+}
+
 async fn _relay(app_state: AppState, query: RelayQuery) -> Result<RelayResponse, Response> {
-    let mut request_trace = RequestTrace::new(&query);
+    let (job_id, report) = start_job(&app_state, query).await?;
+    let tx_hash = finish_job(report).await?;
+    Ok(RelayResponse { tx_hash, job_id })
+}
+
+/// Validate the request, persist it as a new job and pass it to the workers.
+async fn start_job(
+    app_state: &AppState,
+    query: RelayQuery,
+) -> Result<(JobId, TaskReport), Response> {
+    if app_state.runtime_config.is_paused() {
+        return Err(temporary_failure("Relaying is paused. Try again later."));
+    }
+    let mut request_trace = RequestTrace::new(&query.calldata);
 
     check_expected_version(&query.calldata, &mut request_trace)?;
-    check_pocket_money(&app_state, &query, &mut request_trace)?;
-    check_quote_validity(&app_state, &query, &mut request_trace).await?;
-
-    let fee_details = compute_fee(
-        query.quote.gas_price,
-        app_state.relay_gas,
-        query.calldata.pocket_money,
-        app_state.service_fee_percent,
-        query.quote.native_token_unit_price,
-        query.quote.fee_token_unit_price,
+    check_pocket_money(app_state, &query, &mut request_trace)?;
+    check_quote_validity(app_state, &query, &mut request_trace)?;
+    let fee_cap = check_network_fee(app_state, &query, &mut request_trace).await?;
+    check_contract_state(app_state, &query.calldata, &mut request_trace).await?;
+
+    if app_state
+        .runtime_config
+        .token_info(query.calldata.fee_token)
+        .is_none()
+    {
+        request_trace.record_incorrect_token_fee(query.calldata.fee_token.address());
+        return Err(bad_request("Fee token is not supported"));
+    }
+    // The fee comes from the (signed) quote, so that fee policy changes made in the meantime don't
+    // affect the quoted relays.
+    let relayer_fee = query.quote.total_fee;
+
+    check_proof(app_state, &query.calldata, relayer_fee, &mut request_trace).await?;
+
+    let job_id = app_state
+        .job_store
+        .create(&query.calldata, relayer_fee, Some(fee_cap))
+        .map_err(|err| server_error(&format!("Failed to store relay job: {err:?}")))?;
+
+    let report = enqueue_job(
+        app_state,
+        job_id,
+        query.calldata,
+        relayer_fee,
+        Some(fee_cap),
+        request_trace,
     )
-    .map_err(server_error)?;
+    .await?;
+    Ok((job_id, report))
+}
 
+async fn enqueue_job(
+    app_state: &AppState,
+    job_id: JobId,
+    calldata: RelayCalldata,
+    relayer_fee: U256,
+    fee_cap: Option<FeeCap>,
+    request_trace: RequestTrace,
+) -> Result<TaskReport, Response> {
     let withdraw_call = create_call(
-        query.calldata,
+        calldata,
         app_state.signer_info.fee_destination_address,
-        fee_details.total_cost_fee_token,
+        relayer_fee,
     );
-    let rx = app_state
+    app_state.relay_gas_estimator.sample(&withdraw_call);
+    match app_state
         .taskmaster
-        .register_new_task(withdraw_call, request_trace)
+        .register_new_task(job_id, withdraw_call, fee_cap, request_trace)
         .await
-        .map_err(|err| server_error(&format!("Failed to register new task: {err:?}")))?;
+    {
+        Ok(report) => Ok(report),
+        Err(err) => {
+            if let Err(err) = app_state.job_store.set_failed(job_id, "Failed to queue") {
+                error!("Failed to update relay job {job_id}: {err:?}");
+            }
+            Err(server_error(&format!(
+                "Failed to register new task: {err:?}"
+            )))
+        }
+    }
+}
 
-    match rx.await {
+/// `prefix`, followed by the revert reason if the contract reported one.
+fn failure_message(prefix: &str, err: &ShielderContractError) -> String {
+    match err {
+        ShielderContractError::Reverted(revert) => format!("{prefix}: {revert}"),
+        _ => prefix.to_string(),
+    }
+}
+
+/// Wait until the worker gets the transaction confirmed (or fails).
+async fn finish_job(report: TaskReport) -> Result<TxHash, Response> {
+    match report.await {
         Ok((mut request_trace, task_result)) => match task_result {
             TaskResult::Ok(tx_hash) => {
                 request_trace.record_success(tx_hash);
-                Ok(RelayResponse { tx_hash })
+                Ok(tx_hash)
             }
             TaskResult::DryRunFailed(err) => {
+                let message = failure_message("Dry run failed", &err);
                 request_trace.record_dry_run_failure(err);
-                Err(bad_request("Dry run failed"))
+                Err(bad_request(&message))
             }
             TaskResult::RelayFailed(err) => {
+                let message = failure_message("Relay failed", &err);
                 request_trace.record_failure(err);
-                Err(bad_request("Relay failed"))
+                Err(bad_request(&message))
+            }
+            TaskResult::Reverted(tx_hash) => {
+                request_trace.record_revert(tx_hash);
+                Err(bad_request(&format!(
+                    "Relay transaction {tx_hash} reverted"
+                )))
+            }
+            TaskResult::NotMined(reason) => {
+                request_trace.record_not_mined(&reason);
+                Err(server_error("Relay transaction was not mined"))
+            }
+            TaskResult::Unresolved(reason) => {
+                request_trace.record_unresolved(&reason);
+                Err(temporary_failure(
+                    "Relay transaction was not mined yet, but it still can be",
+                ))
+            }
+            TaskResult::Drained => {
+                request_trace.record_drained();
+                Err(temporary_failure(
+                    "Relay request was dropped by the relayer operator",
+                ))
             }
         },
         Err(err) => {
@@ -142,24 +383,137 @@ fn check_expected_version(
     Ok(())
 }
 
-async fn check_quote_validity(
+fn check_quote_validity(
     app_state: &AppState,
     query: &RelayQuery,
     request_trace: &mut RequestTrace,
 ) -> Result<(), Response> {
-    let cached_quote = CachedQuote {
+    let quote = Quote {
         fee_token: query.calldata.fee_token,
         gas_price: query.quote.gas_price,
         native_token_unit_price: query.quote.native_token_unit_price,
         fee_token_unit_price: query.quote.fee_token_unit_price,
+        relay_gas: query.quote.relay_gas,
+        pocket_money: query.calldata.pocket_money,
+        total_fee: query.quote.total_fee,
     };
-    match app_state.quote_cache.is_quote_valid(&cached_quote).await {
-        true => Ok(()),
-        false => {
-            request_trace.record_quote_invalidity();
-            Err(bad_request("Invalid quote (probably expired)"))
+    app_state
+        .quote_signer
+        .verify(&quote, &query.quote.signature, OffsetDateTime::now_utc())
+        .map_err(|err| {
+            request_trace.record_quote_invalidity(err);
+            bad_request(&format!("Invalid quote: {err}"))
+        })
+}
+
+/// Check that the relay can still be mined at the gas price that the user paid for (the base fee
+/// might have grown beyond the quoted headroom) and bound the transaction fees by that price.
+async fn check_network_fee(
+    app_state: &AppState,
+    query: &RelayQuery,
+    request_trace: &mut RequestTrace,
+) -> Result<FeeCap, Response> {
+    let fees = async {
+        let provider = create_simple_provider(&app_state.node_rpc_url).await?;
+        network_fees(&provider, &app_state.gas_pricing).await
+    }
+    .await
+    .map_err(|err| {
+        error!("Failed to get network fees: {err}");
+        temporary_failure("Failed to get network fees. Try again later.")
+    })?;
+
+    let quoted_gas_price = u128::try_from(query.quote.gas_price)
+        .map_err(|_| bad_request("Quoted gas price is out of range"))?;
+    let required_gas_price = fees.required_gas_price();
+    if required_gas_price > quoted_gas_price {
+        request_trace.record_network_fee_too_high(quoted_gas_price, required_gas_price);
+        return Err(bad_request(
+            "Network fees have grown above the quoted gas price. Request a new quote.",
+        ));
+    }
+    Ok(fees.fee_cap(quoted_gas_price))
+}
+
+/// Cheap check (just a few reads) of whether the withdrawal can succeed at all, so that requests
+/// which would surely be rejected by the contract don't occupy workers with dry-runs. If the node
+/// cannot be queried, the request is let through - the dry-run will decide.
+async fn check_contract_state(
+    app_state: &AppState,
+    calldata: &RelayCalldata,
+    request_trace: &mut RequestTrace,
+) -> Result<(), Response> {
+    let provider = match create_simple_provider(&app_state.node_rpc_url).await {
+        Ok(provider) => provider,
+        Err(err) => {
+            warn!("Skipping contract state check - failed to create provider: {err}");
+            return Ok(());
+        }
+    };
+    let contract_address = app_state.shielder_contract_address;
+
+    match is_nullifier_spent(&provider, contract_address, calldata.nullifier_hash).await {
+        Ok(false) => {}
+        Ok(true) => {
+            request_trace.record_spent_nullifier(calldata.nullifier_hash);
+            return Err(bad_request("Nullifier hash has already been used"));
         }
+        Err(err) => warn!("Skipping nullifier check: {err}"),
     }
+    match merkle_root_exists(&provider, contract_address, calldata.merkle_root).await {
+        Ok(Some(true)) => {}
+        Ok(Some(false)) => {
+            request_trace.record_unknown_merkle_root(calldata.merkle_root);
+            return Err(bad_request("Merkle root is unknown"));
+        }
+        Ok(None) => warn!("Skipping merkle root check: unexpected contract storage layout"),
+        Err(err) => warn!("Skipping merkle root check: {err}"),
+    }
+    Ok(())
+}
+
+/// Verify the proof locally (if the relayer is configured with the circuit artifacts), so that
+/// withdrawals that would fail on-chain verification never reach a worker. As with the contract
+/// state check, if the protocol fee cannot be read, the request is let through.
+async fn check_proof(
+    app_state: &AppState,
+    calldata: &RelayCalldata,
+    relayer_fee: U256,
+    request_trace: &mut RequestTrace,
+) -> Result<(), Response> {
+    let Some(verifier) = app_state.withdraw_verifier.clone() else {
+        return Ok(());
+    };
+
+    let provider = match create_simple_provider(&app_state.node_rpc_url).await {
+        Ok(provider) => provider,
+        Err(err) => {
+            warn!("Skipping proof verification - failed to create provider: {err}");
+            return Ok(());
+        }
+    };
+    let protocol_fee_bps =
+        match protocol_withdraw_fee_bps(&provider, app_state.shielder_contract_address).await {
+            Ok(bps) => bps,
+            Err(err) => {
+                warn!("Skipping proof verification - failed to read protocol fee: {err}");
+                return Ok(());
+            }
+        };
+
+    let relayer_address = app_state.signer_info.fee_destination_address;
+    let calldata = calldata.clone();
+    // Verification takes a few milliseconds of CPU, so keep it off the async workers.
+    let result = tokio::task::spawn_blocking(move || {
+        verifier.verify(&calldata, relayer_address, relayer_fee, protocol_fee_bps)
+    })
+    .await
+    .map_err(|err| server_error(&format!("Proof verification task failed: {err}")))?;
+
+    result.map_err(|err| {
+        request_trace.record_invalid_proof(&err);
+        bad_request(&format!("Invalid proof: {err}"))
+    })
 }
 
 fn check_pocket_money(
@@ -174,8 +528,9 @@ fn check_pocket_money(
             "Pocket money is not supported for native token withdrawals.",
         ));
     }
-    if app_state.max_pocket_money < pocket_money {
-        request_trace.record_pocket_money_too_high(app_state.max_pocket_money, pocket_money);
+    let max_pocket_money = app_state.runtime_config.max_pocket_money();
+    if max_pocket_money < pocket_money {
+        request_trace.record_pocket_money_too_high(max_pocket_money, pocket_money);
         return Err(bad_request("Pocket money too high."));
     }
     Ok(())
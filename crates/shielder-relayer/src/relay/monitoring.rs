@@ -5,7 +5,9 @@ use std::sync::{
 
 use tracing::warn;
 
-use crate::relay::OPTIMISTIC_DRY_RUN_THRESHOLD;
+use crate::{
+    config::DryRunning, relay::OPTIMISTIC_DRY_RUN_THRESHOLD, runtime_config::RuntimeConfig,
+};
 
 pub trait RelayingMonitoring: Clone + Send {
     fn notice_relay_success(&mut self) {}
@@ -18,13 +20,6 @@ pub trait DryRunSwitch: Clone + Send {
     }
 }
 
-#[derive(Copy, Clone)]
-pub struct ObligatoryDryRun;
-
-impl RelayingMonitoring for ObligatoryDryRun {}
-
-impl DryRunSwitch for ObligatoryDryRun {}
-
 #[derive(Clone)]
 pub struct OptionalDryRun {
     success_counter: Arc<AtomicU32>,
@@ -54,3 +49,39 @@ impl DryRunSwitch for OptionalDryRun {
         self.success_counter.load(Ordering::Relaxed) < OPTIMISTIC_DRY_RUN_THRESHOLD
     }
 }
+
+/// Dry-running according to the current `dry_running` runtime setting. The optimistic state is
+/// followed also when dry-running is obligatory, so that switching modes takes effect at once.
+#[derive(Clone)]
+pub struct ConfiguredDryRun {
+    runtime_config: RuntimeConfig,
+    optional: OptionalDryRun,
+}
+
+impl ConfiguredDryRun {
+    pub fn new(runtime_config: RuntimeConfig) -> Self {
+        Self {
+            runtime_config,
+            optional: OptionalDryRun::new(),
+        }
+    }
+}
+
+impl RelayingMonitoring for ConfiguredDryRun {
+    fn notice_relay_success(&mut self) {
+        self.optional.notice_relay_success();
+    }
+
+    fn notice_relay_failure(&mut self) {
+        self.optional.notice_relay_failure();
+    }
+}
+
+impl DryRunSwitch for ConfiguredDryRun {
+    fn should_dry_run_now(&self) -> bool {
+        match self.runtime_config.dry_running() {
+            DryRunning::Always => true,
+            DryRunning::Optimistic => self.optional.should_dry_run_now(),
+        }
+    }
+}
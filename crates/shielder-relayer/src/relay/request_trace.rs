@@ -4,11 +4,18 @@ use shielder_contract::{
     alloy_primitives::{Address, TxHash, U256},
     ShielderContractError,
 };
-use shielder_relayer::RelayQuery;
+use shielder_relayer::RelayCalldata;
 use shielder_setup::version::ContractVersion;
-use tracing::{error, info};
-
-use crate::metrics::{WITHDRAW_DRY_RUN_FAILURE, WITHDRAW_FAILURE, WITHDRAW_SUCCESS};
+use tracing::{error, info, warn};
+
+use crate::{
+    metrics::{
+        RELAY_TASKS_DRAINED, WITHDRAW_DRY_RUN_FAILURE, WITHDRAW_FAILURE, WITHDRAW_NOT_MINED,
+        WITHDRAW_PRECHECK_FAILURE, WITHDRAW_RESUBMISSION, WITHDRAW_REVERTED, WITHDRAW_SUCCESS,
+    },
+    proof_verification::ProofVerificationError,
+    quote_signing::QuoteError,
+};
 
 type Measurement = (String, Duration);
 
@@ -25,9 +32,9 @@ pub struct RequestTrace {
 }
 
 impl RequestTrace {
-    pub fn new(relay_query: &RelayQuery) -> Self {
+    pub fn new(calldata: &RelayCalldata) -> Self {
         Self {
-            created_note: relay_query.calldata.new_note,
+            created_note: calldata.new_note,
             measurements: Vec::new(),
             last_timestamp: Instant::now(),
             current_state: "created",
@@ -59,6 +66,31 @@ impl RequestTrace {
         self.finish("✅ SUCCESS");
     }
 
+    pub fn record_resubmission(&mut self, tx_hash: TxHash) {
+        metrics::counter!(WITHDRAW_RESUBMISSION).increment(1);
+        info!("Relay transaction resubmitted as {tx_hash}");
+        self.tx_hash = Some(tx_hash);
+        self.record("resubmitted");
+    }
+
+    pub fn record_revert(&mut self, tx_hash: TxHash) {
+        metrics::counter!(WITHDRAW_REVERTED).increment(1);
+        error!("Relay transaction reverted: {tx_hash}");
+        self.tx_hash = Some(tx_hash);
+        self.finish("❌ REVERTED");
+    }
+
+    pub fn record_not_mined(&mut self, reason: &str) {
+        metrics::counter!(WITHDRAW_NOT_MINED).increment(1);
+        error!("Relay transaction not mined: {reason}");
+        self.finish("❌ NOT MINED");
+    }
+
+    pub fn record_unresolved(&mut self, reason: &str) {
+        warn!("Relay transaction not mined yet: {reason}");
+        self.finish("⏳ UNRESOLVED");
+    }
+
     pub fn record_version_mismatch(
         &mut self,
         expected_by_relayer: ContractVersion,
@@ -97,12 +129,40 @@ impl RequestTrace {
         self.finish("❌ POCKET MONEY FAILURE");
     }
 
-    pub fn record_quote_invalidity(&mut self) {
+    pub fn record_quote_invalidity(&mut self, err: QuoteError) {
         metrics::counter!(WITHDRAW_FAILURE).increment(1);
-        error!("Invalid quote (probably expired)");
+        error!("Invalid quote: {err}");
         self.finish("❌ QUOTE VALIDITY FAILURE");
     }
 
+    pub fn record_network_fee_too_high(&mut self, quoted: u128, required: u128) {
+        metrics::counter!(WITHDRAW_FAILURE).increment(1);
+        error!("Network gas price above the quoted one: {required} > {quoted}");
+        self.finish("❌ NETWORK FEE FAILURE");
+    }
+
+    pub fn record_spent_nullifier(&mut self, nullifier_hash: U256) {
+        metrics::counter!(WITHDRAW_FAILURE).increment(1);
+        metrics::counter!(WITHDRAW_PRECHECK_FAILURE, "reason" => "spent_nullifier").increment(1);
+        error!("Nullifier hash has already been used: {nullifier_hash}");
+        self.finish("❌ NULLIFIER FAILURE");
+    }
+
+    pub fn record_unknown_merkle_root(&mut self, merkle_root: U256) {
+        metrics::counter!(WITHDRAW_FAILURE).increment(1);
+        metrics::counter!(WITHDRAW_PRECHECK_FAILURE, "reason" => "unknown_merkle_root")
+            .increment(1);
+        error!("Merkle root is unknown to the contract: {merkle_root}");
+        self.finish("❌ MERKLE ROOT FAILURE");
+    }
+
+    pub fn record_invalid_proof(&mut self, err: &ProofVerificationError) {
+        metrics::counter!(WITHDRAW_FAILURE).increment(1);
+        metrics::counter!(WITHDRAW_PRECHECK_FAILURE, "reason" => "invalid_proof").increment(1);
+        error!("Proof verification failed: {err}");
+        self.finish("❌ PROOF FAILURE");
+    }
+
     pub fn record_failure(&mut self, err: ShielderContractError) {
         metrics::counter!(WITHDRAW_FAILURE).increment(1);
         error!("Relay failed: {err}");
@@ -115,6 +175,12 @@ impl RequestTrace {
         self.finish("❌ DRY-RUN FAILURE");
     }
 
+    pub fn record_drained(&mut self) {
+        metrics::counter!(RELAY_TASKS_DRAINED).increment(1);
+        info!("Relay request dropped from the queue by the operator");
+        self.finish("❌ DRAINED");
+    }
+
     fn finish(&mut self, status: &'static str) {
         self.record("finished");
         self.status = Some(status);
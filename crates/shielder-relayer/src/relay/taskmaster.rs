@@ -1,104 +1,200 @@
+use std::{sync::Arc, time::Duration};
+
 use alloy_provider::Provider;
+use alloy_signer_local::PrivateKeySigner;
 use anyhow::Result;
 use async_channel::{Receiver as MPMCReceiver, Sender as MPMCSender};
 use shielder_account::{call_data::WithdrawCall, Token};
 use shielder_contract::{
     alloy_primitives::{Address, TxHash},
     call_type::{DryRun, Submit},
-    ShielderContractError, ShielderUser,
+    providers::create_provider_with_nonce_caching_signer,
+    ConnectionPolicy, ContractResult, ShielderContractError, ShielderUser, TxOverrides,
 };
-use tokio::sync::{
-    mpsc::Sender as MPSCSender,
-    oneshot,
-    oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
+use shielder_relayer::RelayJobStatus;
+use tokio::{
+    sync::{
+        mpsc::Sender as MPSCSender,
+        oneshot,
+        oneshot::{Receiver as OneshotReceiver, Sender as OneshotSender},
+    },
+    time::sleep,
 };
-use tracing::{error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::{
-    config::DryRunning,
+    config::{ChainConfig, NoncePolicy},
+    gas_pricing::FeeCap,
     relay::{
-        monitoring::{DryRunSwitch, ObligatoryDryRun, OptionalDryRun, RelayingMonitoring},
+        confirmation::{
+            await_settlement, track_transaction, ConfirmationPolicy, TxOutcome, Unresolved,
+        },
+        jobs::{JobId, JobStore},
+        monitoring::{ConfiguredDryRun, DryRunSwitch, RelayingMonitoring},
         request_trace::RequestTrace,
         TASK_QUEUE_SIZE,
     },
+    runtime_config::RuntimeConfig,
+    signer_pool::{Availability, SignerPool},
 };
 
+/// How often a sidelined worker checks whether it can take tasks again.
+const SIDELINE_RECHECK_INTERVAL: Duration = Duration::from_secs(5);
+/// How many times a submission is retried with a fresh nonce after a nonce conflict.
+const MAX_NONCE_CONFLICTS: u32 = 3;
+
 pub enum TaskResult {
     DryRunFailed(ShielderContractError),
     RelayFailed(ShielderContractError),
+    /// The transaction was mined, but reverted.
+    Reverted(TxHash),
+    /// The transaction was submitted, but never mined. Contains the reason.
+    NotMined(String),
+    /// The transaction was submitted and is not mined yet, but it still can be. The job stays
+    /// submitted until the node settles it. Contains the reason.
+    Unresolved(String),
+    /// The transaction was mined and has enough confirmations.
     Ok(TxHash),
+    /// The task was dropped from the queue by the operator before any worker took it.
+    Drained,
 }
 
 pub struct Task {
+    job_id: JobId,
     report: OneshotSender<(RequestTrace, TaskResult)>,
     payload: WithdrawCall,
+    /// Bound on the fees of the relay transaction. `None` leaves the fees to the provider.
+    fee_cap: Option<FeeCap>,
     request_trace: RequestTrace,
 }
 
+type WorkerSpawner = Arc<dyn Fn(PrivateKeySigner) + Send + Sync>;
+
 #[derive(Clone)]
 pub struct Taskmaster {
     task_sender: MPMCSender<Task>,
+    task_receiver: MPMCReceiver<Task>,
+    job_store: JobStore,
+    spawn_worker: WorkerSpawner,
+    /// For following transactions submitted before the last shutdown.
+    context: WorkerContext,
+    dry_run_manager: ConfiguredDryRun,
+}
+
+/// How the workers connect to the chain.
+#[derive(Clone)]
+pub struct WorkerConnection {
+    pub chain: ChainConfig,
+    pub nonce_policy: NoncePolicy,
+}
+
+/// Everything that workers need to follow the submitted transactions and to keep the persistent
+/// job store up to date.
+#[derive(Clone)]
+struct Tracking {
+    job_store: JobStore,
+    node_rpc_url: String,
+    confirmation_policy: ConfirmationPolicy,
+}
+
+impl Tracking {
+    fn update(&self, job_id: JobId, update: impl FnOnce(&JobStore) -> Result<()>) {
+        if let Err(err) = update(&self.job_store) {
+            error!("Failed to update relay job {job_id}: {err}");
+        }
+    }
+}
+
+/// Shared state of all the relay workers.
+#[derive(Clone)]
+struct WorkerContext {
+    requests: MPMCReceiver<Task>,
+    /// For giving back tasks that were received by a worker that cannot handle them anymore.
+    requeue: MPMCSender<Task>,
+    connection: WorkerConnection,
+    signer_pool: SignerPool,
+    runtime_config: RuntimeConfig,
+    recharge_reporter: MPSCSender<Address>,
+    tracking: Tracking,
 }
 
 impl Taskmaster {
+    /// Creates a new taskmaster with a worker for every signer in `signer_pool`.
     pub fn new(
-        shielder_users: Vec<ShielderUser<impl Provider + Clone + 'static>>,
-        dry_running: DryRunning,
+        signer_pool: SignerPool,
+        signers: Vec<PrivateKeySigner>,
+        connection: WorkerConnection,
+        runtime_config: RuntimeConfig,
         recharge_reporter: MPSCSender<Address>,
+        job_store: JobStore,
+        confirmation_policy: ConfirmationPolicy,
     ) -> Self {
         let (task_sender, task_receiver) = async_channel::bounded(TASK_QUEUE_SIZE);
+        let context = WorkerContext {
+            requests: task_receiver.clone(),
+            requeue: task_sender.clone(),
+            tracking: Tracking {
+                job_store: job_store.clone(),
+                node_rpc_url: connection.chain.node_rpc_url.clone(),
+                confirmation_policy,
+            },
+            connection,
+            signer_pool,
+            runtime_config: runtime_config.clone(),
+            recharge_reporter,
+        };
 
-        match dry_running {
-            DryRunning::Always => {
-                info!("Dry running is turned on for all calls");
-                Self::spawn_workers(
-                    shielder_users,
-                    task_receiver,
-                    ObligatoryDryRun {},
-                    recharge_reporter,
-                );
-            }
-            DryRunning::Optimistic => {
-                info!("Dry running is optimistically disabled.");
-                Self::spawn_workers(
-                    shielder_users,
-                    task_receiver,
-                    OptionalDryRun::new(),
-                    recharge_reporter,
-                );
-            }
+        info!("Dry running mode: {:?}", runtime_config.dry_running());
+        let dry_run_manager = ConfiguredDryRun::new(runtime_config);
+        let spawn_worker = Self::worker_spawner(context.clone(), dry_run_manager.clone());
+        for signer in signers {
+            spawn_worker(signer);
         }
 
-        Self { task_sender }
+        Self {
+            task_sender,
+            task_receiver,
+            job_store,
+            spawn_worker,
+            context,
+            dry_run_manager,
+        }
     }
 
-    fn spawn_workers(
-        shielder_users: Vec<ShielderUser<impl Provider + Clone + 'static>>,
-        task_receiver: MPMCReceiver<Task>,
-        dry_run_manager: impl RelayingMonitoring + DryRunSwitch + 'static,
-        recharge_reporter: MPSCSender<Address>,
-    ) {
-        for shielder_user in shielder_users {
+    fn worker_spawner(
+        context: WorkerContext,
+        dry_run_manager: impl RelayingMonitoring + DryRunSwitch + Sync + 'static,
+    ) -> WorkerSpawner {
+        Arc::new(move |signer| {
             tokio::spawn(relay_worker(
-                task_receiver.clone(),
-                shielder_user,
+                signer,
+                context.clone(),
                 dry_run_manager.clone(),
-                recharge_reporter.clone(),
             ));
-        }
+        })
+    }
+
+    /// Start a worker for `signer`, which must have been already added to the signer pool. The
+    /// worker stops once the signer is removed from the pool.
+    pub fn spawn_worker(&self, signer: PrivateKeySigner) {
+        (self.spawn_worker)(signer)
     }
 
     pub async fn register_new_task(
         &self,
+        job_id: JobId,
         payload: WithdrawCall,
+        fee_cap: Option<FeeCap>,
         mut request_trace: RequestTrace,
     ) -> Result<OneshotReceiver<(RequestTrace, TaskResult)>> {
         let (report_sender, report_receiver) = oneshot::channel();
 
         request_trace.record("queued for relay");
         let task = Task {
+            job_id,
             report: report_sender,
             payload,
+            fee_cap,
             request_trace,
         };
         self.task_sender
@@ -108,39 +204,163 @@ impl Taskmaster {
 
         Ok(report_receiver)
     }
+
+    /// Follow a transaction that was submitted (by `signer` with `nonce`) before the last shutdown,
+    /// exactly as if it had just been submitted by its worker (including resubmissions with bumped
+    /// fees).
+    ///
+    /// If the key of `signer` is not known anymore (e.g. the signer was added at runtime), the
+    /// transaction is only followed until it is settled, without resubmissions.
+    pub async fn resume_submission(
+        &self,
+        job_id: JobId,
+        payload: WithdrawCall,
+        fee_cap: Option<FeeCap>,
+        tx_hash: TxHash,
+        (signer, nonce): (Address, u64),
+        request_trace: RequestTrace,
+    ) -> Result<OneshotReceiver<(RequestTrace, TaskResult)>> {
+        let context = &self.context;
+        let (report_sender, report_receiver) = oneshot::channel();
+
+        let Some(signer_key) = context.signer_pool.signer(signer) else {
+            warn!(
+                "Signer {signer} of relay job {job_id} is not in the pool - following {tx_hash} \
+                 without resubmissions"
+            );
+            tokio::spawn(watch_submission(
+                job_id,
+                tx_hash,
+                (signer, nonce),
+                (report_sender, request_trace),
+                context.tracking.clone(),
+                context.signer_pool.clone(),
+                self.dry_run_manager.clone(),
+            ));
+            return Ok(report_receiver);
+        };
+        let shielder_user = build_shielder_user(signer_key, &context.connection).await?;
+        context.signer_pool.submitted(signer, nonce);
+
+        tokio::spawn(follow_submission(
+            shielder_user,
+            Submission {
+                job_id,
+                payload,
+                fee_cap,
+                tx_hash,
+                nonce,
+                report: report_sender,
+                request_trace,
+            },
+            context.tracking.clone(),
+            context.signer_pool.clone(),
+            self.dry_run_manager.clone(),
+        ));
+        Ok(report_receiver)
+    }
+
+    /// Drop all the tasks that are waiting for a worker. Their jobs are marked as failed and the
+    /// clients are notified. Returns the number of dropped tasks.
+    pub fn drain(&self) -> usize {
+        let mut drained = 0;
+        while let Ok(task) = self.task_receiver.try_recv() {
+            if let Err(err) = self
+                .job_store
+                .set_failed(task.job_id, "Dropped from the queue by the operator")
+            {
+                error!("Failed to update relay job {}: {err}", task.job_id);
+            }
+            let _ = task.report.send((task.request_trace, TaskResult::Drained));
+            drained += 1;
+        }
+        drained
+    }
+}
+
+/// A transaction sent by a worker, followed until it is final.
+struct Submission {
+    job_id: JobId,
+    payload: WithdrawCall,
+    fee_cap: Option<FeeCap>,
+    tx_hash: TxHash,
+    nonce: u64,
+    report: OneshotSender<(RequestTrace, TaskResult)>,
+    request_trace: RequestTrace,
 }
 
 async fn relay_worker(
-    requests: MPMCReceiver<Task>,
-    shielder_user: ShielderUser<impl Provider + Clone>,
-    mut dry_run_manager: impl RelayingMonitoring + DryRunSwitch,
-    recharge_reporter: MPSCSender<Address>,
+    signer: PrivateKeySigner,
+    context: WorkerContext,
+    mut dry_run_manager: impl RelayingMonitoring + DryRunSwitch + 'static,
 ) {
-    let worker_address = shielder_user.address();
-    while let Ok(task) = requests.recv().await {
+    let worker_address = signer.address();
+    let WorkerContext {
+        requests,
+        requeue,
+        connection,
+        signer_pool,
+        runtime_config,
+        recharge_reporter,
+        tracking,
+    } = context;
+
+    let shielder_user = match build_shielder_user(signer, &connection).await {
+        Ok(shielder_user) => shielder_user,
+        Err(err) => {
+            error!("Failed to start relay worker for {worker_address}: {err}");
+            signer_pool.remove(worker_address);
+            return;
+        }
+    };
+
+    loop {
+        if runtime_config.is_paused() {
+            debug!("Relay worker {worker_address} is waiting for relaying to be resumed");
+            runtime_config.wait_until_resumed().await;
+            continue;
+        }
+        match signer_pool.availability(worker_address) {
+            Availability::Available => {}
+            Availability::Removed => break,
+            sidelined => {
+                debug!("Relay worker {worker_address} is sidelined: {sidelined:?}");
+                sleep(SIDELINE_RECHECK_INTERVAL).await;
+                continue;
+            }
+        }
+
+        let Ok(task) = requests.recv().await else {
+            error!("Relay worker {worker_address} stopped working - channel closed");
+            return;
+        };
+        // The signer might have been sidelined or removed (or relaying paused) while waiting for
+        // the task.
+        if signer_pool.availability(worker_address) != Availability::Available
+            || runtime_config.is_paused()
+        {
+            if requeue.send(task).await.is_err() {
+                error!("Failed to give back a task - channel closed");
+            }
+            continue;
+        }
+
+        let job_id = task.job_id;
         let mut request_trace = task.request_trace;
         request_trace.record("received by worker");
         request_trace.set_relayer_address(worker_address);
 
         if dry_run_manager.should_dry_run_now() {
-            let dry_run_result = match task.payload.token {
-                Token::Native => {
-                    shielder_user
-                        .withdraw_native::<DryRun>(task.payload.clone().try_into().unwrap())
-                        .await
-                }
-                Token::ERC20(_) => {
-                    shielder_user
-                        .withdraw_erc20::<DryRun>(
-                            task.payload.clone().try_into().unwrap(),
-                            task.payload.pocket_money,
-                        )
-                        .await
-                }
-            };
+            tracking.update(job_id, |store| {
+                store.set_status(job_id, RelayJobStatus::DryRun)
+            });
+            let dry_run_result = dry_run(&shielder_user, task.payload.clone()).await;
             request_trace.record("dry run completed");
 
             if let Err(err) = dry_run_result {
+                tracking.update(job_id, |store| {
+                    store.set_failed(job_id, &format!("Dry run failed: {err}"))
+                });
                 let _ = task
                     .report
                     .send((request_trace, TaskResult::DryRunFailed(err)));
@@ -148,29 +368,36 @@ async fn relay_worker(
             }
         }
 
-        let submit_result = match task.payload.token {
-            Token::Native => {
-                shielder_user
-                    .withdraw_native::<Submit>(task.payload.try_into().unwrap())
-                    .await
-            }
-            Token::ERC20(_) => {
-                shielder_user
-                    .withdraw_erc20::<Submit>(
-                        task.payload.clone().try_into().unwrap(),
-                        task.payload.pocket_money,
-                    )
-                    .await
-            }
-        };
+        let submit_result =
+            submit_with_pool_nonce(&shielder_user, &signer_pool, &task.payload, task.fee_cap).await;
         request_trace.record("relay completed");
 
         match submit_result {
-            Ok(tx_hash) => {
-                let _ = task.report.send((request_trace, TaskResult::Ok(tx_hash)));
-                dry_run_manager.notice_relay_success();
+            Ok((tx_hash, nonce)) => {
+                tracking.update(job_id, |store| {
+                    store.set_submitted(job_id, tx_hash, worker_address, nonce)
+                });
+                // Confirmation takes a few blocks - the worker can handle other tasks meanwhile.
+                tokio::spawn(follow_submission(
+                    shielder_user.clone(),
+                    Submission {
+                        job_id,
+                        payload: task.payload,
+                        fee_cap: task.fee_cap,
+                        tx_hash,
+                        nonce,
+                        report: task.report,
+                        request_trace,
+                    },
+                    tracking.clone(),
+                    signer_pool.clone(),
+                    dry_run_manager.clone(),
+                ));
             }
             Err(err) => {
+                tracking.update(job_id, |store| {
+                    store.set_failed(job_id, &format!("Relay failed: {err}"))
+                });
                 let _ = task
                     .report
                     .send((request_trace, TaskResult::RelayFailed(err)));
@@ -186,5 +413,241 @@ async fn relay_worker(
         }
     }
 
-    error!("Relay worker thread stopped working - channel closed. Corresponding address: {worker_address}");
+    info!("Relay worker {worker_address} stopped - signer removed from the pool");
+}
+
+/// Submit `payload` with a nonce assigned by the pool (with the fees of `fee_cap`, if given). If the node rejects the nonce, the pool is resynchronized and the submission is retried.
+async fn submit_with_pool_nonce(
+    shielder_user: &ShielderUser<impl Provider + Clone>,
+    signer_pool: &SignerPool,
+    payload: &WithdrawCall,
+    fee_cap: Option<FeeCap>,
+) -> ContractResult<(TxHash, u64)> {
+    let address = shielder_user.address();
+    let mut conflicts = 0;
+    loop {
+        let nonce = signer_pool.acquire_nonce(address).await?;
+        let overrides = match fee_cap {
+            Some(fee_cap) => fee_cap.tx_overrides(nonce),
+            None => TxOverrides {
+                nonce: Some(nonce),
+                ..Default::default()
+            },
+        };
+        match submit(shielder_user.with_overrides(overrides), payload.clone()).await {
+            Ok(tx_hash) => {
+                signer_pool.submitted(address, nonce);
+                return Ok((tx_hash, nonce));
+            }
+            Err(ShielderContractError::SignerConflict) if conflicts < MAX_NONCE_CONFLICTS => {
+                conflicts += 1;
+                signer_pool.reset_nonces(address);
+            }
+            Err(err) => {
+                if matches!(err, ShielderContractError::SignerConflict) {
+                    signer_pool.reset_nonces(address);
+                } else {
+                    signer_pool.release(address, nonce);
+                }
+                return Err(err);
+            }
+        }
+    }
+}
+
+/// Wait until the submitted transaction (or its replacement) is final and report the outcome.
+async fn follow_submission(
+    shielder_user: ShielderUser<impl Provider + Clone>,
+    submission: Submission,
+    tracking: Tracking,
+    signer_pool: SignerPool,
+    mut dry_run_manager: impl RelayingMonitoring,
+) {
+    let Submission {
+        job_id,
+        payload,
+        fee_cap,
+        tx_hash,
+        nonce,
+        report,
+        mut request_trace,
+    } = submission;
+
+    let signer_address = shielder_user.address();
+    let tracked = track_transaction(
+        &tracking.node_rpc_url,
+        tracking.confirmation_policy,
+        tx_hash,
+        fee_cap,
+        |overrides| submit(shielder_user.with_overrides(overrides), payload.clone()),
+        |new_tx_hash| {
+            request_trace.record_resubmission(new_tx_hash);
+            tracking.update(job_id, |store| {
+                store.set_submitted(job_id, new_tx_hash, signer_address, nonce)
+            });
+        },
+    )
+    .await;
+
+    let outcome = match tracked {
+        Ok(outcome) => outcome,
+        Err(Unresolved { reason, sent }) => {
+            // Some of the sent versions can still be mined, so neither the job can be failed nor
+            // the nonce reused. The client is told that and the node is watched until it decides.
+            warn!(
+                "Relay job {job_id} is unresolved ({reason}) - waiting for the node to settle it"
+            );
+            let _ = report.send((request_trace, TaskResult::Unresolved(reason)));
+            let outcome = await_settlement(
+                &tracking.node_rpc_url,
+                tracking.confirmation_policy.confirmations,
+                signer_address,
+                nonce,
+                sent,
+            )
+            .await;
+            conclude(
+                job_id,
+                outcome,
+                (signer_address, nonce),
+                &tracking,
+                &signer_pool,
+                &mut dry_run_manager,
+            );
+            return;
+        }
+    };
+    request_trace.record("confirmation completed");
+
+    let result = conclude(
+        job_id,
+        outcome,
+        (signer_address, nonce),
+        &tracking,
+        &signer_pool,
+        &mut dry_run_manager,
+    );
+    let _ = report.send((request_trace, result));
+}
+
+/// Wait until the transaction sent by `signer` with `nonce` is settled, without resubmitting it,
+/// and report the outcome.
+async fn watch_submission(
+    job_id: JobId,
+    tx_hash: TxHash,
+    (signer, nonce): (Address, u64),
+    (report, mut request_trace): (OneshotSender<(RequestTrace, TaskResult)>, RequestTrace),
+    tracking: Tracking,
+    signer_pool: SignerPool,
+    mut dry_run_manager: impl RelayingMonitoring,
+) {
+    let outcome = await_settlement(
+        &tracking.node_rpc_url,
+        tracking.confirmation_policy.confirmations,
+        signer,
+        nonce,
+        vec![tx_hash],
+    )
+    .await;
+    request_trace.record("confirmation completed");
+
+    let result = conclude(
+        job_id,
+        outcome,
+        (signer, nonce),
+        &tracking,
+        &signer_pool,
+        &mut dry_run_manager,
+    );
+    let _ = report.send((request_trace, result));
+}
+
+/// Update the job store and the signer pool with the final `outcome` of the transaction sent by
+/// `signer` with `nonce`.
+fn conclude(
+    job_id: JobId,
+    outcome: TxOutcome,
+    (signer, nonce): (Address, u64),
+    tracking: &Tracking,
+    signer_pool: &SignerPool,
+    dry_run_manager: &mut impl RelayingMonitoring,
+) -> TaskResult {
+    match outcome {
+        TxOutcome::Confirmed(_) | TxOutcome::Reverted(_) => signer_pool.finished(signer, nonce),
+        // The nonce is free again - it will be reused or filled by the pool.
+        TxOutcome::NotMined(_) => signer_pool.release(signer, nonce),
+    }
+
+    match outcome {
+        TxOutcome::Confirmed(tx_hash) => {
+            tracking.update(job_id, |store| store.set_mined(job_id, tx_hash));
+            dry_run_manager.notice_relay_success();
+            TaskResult::Ok(tx_hash)
+        }
+        TxOutcome::Reverted(tx_hash) => {
+            tracking.update(job_id, |store| {
+                store.set_failed(job_id, &format!("Transaction {tx_hash} reverted"))
+            });
+            dry_run_manager.notice_relay_failure();
+            TaskResult::Reverted(tx_hash)
+        }
+        TxOutcome::NotMined(reason) => {
+            tracking.update(job_id, |store| store.set_failed(job_id, &reason));
+            dry_run_manager.notice_relay_failure();
+            TaskResult::NotMined(reason)
+        }
+    }
+}
+
+async fn dry_run(
+    shielder_user: &ShielderUser<impl Provider + Clone>,
+    payload: WithdrawCall,
+) -> ContractResult<()> {
+    match payload.token {
+        Token::Native => shielder_user
+            .withdraw_native::<DryRun>(payload.try_into().unwrap())
+            .await
+            .map(|_| ()),
+        Token::ERC20(_) => shielder_user
+            .withdraw_erc20::<DryRun>(payload.clone().try_into().unwrap(), payload.pocket_money)
+            .await
+            .map(|_| ()),
+    }
+}
+
+async fn submit(
+    shielder_user: ShielderUser<impl Provider + Clone>,
+    payload: WithdrawCall,
+) -> ContractResult<TxHash> {
+    match payload.token {
+        Token::Native => {
+            shielder_user
+                .withdraw_native::<Submit>(payload.try_into().unwrap())
+                .await
+        }
+        Token::ERC20(_) => {
+            shielder_user
+                .withdraw_erc20::<Submit>(payload.clone().try_into().unwrap(), payload.pocket_money)
+                .await
+        }
+    }
+}
+
+async fn build_shielder_user(
+    signer: PrivateKeySigner,
+    connection: &WorkerConnection,
+) -> Result<ShielderUser<impl Provider + Clone>> {
+    let chain = &connection.chain;
+    let policy = match connection.nonce_policy {
+        NoncePolicy::Caching => ConnectionPolicy::Keep {
+            caller_address: signer.address(),
+            provider: create_provider_with_nonce_caching_signer(&chain.node_rpc_url, signer)
+                .await?,
+        },
+        NoncePolicy::Stateless => ConnectionPolicy::OnDemand {
+            signer,
+            rpc_url: chain.node_rpc_url.clone(),
+        },
+    };
+    Ok(ShielderUser::new(chain.shielder_contract_address, policy))
 }
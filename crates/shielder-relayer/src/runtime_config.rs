@@ -0,0 +1,267 @@
+use std::{collections::HashSet, sync::Arc};
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use shielder_account::Token;
+use shielder_contract::alloy_primitives::U256;
+use shielder_relayer::{FeePolicy, TokenInfo, TokenKind};
+use tokio::sync::watch;
+
+use crate::config::{DryRunning, OperationalConfig};
+
+/// The part of `OperationalConfig` that can be changed without restarting the relayer.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RuntimeSettings {
+    pub service_fee_percent: u32,
+    pub max_pocket_money: U256,
+    pub token_config: Vec<TokenInfo>,
+    pub dry_running: DryRunning,
+}
+
+impl From<&OperationalConfig> for RuntimeSettings {
+    fn from(config: &OperationalConfig) -> Self {
+        Self {
+            service_fee_percent: config.service_fee_percent,
+            max_pocket_money: config.max_pocket_money,
+            token_config: config.token_config.clone(),
+            dry_running: config.dry_running,
+        }
+    }
+}
+
+/// Requested change of the runtime settings. Missing fields are left unchanged.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SettingsUpdate {
+    pub service_fee_percent: Option<u32>,
+    pub max_pocket_money: Option<U256>,
+    pub token_config: Option<Vec<TokenInfo>>,
+    pub dry_running: Option<DryRunning>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, thiserror::Error)]
+pub enum SettingsError {
+    #[error("Native token must be supported - all fees are computed from its price")]
+    MissingNativeToken,
+    #[error("Token {0} is configured more than once")]
+    DuplicatedToken(TokenKind),
+    #[error("Invalid fee policy for {0}: {1}")]
+    InvalidFeePolicy(TokenKind, &'static str),
+}
+
+/// Settings shared by the server and the workers, which can be updated at runtime (see `admin`),
+/// together with the relaying pause switch.
+#[derive(Clone)]
+pub struct RuntimeConfig {
+    settings: Arc<RwLock<RuntimeSettings>>,
+    paused: Arc<watch::Sender<bool>>,
+}
+
+impl RuntimeConfig {
+    pub fn new(settings: RuntimeSettings) -> Self {
+        Self {
+            settings: Arc::new(RwLock::new(settings)),
+            paused: Arc::new(watch::channel(false).0),
+        }
+    }
+
+    pub fn settings(&self) -> RuntimeSettings {
+        self.settings.read().clone()
+    }
+
+    pub fn service_fee_percent(&self) -> u32 {
+        self.settings.read().service_fee_percent
+    }
+
+    pub fn max_pocket_money(&self) -> U256 {
+        self.settings.read().max_pocket_money
+    }
+
+    pub fn token_config(&self) -> Vec<TokenInfo> {
+        self.settings.read().token_config.clone()
+    }
+
+    /// Configuration of `token`, if it is accepted as a fee token.
+    pub fn token_info(&self, token: Token) -> Option<TokenInfo> {
+        self.settings
+            .read()
+            .token_config
+            .iter()
+            .find(|info| Token::from(info.kind) == token)
+            .cloned()
+    }
+
+    /// Fee policy of `token_info`, with the missing values taken from the relayer-wide settings.
+    pub fn fee_policy(&self, token_info: &TokenInfo, default_relay_gas: u64) -> FeePolicy {
+        token_info
+            .fee_policy
+            .resolve(default_relay_gas, self.service_fee_percent())
+    }
+
+    pub fn dry_running(&self) -> DryRunning {
+        self.settings.read().dry_running
+    }
+
+    /// Apply `update` atomically. Returns the names of the settings that actually changed.
+    pub fn update(&self, update: SettingsUpdate) -> Result<Vec<&'static str>, SettingsError> {
+        if let Some(token_config) = &update.token_config {
+            validate_token_config(token_config)?;
+        }
+
+        let mut settings = self.settings.write();
+        let mut changed = Vec::new();
+        if let Some(service_fee_percent) = update.service_fee_percent {
+            if settings.service_fee_percent != service_fee_percent {
+                settings.service_fee_percent = service_fee_percent;
+                changed.push("service_fee_percent");
+            }
+        }
+        if let Some(max_pocket_money) = update.max_pocket_money {
+            if settings.max_pocket_money != max_pocket_money {
+                settings.max_pocket_money = max_pocket_money;
+                changed.push("max_pocket_money");
+            }
+        }
+        if let Some(token_config) = update.token_config {
+            if settings.token_config != token_config {
+                settings.token_config = token_config;
+                changed.push("token_config");
+            }
+        }
+        if let Some(dry_running) = update.dry_running {
+            if settings.dry_running != dry_running {
+                settings.dry_running = dry_running;
+                changed.push("dry_running");
+            }
+        }
+        Ok(changed)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// Pause or resume relaying. Returns `false` if relaying already was in the requested state.
+    pub fn set_paused(&self, paused: bool) -> bool {
+        self.paused.send_if_modified(|current| {
+            let modified = *current != paused;
+            *current = paused;
+            modified
+        })
+    }
+
+    /// Wait until relaying is resumed (returns immediately if it is not paused).
+    pub async fn wait_until_resumed(&self) {
+        // The sender lives as long as `self`, so waiting cannot fail.
+        let _ = self.paused.subscribe().wait_for(|paused| !paused).await;
+    }
+}
+
+fn validate_token_config(token_config: &[TokenInfo]) -> Result<(), SettingsError> {
+    let mut seen = HashSet::new();
+    for token in token_config {
+        if !seen.insert(token.kind) {
+            return Err(SettingsError::DuplicatedToken(token.kind));
+        }
+        token
+            .fee_policy
+            .validate()
+            .map_err(|err| SettingsError::InvalidFeePolicy(token.kind, err))?;
+    }
+    if !seen.contains(&TokenKind::Native) {
+        return Err(SettingsError::MissingNativeToken);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rust_decimal::Decimal;
+    use shielder_contract::alloy_primitives::U256;
+    use shielder_relayer::{PriceProvider, TokenInfo, TokenKind};
+
+    use crate::{
+        config::DryRunning,
+        runtime_config::{RuntimeConfig, RuntimeSettings, SettingsError, SettingsUpdate},
+    };
+
+    fn native_token() -> TokenInfo {
+        TokenInfo {
+            kind: TokenKind::Native,
+            price_provider: PriceProvider::Static(Decimal::ONE),
+            fee_policy: Default::default(),
+        }
+    }
+
+    fn runtime_config() -> RuntimeConfig {
+        RuntimeConfig::new(RuntimeSettings {
+            service_fee_percent: 15,
+            max_pocket_money: U256::from(100),
+            token_config: vec![native_token()],
+            dry_running: DryRunning::Always,
+        })
+    }
+
+    #[test]
+    fn update_changes_only_provided_settings() {
+        let config = runtime_config();
+
+        let changed = config
+            .update(SettingsUpdate {
+                service_fee_percent: Some(20),
+                max_pocket_money: Some(U256::from(100)),
+                dry_running: Some(DryRunning::Optimistic),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert_eq!(changed, vec!["service_fee_percent", "dry_running"]);
+        assert_eq!(config.service_fee_percent(), 20);
+        assert_eq!(config.max_pocket_money(), U256::from(100));
+        assert_eq!(config.dry_running(), DryRunning::Optimistic);
+        assert_eq!(config.token_config(), vec![native_token()]);
+    }
+
+    #[test]
+    fn invalid_token_config_is_rejected_as_a_whole() {
+        let config = runtime_config();
+
+        let result = config.update(SettingsUpdate {
+            service_fee_percent: Some(20),
+            token_config: Some(vec![native_token(), native_token()]),
+            ..Default::default()
+        });
+        assert_eq!(
+            result,
+            Err(SettingsError::DuplicatedToken(TokenKind::Native))
+        );
+
+        let result = config.update(SettingsUpdate {
+            token_config: Some(vec![]),
+            ..Default::default()
+        });
+        assert_eq!(result, Err(SettingsError::MissingNativeToken));
+
+        assert_eq!(config.service_fee_percent(), 15);
+    }
+
+    #[tokio::test]
+    async fn pausing_is_reported_only_when_state_changes() {
+        let config = runtime_config();
+
+        assert!(config.set_paused(true));
+        assert!(!config.set_paused(true));
+        assert!(config.is_paused());
+
+        let waiting = tokio::spawn({
+            let config = config.clone();
+            async move { config.wait_until_resumed().await }
+        });
+        assert!(config.set_paused(false));
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .expect("worker should be released")
+            .unwrap();
+    }
+}
@@ -1,5 +1,7 @@
+use std::time::Duration;
+
 use axum::{
-    http::StatusCode,
+    http::{header::RETRY_AFTER, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -24,11 +26,30 @@ pub fn bad_request(msg: &str) -> Response {
     (StatusCode::BAD_REQUEST, jsonize_str(msg)).into_response()
 }
 
+pub fn unauthorized(msg: &str) -> Response {
+    (StatusCode::UNAUTHORIZED, jsonize_str(msg)).into_response()
+}
+
+pub fn not_found(msg: &str) -> Response {
+    (StatusCode::NOT_FOUND, jsonize_str(msg)).into_response()
+}
+
 pub fn temporary_failure(msg: &str) -> Response {
     let code = StatusCode::SERVICE_UNAVAILABLE;
     (code, jsonize_str(msg)).into_response()
 }
 
+/// `429 Too Many Requests` with the `Retry-After` header (in whole seconds, rounded up).
+pub fn too_many_requests(retry_after: Duration) -> Response {
+    let seconds = retry_after.as_secs() + (retry_after.subsec_nanos() > 0) as u64;
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(RETRY_AFTER, seconds.to_string())],
+        jsonize_str(&format!("Too many requests. Retry in {seconds} seconds.")),
+    )
+        .into_response()
+}
+
 fn jsonize_str(msg: &str) -> Json<SimpleServiceResponse> {
     Json(SimpleServiceResponse {
         message: msg.into(),
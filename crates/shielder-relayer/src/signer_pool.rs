@@ -0,0 +1,478 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+    time::Duration,
+};
+
+use alloy_provider::{network::TransactionBuilder, Provider};
+use alloy_rpc_types::TransactionRequest;
+use alloy_signer_local::PrivateKeySigner;
+use parking_lot::Mutex;
+use serde::Serialize;
+use shielder_contract::{
+    alloy_primitives::{Address, TxHash, U256},
+    providers::{create_provider_with_signer, create_simple_provider},
+    ContractResult, ShielderContractError,
+};
+use tokio::{
+    sync::mpsc::Sender as MPSCSender,
+    time::{interval, Instant},
+};
+use tracing::{error, info, warn};
+
+use crate::metrics::{NONCE_GAP_FILLED, SIGNER_SIDELINED};
+
+/// How often the pool compares its state with the chain and fills nonce gaps.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Whether a signer can take new relay tasks.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize)]
+pub enum Availability {
+    Available,
+    /// Balance is below the recharge threshold (the signer is waiting to be recharged).
+    LowBalance,
+    /// One of the signer's transactions has been pending for too long.
+    Stuck,
+    /// The signer is no longer in the pool.
+    Removed,
+}
+
+/// Local view of the nonces of a single signer.
+#[derive(Clone, Debug, Default)]
+struct NonceTracker {
+    /// Next nonce that has never been used. `None` until it is read from the node.
+    next: Option<u64>,
+    /// Nonces below `next` that were given out, but whose transactions never reached the network.
+    /// Until they are used, all the later transactions of the signer are stuck.
+    gaps: BTreeSet<u64>,
+    /// Nonces of the sent transactions that are not known to be mined yet, with the sending time.
+    pending: BTreeMap<u64, Instant>,
+}
+
+impl NonceTracker {
+    /// Take a nonce for a new transaction. Gaps are filled first.
+    fn acquire(&mut self) -> Option<u64> {
+        if let Some(nonce) = self.gaps.pop_first() {
+            return Some(nonce);
+        }
+        let nonce = self.next?;
+        self.next = Some(nonce + 1);
+        Some(nonce)
+    }
+
+    /// Initialize the tracker with the number of transactions of the signer (including the ones in
+    /// the mempool), unless it is initialized already.
+    fn init(&mut self, transaction_count: u64) {
+        self.next.get_or_insert(transaction_count);
+    }
+
+    fn submitted(&mut self, nonce: u64, now: Instant) {
+        self.pending.insert(nonce, now);
+    }
+
+    /// The transaction with `nonce` has been mined (or replaced by another mined transaction).
+    fn finished(&mut self, nonce: u64) {
+        self.pending.remove(&nonce);
+    }
+
+    /// The transaction with `nonce` did not reach the network (or was dropped from it).
+    fn release(&mut self, nonce: u64) {
+        self.pending.remove(&nonce);
+        let Some(next) = self.next.as_mut() else {
+            return;
+        };
+        if nonce >= *next {
+            return;
+        }
+        self.gaps.insert(nonce);
+        // Released nonces right below `next` are not gaps - they can be simply given out again.
+        while self.gaps.last() == Some(&(*next - 1)) {
+            self.gaps.pop_last();
+            *next -= 1;
+        }
+    }
+
+    /// Account for `mined_count` transactions of the signer being already mined.
+    fn sync(&mut self, mined_count: u64) {
+        self.pending.retain(|nonce, _| *nonce >= mined_count);
+        self.gaps.retain(|nonce| *nonce >= mined_count);
+        if let Some(next) = self.next.as_mut() {
+            // The key has been used outside the relayer.
+            *next = (*next).max(mined_count);
+        }
+    }
+
+    /// Forget everything and read the state from the node again on the next `acquire`.
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    fn oldest_pending(&self) -> Option<Instant> {
+        self.pending.values().min().copied()
+    }
+}
+
+struct SignerSlot {
+    signer: PrivateKeySigner,
+    nonces: NonceTracker,
+    low_balance: bool,
+}
+
+/// Public view of a signer in the pool.
+#[derive(Clone, Debug, Serialize)]
+pub struct SignerStatus {
+    pub address: Address,
+    pub availability: Availability,
+    pub next_nonce: Option<u64>,
+    pub pending_transactions: usize,
+    pub nonce_gaps: Vec<u64>,
+}
+
+/// Signing keys used by the relay workers, together with their nonces.
+///
+/// Nonces are assigned by the pool (instead of the providers) so that a transaction that failed to
+/// be sent doesn't leave a gap blocking all the later transactions of the signer. Gaps that are not
+/// reused by the next relay are filled with empty self-transfers by `signer_pool_worker`.
+#[derive(Clone)]
+pub struct SignerPool {
+    signers: Arc<Mutex<HashMap<Address, SignerSlot>>>,
+    node_rpc_url: String,
+    chain_id: u64,
+    recharge_threshold: U256,
+    /// How long a transaction can stay pending before its signer is sidelined.
+    stuck_timeout: Duration,
+}
+
+impl SignerPool {
+    pub fn new(
+        signers: Vec<PrivateKeySigner>,
+        node_rpc_url: String,
+        chain_id: u64,
+        recharge_threshold: U256,
+        stuck_timeout: Duration,
+    ) -> Self {
+        let pool = Self {
+            signers: Default::default(),
+            node_rpc_url,
+            chain_id,
+            recharge_threshold,
+            stuck_timeout,
+        };
+        for signer in signers {
+            pool.add(signer);
+        }
+        pool
+    }
+
+    /// Add `signer` to the pool. Returns `false` if it is already there.
+    pub fn add(&self, signer: PrivateKeySigner) -> bool {
+        let mut signers = self.signers.lock();
+        if signers.contains_key(&signer.address()) {
+            return false;
+        }
+        signers.insert(
+            signer.address(),
+            SignerSlot {
+                signer,
+                nonces: Default::default(),
+                low_balance: false,
+            },
+        );
+        true
+    }
+
+    /// Remove `address` from the pool. Its worker stops after finishing the current task. Returns
+    /// `false` if there was no such signer.
+    pub fn remove(&self, address: Address) -> bool {
+        metrics::gauge!(
+            SIGNER_SIDELINED,
+            "chain_id" => self.chain_id.to_string(),
+            "address" => address.to_string()
+        )
+        .set(0.);
+        self.signers.lock().remove(&address).is_some()
+    }
+
+    /// Signing key of `address`, if it is in the pool.
+    pub fn signer(&self, address: Address) -> Option<PrivateKeySigner> {
+        self.signers
+            .lock()
+            .get(&address)
+            .map(|slot| slot.signer.clone())
+    }
+
+    pub fn signer_count(&self) -> usize {
+        self.signers.lock().len()
+    }
+
+    pub fn availability(&self, address: Address) -> Availability {
+        match self.signers.lock().get(&address) {
+            Some(slot) => self.slot_availability(slot, Instant::now()),
+            None => Availability::Removed,
+        }
+    }
+
+    fn slot_availability(&self, slot: &SignerSlot, now: Instant) -> Availability {
+        let stuck = slot
+            .nonces
+            .oldest_pending()
+            .is_some_and(|sent_at| now.saturating_duration_since(sent_at) > self.stuck_timeout);
+        if slot.low_balance {
+            Availability::LowBalance
+        } else if stuck {
+            Availability::Stuck
+        } else {
+            Availability::Available
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<SignerStatus> {
+        let now = Instant::now();
+        let mut statuses = self
+            .signers
+            .lock()
+            .iter()
+            .map(|(address, slot)| SignerStatus {
+                address: *address,
+                availability: self.slot_availability(slot, now),
+                next_nonce: slot.nonces.next,
+                pending_transactions: slot.nonces.pending.len(),
+                nonce_gaps: slot.nonces.gaps.iter().copied().collect(),
+            })
+            .collect::<Vec<_>>();
+        statuses.sort_by_key(|status| status.address);
+        statuses
+    }
+
+    /// Take a nonce for the next transaction of `address`.
+    pub async fn acquire_nonce(&self, address: Address) -> ContractResult<u64> {
+        if let Some(nonce) = self.with_nonces(address, NonceTracker::acquire)? {
+            return Ok(nonce);
+        }
+        let transaction_count = create_simple_provider(&self.node_rpc_url)
+            .await?
+            .get_transaction_count(address)
+            .pending()
+            .await
+            .map_err(ShielderContractError::ProviderError)?;
+        self.with_nonces(address, |nonces| {
+            nonces.init(transaction_count);
+            nonces.acquire().expect("Tracker has just been initialized")
+        })
+    }
+
+    /// The transaction with `nonce` has been sent.
+    pub fn submitted(&self, address: Address, nonce: u64) {
+        let _ = self.with_nonces(address, |nonces| nonces.submitted(nonce, Instant::now()));
+    }
+
+    /// The transaction with `nonce` has been mined.
+    pub fn finished(&self, address: Address, nonce: u64) {
+        let _ = self.with_nonces(address, |nonces| nonces.finished(nonce));
+    }
+
+    /// The transaction with `nonce` has not been (or will not be) mined, so the nonce is free.
+    pub fn release(&self, address: Address, nonce: u64) {
+        let _ = self.with_nonces(address, |nonces| nonces.release(nonce));
+    }
+
+    /// The local state turned out to be wrong (e.g. the node rejected a nonce). Read it again from
+    /// the node.
+    pub fn reset_nonces(&self, address: Address) {
+        warn!("Nonce conflict for {address} - resynchronizing nonces with the node");
+        let _ = self.with_nonces(address, NonceTracker::reset);
+    }
+
+    fn with_nonces<R>(
+        &self,
+        address: Address,
+        action: impl FnOnce(&mut NonceTracker) -> R,
+    ) -> ContractResult<R> {
+        self.signers
+            .lock()
+            .get_mut(&address)
+            .map(|slot| action(&mut slot.nonces))
+            .ok_or_else(|| ShielderContractError::Other(format!("Signer {address} was removed")))
+    }
+
+    /// Compare the pool state with the chain: update balances and mined nonces, and fill the gaps.
+    async fn refresh(&self, recharge_reporter: &MPSCSender<Address>) -> ContractResult<()> {
+        let provider = create_simple_provider(&self.node_rpc_url).await?;
+        let signers = self
+            .signers
+            .lock()
+            .iter()
+            .map(|(address, slot)| (*address, slot.signer.clone()))
+            .collect::<Vec<_>>();
+
+        for (address, signer) in signers {
+            let balance = provider.get_balance(address).await;
+            let mined_count = provider.get_transaction_count(address).await;
+            let (Ok(balance), Ok(mined_count)) = (balance, mined_count) else {
+                warn!("Failed to read the state of signer {address}");
+                continue;
+            };
+
+            let low_balance = balance < self.recharge_threshold;
+            let availability = {
+                let mut signers = self.signers.lock();
+                let Some(slot) = signers.get_mut(&address) else {
+                    continue;
+                };
+                slot.low_balance = low_balance;
+                slot.nonces.sync(mined_count);
+                self.slot_availability(slot, Instant::now())
+            };
+            metrics::gauge!(
+                SIGNER_SIDELINED,
+                "chain_id" => self.chain_id.to_string(),
+                "address" => address.to_string()
+            )
+            .set((availability != Availability::Available) as u8 as f64);
+
+            if low_balance {
+                info!("Signer {address} has low balance ({balance}) - requesting recharge");
+                let _ = recharge_reporter.try_send(address);
+            }
+
+            while let Ok(Some(gap)) = self.with_nonces(address, |nonces| nonces.gaps.pop_first()) {
+                match fill_gap(&self.node_rpc_url, signer.clone(), gap).await {
+                    Ok(tx_hash) => {
+                        info!("Filled nonce gap {gap} of {address} with {tx_hash}");
+                        metrics::counter!(NONCE_GAP_FILLED).increment(1);
+                        self.submitted(address, gap);
+                    }
+                    Err(err) => {
+                        warn!("Failed to fill nonce gap {gap} of {address}: {err}");
+                        self.reset_nonces(address);
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Send an empty self-transfer with `nonce`.
+async fn fill_gap(
+    node_rpc_url: &str,
+    signer: PrivateKeySigner,
+    nonce: u64,
+) -> ContractResult<TxHash> {
+    let address = signer.address();
+    let provider = create_provider_with_signer(node_rpc_url, signer).await?;
+    let tx = TransactionRequest::default()
+        .with_from(address)
+        .with_to(address)
+        .with_value(U256::ZERO)
+        .with_nonce(nonce);
+    let pending = provider
+        .send_transaction(tx)
+        .await
+        .map_err(ShielderContractError::ProviderError)?;
+    Ok(*pending.tx_hash())
+}
+
+/// Periodically synchronize the pool with the chain.
+pub async fn signer_pool_worker(pool: SignerPool, recharge_reporter: MPSCSender<Address>) {
+    let mut interval = interval(REFRESH_INTERVAL);
+    loop {
+        interval.tick().await;
+        if let Err(err) = pool.refresh(&recharge_reporter).await {
+            error!("Failed to refresh signer pool: {err}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use alloy_signer_local::PrivateKeySigner;
+    use shielder_contract::alloy_primitives::U256;
+    use tokio::time::Instant;
+
+    use crate::signer_pool::{Availability, NonceTracker, SignerPool};
+
+    fn synced_tracker(next: u64) -> NonceTracker {
+        let mut tracker = NonceTracker::default();
+        tracker.init(next);
+        tracker
+    }
+
+    #[test]
+    fn nonces_are_read_from_node_only_once() {
+        let mut tracker = NonceTracker::default();
+        assert_eq!(tracker.acquire(), None);
+
+        tracker.init(5);
+        tracker.init(100);
+        assert_eq!(tracker.acquire(), Some(5));
+        assert_eq!(tracker.acquire(), Some(6));
+    }
+
+    #[test]
+    fn released_nonces_are_reused_before_new_ones() {
+        let mut tracker = synced_tracker(0);
+        let [a, b, c] = [0, 1, 2].map(|_| tracker.acquire().unwrap());
+
+        tracker.release(a);
+        tracker.release(c);
+        // `c` was the last one given out, so it is not a gap.
+        assert_eq!(tracker.gaps.iter().copied().collect::<Vec<_>>(), vec![a]);
+        assert_eq!(tracker.next, Some(c));
+
+        assert_eq!(tracker.acquire(), Some(a));
+        assert_eq!(tracker.acquire(), Some(c));
+        assert_eq!(tracker.acquire(), Some(b + 2));
+    }
+
+    #[test]
+    fn mined_nonces_are_forgotten() {
+        let mut tracker = synced_tracker(0);
+        for _ in 0..4 {
+            let nonce = tracker.acquire().unwrap();
+            tracker.submitted(nonce, Instant::now());
+        }
+        tracker.release(1);
+
+        tracker.sync(2);
+        assert_eq!(
+            tracker.pending.keys().copied().collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert!(tracker.gaps.is_empty());
+
+        // Someone else used the key.
+        tracker.sync(10);
+        assert_eq!(tracker.acquire(), Some(10));
+    }
+
+    #[test]
+    fn signer_with_old_pending_transaction_is_sidelined() {
+        let signer = PrivateKeySigner::random();
+        let address = signer.address();
+        let pool = SignerPool::new(
+            vec![signer],
+            "http://localhost:8545".into(),
+            1,
+            U256::ZERO,
+            Duration::from_secs(60),
+        );
+        assert_eq!(pool.availability(address), Availability::Available);
+
+        pool.with_nonces(address, |nonces| {
+            nonces.init(0);
+            nonces.submitted(0, Instant::now() - Duration::from_secs(61));
+        })
+        .unwrap();
+        assert_eq!(pool.availability(address), Availability::Stuck);
+
+        pool.finished(address, 0);
+        assert_eq!(pool.availability(address), Availability::Available);
+
+        assert!(pool.remove(address));
+        assert_eq!(pool.availability(address), Availability::Removed);
+    }
+}
@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 use shielder_account::Token;
 use utoipa::ToSchema;
 
+use crate::TokenFeePolicy;
+
 #[derive(Copy, Clone, Debug, Default, Hash, Eq, PartialEq, Deserialize, Serialize, ToSchema)]
 pub enum TokenKind {
     #[default]
@@ -38,14 +40,37 @@ impl Display for TokenKind {
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum PriceProvider {
+    /// DIA-compatible endpoint, returning `{"Price": ..., "Time": ...}`.
     Url(String),
     Static(Decimal),
+    /// CoinGecko-compatible `simple/price` endpoint. `url` should request USD prices (with
+    /// `include_last_updated_at=true`) and `id` is the key of the token in the response.
+    CoinGecko {
+        url: String,
+        id: String,
+    },
+    /// Chainlink aggregator contract, queried with `latestRoundData()`.
+    Chainlink {
+        rpc_url: String,
+        aggregator: Address,
+    },
+    /// Another Shielder relayer (base URL), queried for a fee quote in the token.
+    Relayer(String),
+    /// Median of several providers. At least `quorum` of them must respond with a price that
+    /// deviates from the median by no more than `max_deviation_percent`.
+    Median {
+        providers: Vec<PriceProvider>,
+        quorum: usize,
+        max_deviation_percent: u32,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub struct TokenInfo {
     pub kind: TokenKind,
     pub price_provider: PriceProvider,
+    #[serde(flatten)]
+    pub fee_policy: TokenFeePolicy,
 }
 
 impl TokenInfo {
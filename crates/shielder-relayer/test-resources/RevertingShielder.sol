@@ -3,6 +3,22 @@
 pragma solidity ^0.8.20;
 
 contract RevertingShielder {
+    // `MerkleTreeStorage` location of the real Shielder contract (ERC-7201).
+    uint256 private constant MERKLE_TREE_LOCATION =
+        0x8dc6e18c8cfce769481c51bf18d0c9f95b9f5dd0b4b4cafa4091d6a06254b700;
+
+    // Mark the zero Merkle root as known, so that the relayer pre-checks pass for the test requests.
+    constructor() {
+        bytes32 slot = keccak256(abi.encode(uint256(0), MERKLE_TREE_LOCATION + 5));
+        assembly {
+            sstore(slot, 1)
+        }
+    }
+
+    function nullifiers(uint256) public pure returns (uint256) {
+        return 0;
+    }
+
     function withdrawNative(
         bytes3 expectedContractVersion,
         uint256 amount,
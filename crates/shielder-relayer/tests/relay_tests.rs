@@ -3,6 +3,7 @@ use std::time::Duration;
 use parameterized::parameterized;
 use reqwest::StatusCode;
 use shielder_account::Token;
+use shielder_relayer::RelayJobStatus;
 use tokio::time::sleep;
 
 use crate::utils::{
@@ -21,7 +22,7 @@ async fn relay_query_without_quote_before_fails(token: Token) {
     ensure_response(
         response,
         StatusCode::BAD_REQUEST,
-        &String::from("Invalid quote (probably expired)"),
+        &String::from("Invalid quote: Quote signature is invalid"),
         &context,
     )
     .await;
@@ -56,6 +57,49 @@ async fn when_contract_reverts_server_screams_failure(token: Token) {
     .await;
 }
 
+#[parameterized(token = { Token::Native, Token::ERC20(ERC20_ADDRESS) })]
+#[parameterized_macro(tokio::test)]
+async fn async_relay_job_gets_mined(token: Token) {
+    let context = TestContext::default().await;
+
+    let job_id = context.relay_async_with_quote(token).await;
+    let job = context.await_relay_job(job_id).await;
+
+    ctx_assert_eq!(job.job_id, job_id, context);
+    ctx_assert_eq!(job.status, RelayJobStatus::Mined, context);
+    ctx_assert!(job.tx_hash.is_some(), context);
+    ctx_assert!(job.failure_reason.is_none(), context);
+}
+
+#[parameterized(token = { Token::Native, Token::ERC20(ERC20_ADDRESS) })]
+#[parameterized_macro(tokio::test)]
+async fn async_relay_job_fails_when_contract_reverts(token: Token) {
+    let config = TestConfig {
+        shielder_contract: ShielderContract::Reverting,
+        ..Default::default()
+    };
+    let context = TestContext::new(config).await;
+
+    let job_id = context.relay_async_with_quote(token).await;
+    let job = context.await_relay_job(job_id).await;
+
+    ctx_assert_eq!(job.status, RelayJobStatus::Failed, context);
+    ctx_assert!(
+        job.failure_reason
+            .is_some_and(|reason| reason.starts_with("Dry run failed")),
+        context
+    );
+}
+
+#[tokio::test]
+async fn relay_job_status_of_unknown_job_is_not_found() {
+    let context = TestContext::default().await;
+
+    let response = context.reach("relay/1000000").await;
+
+    ctx_assert_eq!(response.status(), StatusCode::NOT_FOUND, context);
+}
+
 #[tokio::test]
 async fn metrics_register_withdrawals() {
     let context = TestContext::default().await;
@@ -1,6 +1,6 @@
 #![allow(unused)]
 
-use std::{fmt::Debug, net::TcpListener, str::FromStr};
+use std::{fmt::Debug, net::TcpListener, str::FromStr, time::Duration};
 
 use alloy_primitives::{address, Address, Bytes, U256};
 use alloy_signer_local::PrivateKeySigner;
@@ -10,14 +10,15 @@ use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use shielder_account::Token;
 use shielder_relayer::{
-    PriceProvider, QuoteFeeQuery, QuoteFeeResponse, RelayCalldata, RelayQuery, RelayQuote,
-    TokenInfo, TokenKind,
+    PriceProvider, QuoteFeeQuery, QuoteFeeResponse, RelayCalldata, RelayJobInfo, RelayJobResponse,
+    RelayQuery, RelayQuote, TokenInfo, TokenKind,
 };
 use shielder_setup::version::contract_version;
 use testcontainers::{
     core::IntoContainerPort, runners::AsyncRunner, ContainerAsync, ContainerRequest, Image,
     ImageExt, TestcontainersError,
 };
+use tokio::time::sleep;
 
 use crate::{
     ctx_assert,
@@ -32,6 +33,11 @@ pub mod relayer_image;
 
 pub const ERC20_ADDRESS: Address = address!("2222222222222222222222222222222222222222");
 
+/// How often the status of a relay job is checked while waiting for it to finish.
+const JOB_POLLING_INTERVAL: Duration = Duration::from_millis(500);
+/// How many times the status of a relay job is checked before giving up.
+const JOB_POLLING_ATTEMPTS: u32 = 60;
+
 /// Dockerized testing environment.
 pub struct TestContext {
     /// The running container with the relayer service.
@@ -65,6 +71,7 @@ impl TestContext {
                 TokenInfo {
                     kind: TokenKind::Native,
                     price_provider: PriceProvider::Static(Decimal::ONE),
+                    fee_policy: Default::default(),
                 },
                 TokenInfo {
                     kind: TokenKind::ERC20 {
@@ -72,6 +79,7 @@ impl TestContext {
                         decimals: 18,
                     },
                     price_provider: PriceProvider::Static(Decimal::ONE),
+                    fee_policy: Default::default(),
                 },
             ],
             BALANCE_MONITOR_INTERVAL.to_string(),
@@ -111,8 +119,48 @@ impl TestContext {
     }
 
     pub async fn relay(&self, quote: RelayQuote, fee_token: Token) -> Response {
+        self.post_relay_query("relay", quote, fee_token).await
+    }
+
+    /// Relay with a fresh quote through `/relay/async` and return the identifier of the job.
+    pub async fn relay_async_with_quote(&self, fee_token: Token) -> u64 {
+        let quote = self.quote(fee_token).await;
+        let response = self.post_relay_query("relay/async", quote, fee_token).await;
+        ctx_assert!(response.status().is_success(), self);
+        response
+            .json::<RelayJobResponse>()
+            .await
+            .expect("Cannot parse relay job response")
+            .job_id
+    }
+
+    pub async fn relay_job(&self, job_id: u64) -> RelayJobInfo {
+        let response = self.reach(&format!("relay/{job_id}")).await;
+        ctx_assert!(response.status().is_success(), self);
+        response
+            .json::<RelayJobInfo>()
+            .await
+            .expect("Cannot parse relay job info")
+    }
+
+    /// Poll `/relay/{id}` until the job reaches a final status.
+    pub async fn await_relay_job(&self, job_id: u64) -> RelayJobInfo {
+        for _ in 0..JOB_POLLING_ATTEMPTS {
+            let job = self.relay_job(job_id).await;
+            if job.status.is_final() {
+                return job;
+            }
+            sleep(JOB_POLLING_INTERVAL).await;
+        }
+        panic!(
+            "Relay job {job_id} has not finished\n{}",
+            container_logs(&self.relayer_container).await
+        )
+    }
+
+    async fn post_relay_query(&self, path: &str, quote: RelayQuote, fee_token: Token) -> Response {
         reqwest::Client::new()
-            .post(format!("{BASE_URL}:{}/relay", self.relayer_port))
+            .post(format!("{BASE_URL}:{}/{path}", self.relayer_port))
             .json(&RelayQuery {
                 calldata: RelayCalldata {
                     expected_contract_version: contract_version().to_bytes(),
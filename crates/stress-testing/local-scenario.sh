@@ -25,7 +25,6 @@ run() {
     --node-rpc-url "${NODE_RPC_URL}" \
     --shielder "${SHIELDER_CONTRACT_ADDRESS}" \
     --relayer-url "${RELAYER_URL}" \
-    --relayer-address "${FEE_DESTINATION}" \
     --actor-count 10
 
   popd &>> output.log
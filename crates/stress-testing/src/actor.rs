@@ -1,81 +1,38 @@
+use std::sync::Arc;
+
 use alloy_signer_local::PrivateKeySigner;
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use shielder_account::{
-    call_data::{NewAccountCallExtra, NewAccountCallType},
-    ShielderAccount, Token,
-};
-use shielder_circuits::{
-    circuits::{Params, ProvingKey},
-    GrumpkinPointAffine,
-};
-use shielder_contract::{
-    alloy_primitives::{Address, Bytes, U256},
-    ConnectionPolicy,
-    ShielderContract::newAccountNativeCall,
-    ShielderUser,
-};
+use shielder_account::{ShielderAccount, Token};
+use shielder_client::{ClientConfig, ProvingKeys, ShielderClient};
+use shielder_contract::alloy_primitives::{Address, U256};
+
+use crate::config::Config;
 
 pub struct Actor {
     pub id: u32,
-    pub shielder_user: ShielderUser,
-    pub account: ShielderAccount,
+    pub client: ShielderClient,
 }
 
-pub const ANONYMITY_REVOKER_PKEY: GrumpkinPointAffine<U256> = GrumpkinPointAffine {
-    x: U256::from_limbs([1, 0, 0, 0]),
-    y: U256::from_limbs([
-        9457493854555940652,
-        3253583849847263892,
-        14921373847124204899,
-        2,
-    ]),
-};
-
 impl Actor {
-    pub fn new(id: u32, rpc_url: String, shielder: Address) -> Self {
+    pub fn new(id: u32, config: &Config, proving_keys: Arc<ProvingKeys>) -> Self {
         let mut rng = StdRng::from_seed(seed(id));
 
         let signer = PrivateKeySigner::random_with(&mut rng);
-        let shielder_user =
-            ShielderUser::new(shielder, ConnectionPolicy::OnDemand { rpc_url, signer });
-
         let account = ShielderAccount::new(U256::from(rng.gen::<u64>()), Token::Native);
+        let client_config = ClientConfig {
+            rpc_url: config.node_rpc_url.clone(),
+            contract_address: config.shielder,
+            relayer_url: Some(config.relayer_url.clone()),
+        };
 
         Self {
             id,
-            shielder_user,
-            account,
+            client: ShielderClient::new(client_config, signer, account, proving_keys),
         }
     }
 
     pub fn address(&self) -> Address {
-        self.shielder_user.address()
-    }
-
-    pub fn prepare_new_account_call(
-        &self,
-        params: &Params,
-        pk: &ProvingKey,
-        amount: U256,
-        protocol_fee: U256,
-    ) -> newAccountNativeCall {
-        self.account
-            .prepare_call::<NewAccountCallType>(
-                params,
-                pk,
-                Token::Native,
-                amount,
-                &NewAccountCallExtra {
-                    anonymity_revoker_public_key: ANONYMITY_REVOKER_PKEY,
-                    encryption_salt: U256::MAX,
-                    mac_salt: U256::ZERO,
-                    caller_address: self.shielder_user.address(),
-                    protocol_fee,
-                    memo: Bytes::from(vec![]),
-                },
-            )
-            .try_into()
-            .unwrap()
+        self.client.user().address()
     }
 }
 
@@ -18,9 +18,6 @@ pub struct Config {
 
     #[clap(long)]
     pub relayer_url: String,
-
-    #[clap(long, value_parser = parsing::parse_address)]
-    pub relayer_address: Address,
 }
 
 mod parsing {
@@ -1,26 +1,10 @@
 use std::time::Instant;
 
-use alloy_provider::Provider;
 use anyhow::Result;
-use shielder_account::{
-    call_data::{WithdrawCallType, WithdrawExtra},
-    Token,
-};
-use shielder_circuits::{
-    circuits::{Params, ProvingKey},
-    withdraw::WithdrawCircuit,
-};
-use shielder_contract::{
-    alloy_primitives::{Bytes, U256},
-    merkle_path::get_current_merkle_path,
-    protocol_fee::get_protocol_withdraw_fee_bps,
-    providers::create_simple_provider,
-    ShielderContract::withdrawNativeCall,
-};
-use shielder_relayer::{QuoteFeeQuery, QuoteFeeResponse, RelayCalldata, RelayQuery, RelayQuote};
-use shielder_setup::{protocol_fee::compute_protocol_fee_from_net, version::contract_version};
+use shielder_contract::alloy_primitives::{Bytes, U256};
+use shielder_relayer::RelayQuery;
 
-use crate::{actor::Actor, config::Config, util::proving_keys, WITHDRAW_AMOUNT};
+use crate::{actor::Actor, config::Config, WITHDRAW_AMOUNT};
 
 pub async fn enter_pandemonium(config: &Config, actors: Vec<Actor>) -> Result<()> {
     let task_inputs = prepare_relay_queries(config, actors).await?;
@@ -28,10 +12,10 @@ pub async fn enter_pandemonium(config: &Config, actors: Vec<Actor>) -> Result<()
 
     println!("🎉 Entering pandemonium! 🎉");
     let mut handles = vec![];
-    for (actor, query) in task_inputs {
+    for (actor_id, query) in task_inputs {
         let relayer = config.relayer_url.clone();
         handles.push(tokio::spawn(async move {
-            actor_task(actor, query, relayer).await
+            actor_task(actor_id, query, relayer).await
         }));
     }
 
@@ -52,8 +36,8 @@ pub async fn enter_pandemonium(config: &Config, actors: Vec<Actor>) -> Result<()
     Ok(())
 }
 
-async fn actor_task(actor: Actor, query: RelayQuery, relayer_rpc_url: String) -> Result<bool> {
-    println!("  🚀 Actor {} is starting the withdrawal...", actor.id);
+async fn actor_task(actor_id: u32, query: RelayQuery, relayer_rpc_url: String) -> Result<bool> {
+    println!("  🚀 Actor {actor_id} is starting the withdrawal...");
 
     let start = Instant::now();
     let status = reqwest::Client::new()
@@ -65,107 +49,35 @@ async fn actor_task(actor: Actor, query: RelayQuery, relayer_rpc_url: String) ->
     let elapsed = start.elapsed();
 
     if status.is_success() {
-        println!("  ✅ Actor {} succeeded! Latency: {elapsed:?}.", actor.id);
+        println!("  ✅ Actor {actor_id} succeeded! Latency: {elapsed:?}.");
         Ok(true)
     } else {
-        println!(
-            "  ❌ Actor {} failed: {status:?}. Latency: {elapsed:?}.",
-            actor.id
-        );
+        println!("  ❌ Actor {actor_id} failed: {status:?}. Latency: {elapsed:?}.");
         Ok(false)
     }
 }
 
+/// Prove the withdrawals of all the actors upfront, so that the relayer gets all the requests at
+/// once.
 async fn prepare_relay_queries(
     config: &Config,
     actors: Vec<Actor>,
-) -> Result<Vec<(Actor, RelayQuery)>> {
-    let (params, pk) = proving_keys::<WithdrawCircuit>();
+) -> Result<Vec<(u32, RelayQuery)>> {
     let mut result = Vec::new();
 
-    let quote = reqwest::Client::new()
-        .post(config.relayer_url.clone() + "/quote_fees")
-        .json(&QuoteFeeQuery {
-            fee_token: Token::Native,
-            pocket_money: U256::ZERO,
-        })
-        .send()
-        .await?
-        .json::<QuoteFeeResponse>()
-        .await?;
-
     println!("⏳ Preparing relay queries for actors...");
-    for actor in actors {
-        let query = prepare_relay_query(config, &actor, &params, &pk, quote.clone()).await?;
-        result.push((actor, query));
+    for mut actor in actors {
+        let query = actor
+            .client
+            .prepare_withdraw(
+                U256::from(WITHDRAW_AMOUNT),
+                config.master_seed.address(),
+                U256::ZERO,
+                Bytes::new(),
+            )
+            .await?;
+        println!("  ✅ Prepared relay query for actor {}", actor.id);
+        result.push((actor.id, query));
     }
     Ok(result)
 }
-
-async fn prepare_relay_query(
-    config: &Config,
-    actor: &Actor,
-    params: &Params,
-    pk: &ProvingKey,
-    quote: QuoteFeeResponse,
-) -> Result<RelayQuery> {
-    let (merkle_root, merkle_path) =
-        get_current_merkle_path(U256::from(actor.id), &actor.shielder_user).await?;
-    let to = config.master_seed.address();
-    let chain_id = create_simple_provider(&config.node_rpc_url)
-        .await?
-        .get_chain_id()
-        .await?;
-
-    let protocol_fee_bps = get_protocol_withdraw_fee_bps(&actor.shielder_user).await?;
-    let protocol_fee = compute_protocol_fee_from_net(U256::from(WITHDRAW_AMOUNT), protocol_fee_bps);
-    let amount = U256::from(WITHDRAW_AMOUNT) + protocol_fee + quote.fee_details.total_cost_native;
-
-    let calldata: withdrawNativeCall = actor
-        .account
-        .prepare_call::<WithdrawCallType>(
-            params,
-            pk,
-            Token::Native,
-            amount,
-            &WithdrawExtra {
-                merkle_path,
-                to,
-                relayer_address: config.relayer_address,
-                relayer_fee: quote.fee_details.total_cost_fee_token,
-                contract_version: contract_version(),
-                chain_id: U256::from(chain_id),
-                mac_salt: U256::ZERO,
-                pocket_money: U256::ZERO,
-                protocol_fee,
-                memo: Bytes::from(vec![]),
-            },
-        )
-        .try_into()
-        .unwrap();
-
-    let query = RelayQuery {
-        calldata: RelayCalldata {
-            expected_contract_version: contract_version().to_bytes(),
-            amount,
-            withdraw_address: to,
-            merkle_root,
-            nullifier_hash: calldata.oldNullifierHash,
-            new_note: calldata.newNote,
-            proof: calldata.proof,
-            fee_token: Token::Native,
-            fee_amount: calldata.relayerFee,
-            mac_salt: calldata.macSalt,
-            mac_commitment: calldata.macCommitment,
-            pocket_money: U256::ZERO,
-            memo: calldata.memo,
-        },
-        quote: RelayQuote {
-            gas_price: quote.price_details.gas_price,
-            native_token_unit_price: quote.price_details.native_token_unit_price,
-            fee_token_unit_price: quote.price_details.fee_token_unit_price,
-        },
-    };
-    println!("  ✅ Prepared relay query for actor {}", actor.id);
-    Ok(query)
-}
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use alloy_provider::{
     fillers::WalletFiller,
     network::{EthereumWallet, TransactionBuilder},
@@ -6,16 +8,7 @@ use alloy_provider::{
 use alloy_rpc_types::TransactionRequest;
 use alloy_signer_local::PrivateKeySigner;
 use anyhow::{anyhow, Result};
-use shielder_account::{ShielderAction, Token};
-use shielder_circuits::new_account::NewAccountCircuit;
-use shielder_contract::{
-    alloy_primitives::U256,
-    call_type::{Call, DryRun},
-    events::get_event,
-    providers::create_simple_provider,
-    ShielderContract::NewAccount,
-};
-use shielder_setup::protocol_fee::compute_protocol_fee_from_net;
+use shielder_contract::alloy_primitives::{Bytes, U256};
 
 use crate::{actor::Actor, config::Config, util::proving_keys, INITIAL_BALANCE, SHIELDED_BALANCE};
 
@@ -26,15 +19,16 @@ pub async fn setup_world(config: &Config) -> Result<Vec<Actor>> {
     distribute_tokens(config, &actors).await?;
     println!("✅ Distributed tokens to actors\n");
 
-    shield_tokens(config, &mut actors).await?;
+    shield_tokens(&mut actors).await?;
     println!("✅ Actors have opened shielder accounts\n");
 
     Ok(actors)
 }
 
 fn generate_actors(config: &Config) -> Vec<Actor> {
+    let proving_keys = Arc::new(proving_keys());
     (0..config.actor_count)
-        .map(|id| Actor::new(id, config.node_rpc_url.clone(), config.shielder))
+        .map(|id| Actor::new(id, config, proving_keys.clone()))
         .collect()
 }
 
@@ -66,44 +60,17 @@ async fn distribute_tokens(config: &Config, actors: &[Actor]) -> Result<()> {
     Ok(())
 }
 
-async fn shield_tokens(config: &Config, actors: &mut [Actor]) -> Result<()> {
-    let (params, pk) = proving_keys::<NewAccountCircuit>();
+async fn shield_tokens(actors: &mut [Actor]) -> Result<()> {
     let shielded_amount = U256::from(SHIELDED_BALANCE);
-    let provider = create_simple_provider(&config.node_rpc_url).await?;
-
-    let protocol_fee = if let Some(actor) = actors.first() {
-        let protocol_fee_bps = actor
-            .shielder_user
-            .protocol_deposit_fee_bps::<DryRun>()
-            .await?;
-        compute_protocol_fee_from_net(shielded_amount, protocol_fee_bps)
-    } else {
-        U256::ZERO
-    };
-    let total_amount = shielded_amount + protocol_fee;
 
     println!(
-        "⏳ Creating shielder accounts. Every account will shield {}, additionally paying {} protocol fee.",
-        shielded_amount, protocol_fee
+        "⏳ Creating shielder accounts. Every account will shield {shielded_amount}, additionally paying the protocol fee."
     );
     for actor in actors {
-        let call = actor.prepare_new_account_call(&params, &pk, total_amount, protocol_fee);
-
-        let (tx_hash, block_hash) = actor
-            .shielder_user
-            .new_account_native::<Call>(call, total_amount)
+        actor
+            .client
+            .new_account(shielded_amount, Bytes::new())
             .await?;
-
-        let new_account_event = get_event::<NewAccount>(&provider, tx_hash, block_hash).await?;
-
-        actor.account.register_action(ShielderAction::new_account(
-            total_amount,
-            new_account_event.newNoteIndex,
-            tx_hash,
-            Token::Native,
-            protocol_fee,
-        ));
-
         println!("  ✅ Shielded tokens for address {}", actor.address());
     }
     Ok(())
@@ -1,15 +1,24 @@
 use powers_of_tau::{get_ptau_file_path, read as read_setup_parameters, Format};
 use shielder_circuits::{
-    circuits::{Params, ProvingKey},
-    generate_keys_with_min_k, Circuit, Fr, MAX_K,
+    deposit::DepositCircuit, generate_keys_with_min_k, new_account::NewAccountCircuit,
+    withdraw::WithdrawCircuit, Circuit, Fr, MAX_K,
 };
+use shielder_client::{ProvingEquipment, ProvingKeys};
 
-pub fn proving_keys<C: Circuit<Fr> + Default>() -> (Params, ProvingKey) {
+pub fn proving_keys() -> ProvingKeys {
+    ProvingKeys {
+        new_account: proving_equipment::<NewAccountCircuit>(),
+        deposit: proving_equipment::<DepositCircuit>(),
+        withdraw: proving_equipment::<WithdrawCircuit>(),
+    }
+}
+
+fn proving_equipment<C: Circuit<Fr> + Default>() -> ProvingEquipment {
     let params = read_setup_parameters(
         get_ptau_file_path(MAX_K, Format::PerpetualPowersOfTau),
         Format::PerpetualPowersOfTau,
     )
     .unwrap();
     let (params, _, pk, _) = generate_keys_with_min_k(C::default(), params).unwrap();
-    (params, pk)
+    ProvingEquipment { params, pk }
 }
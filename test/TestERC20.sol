@@ -2,6 +2,7 @@
 pragma solidity 0.8.26;
 
 import { ERC20 } from "@openzeppelin/contracts/token/ERC20/ERC20.sol";
+import { ERC20Permit } from "@openzeppelin/contracts/token/ERC20/extensions/ERC20Permit.sol";
 
 contract TestERC20 is ERC20 {
     constructor(
@@ -15,13 +16,13 @@ contract TestERC20 is ERC20 {
 }
 
 /**
- * Customized `TestERC20` used in `integration-tests`. Reverts transfers
- * to 0xffffffffffffffffffffffffffffffffffffffff.
+ * Customized `TestERC20` used in `integration-tests`. Supports EIP-2612 permits
+ * and reverts transfers to 0xffffffffffffffffffffffffffffffffffffffff.
  */
-contract RevmTestERC20 is TestERC20 {
+contract RevmTestERC20 is TestERC20, ERC20Permit {
     error DestinationTriggeredRevert();
 
-    constructor() TestERC20("TestERC20", "TERC20") {
+    constructor() TestERC20("TestERC20", "TERC20") ERC20Permit("TestERC20") {
         _mint(msg.sender, type(uint256).max);
     }
 
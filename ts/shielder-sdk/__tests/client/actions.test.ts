@@ -314,6 +314,11 @@ describe("ShielderActions", () => {
           fee_token_price: "1",
           fee_token_unit_price: "1",
           token_price_ratio: "1"
+        },
+        quote_signature: {
+          expires_at: 0,
+          nonce: "0x00",
+          signature: "0x00"
         }
       };
 
@@ -20,7 +20,9 @@ const quoteFeesResponseSchema = z.object({
     pocket_money_native: z.coerce.bigint(),
     pocket_money_fee_token: z.coerce.bigint(),
     commission_native: z.coerce.bigint(),
-    commission_fee_token: z.coerce.bigint()
+    commission_fee_token: z.coerce.bigint(),
+    // Missing in responses of older relayers.
+    relay_gas: z.number().optional()
   }),
   price_details: z.object({
     gas_price: z.coerce.bigint(),
@@ -29,6 +31,11 @@ const quoteFeesResponseSchema = z.object({
     fee_token_price: z.coerce.string(),
     fee_token_unit_price: z.coerce.string(),
     token_price_ratio: z.coerce.string()
+  }),
+  quote_signature: z.object({
+    expires_at: z.number(),
+    nonce: z.string(),
+    signature: z.string()
   })
 });
 
@@ -47,7 +54,8 @@ export const quotedFeesFromExpectedTokenFee = (totalCostFeeToken: bigint) => {
       pocket_money_native: 0n,
       pocket_money_fee_token: 0n,
       commission_native: 0n,
-      commission_fee_token: 0n
+      commission_fee_token: 0n,
+      relay_gas: 0
     },
     price_details: {
       gas_price: 0n,
@@ -56,6 +64,11 @@ export const quotedFeesFromExpectedTokenFee = (totalCostFeeToken: bigint) => {
       fee_token_price: "1",
       fee_token_unit_price: "1",
       token_price_ratio: "1"
+    },
+    quote_signature: {
+      expires_at: 0,
+      nonce: `0x${"0".repeat(64)}`,
+      signature: `0x${"0".repeat(64)}`
     }
   };
 };
@@ -140,7 +153,10 @@ export class Relayer implements IRelayer {
               native_token_unit_price:
                 quotedFees.price_details.native_token_unit_price,
               fee_token_unit_price:
-                quotedFees.price_details.fee_token_unit_price
+                quotedFees.price_details.fee_token_unit_price,
+              relay_gas: quotedFees.fee_details.relay_gas,
+              total_fee: quotedFees.fee_details.total_cost_fee_token,
+              signature: quotedFees.quote_signature
             }
           },
           (_, value: unknown) =>